@@ -6,83 +6,104 @@ extern crate memmap;
 extern crate rand;
 
 use powersoftau_core::batched_accumulator::BatchedAccumulator;
-use powersoftau_core::keypair::keypair;
-use powersoftau_core::parameters::{CheckForCorrectness, UseCompression};
-use powersoftau_core::small_bls12_381::Bls12CeremonyParameters;
+use powersoftau_core::beacon::{beacon_rng, BeaconRecord};
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::keypair::keypair_for_mode;
+use powersoftau_core::parameters::{CheckForCorrectness, TranscriptFile, UseCompression};
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
 
 use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
 use memmap::*;
 use std::fs::OpenOptions;
 
-use hex_literal::hex;
 use std::io::Write;
 
-use powersoftau_core::parameters::PowersOfTauParameters;
-
-const INPUT_IS_COMPRESSED: UseCompression = UseCompression::No;
 const COMPRESS_THE_OUTPUT: UseCompression = UseCompression::Yes;
 const CHECK_INPUT_CORRECTNESS: CheckForCorrectness = CheckForCorrectness::No;
 
 fn main() {
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(std::env::args().skip(1))
+        .expect("Failed to load ceremony configuration");
+
+    // The challenge tells the power of tau and the encoding of its points
+    let challenge = detect_layout("challenge", params.curve())
+        .expect("unable to recognize `./challenge` in this directory");
+    challenge
+        .ensure_role(TranscriptFile::Challenge)
+        .expect("`./challenge` is not a challenge");
+    let params = challenge.parameters(&params);
+
+    params.print_info();
+    println!("`./challenge` is {}", challenge);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => beacon_contribution::<Bls12>(&params, &challenge),
+        CurveKind::Bn254 => beacon_contribution::<Bn256>(&params, &challenge),
+    }
+}
+
+fn beacon_contribution<E: Engine>(params: &RuntimeCeremonyParameters, challenge: &FileLayout) {
+    let mut progress = ConsoleProgress::new();
+
     println!(
         "Will contribute a random beacon to accumulator for 2^{} powers of tau",
-        Bls12CeremonyParameters::REQUIRED_POWER
+        params.required_power()
     );
     println!(
         "In total will generate up to {} powers",
-        Bls12CeremonyParameters::TAU_POWERS_G1_LENGTH
+        params.tau_powers_g1_length()
     );
 
+    // Create `./response` in this directory before the beacon is iterated, so that an existing
+    // response and its beacon record are left alone
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open("response")
+        .expect("unable to create `./response` in this directory");
+
     // Create an RNG based on the outcome of the random beacon
     let mut rng = {
-        use byteorder::{BigEndian, ReadBytesExt};
-        use crypto::digest::Digest;
-        use crypto::sha2::Sha256;
-        use rand::chacha::ChaChaRng;
-        use rand::SeedableRng;
-
-        // Place the block hash here (block number #564321)
-        let mut cur_hash: [u8; 32] =
-            hex!("0000000000000000000a558a61ddc8ee4e488d647a747fe4dcc362fe2026c620");
-
-        // Performs 2^n hash iterations over it
-        // TODO: add a parameter?
-        const N: usize = 10;
-
-        for i in 0..(1u64 << N) {
-            // Print 1024 of the interstitial states
-            // so that verification can be
-            // parallelized
-
-            if i % (1u64 << (N - 10)) == 0 {
+        let (beacon, provenance) = params
+            .beacon()
+            .resolve()
+            .expect("unable to take the beacon from its source");
+        println!("Beacon: {}", beacon);
+        println!("Taken from {}", provenance);
+
+        // Performs 2^n hash iterations over the beacon value, and prints 1024 of the
+        // interstitial states so that verification can be parallelized
+        let mut checkpoints = vec![];
+        let result = beacon
+            .iterate(&mut |i, hash| {
+                checkpoints.push(*hash);
                 print!("{}: ", i);
-                for b in cur_hash.iter() {
+                for b in hash.iter() {
                     print!("{:02x}", b);
                 }
                 println!("");
-            }
-
-            let mut h = Sha256::new();
-            h.input(&cur_hash);
-            h.result(&mut cur_hash);
-        }
+            })
+            .expect("unusable beacon");
 
         print!("Final result of beacon: ");
-        for b in cur_hash.iter() {
+        for b in result.iter() {
             print!("{:02x}", b);
         }
         println!("");
 
-        let mut digest = &cur_hash[..];
+        // Record the beacon, where it came from and its checkpoints next to the response, so
+        // that anyone can derive it again, in parallel
+        BeaconRecord::new(&beacon, &provenance, &checkpoints, &result)
+            .save(BeaconRecord::path_for("response"))
+            .expect("unable to write `./response.beacon`");
 
-        let mut seed = [0u32; 8];
-        for i in 0..8 {
-            seed[i] = digest
-                .read_u32::<BigEndian>()
-                .expect("digest is large enough for this to work");
-        }
-
-        ChaChaRng::from_seed(&seed)
+        beacon_rng(&result)
     };
 
     println!("Done creating a beacon RNG");
@@ -93,52 +114,40 @@ fn main() {
         .open("challenge")
         .expect("unable open `./challenge` in this directory");
 
-    {
-        let metadata = reader
-            .metadata()
-            .expect("unable to get filesystem metadata for `./challenge`");
-        let expected_challenge_length = match INPUT_IS_COMPRESSED {
-            UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-            UseCompression::No => Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE,
-        };
-
-        if metadata.len() != (expected_challenge_length as u64) {
-            panic!(
-                "The size of `./challenge` should be {}, but it's {}, so something isn't right.",
-                expected_challenge_length,
-                metadata.len()
-            );
-        }
-    }
-
     let readable_map = unsafe {
         MmapOptions::new()
+            .offset(challenge.body_offset())
             .map(&reader)
             .expect("unable to create a memory map for input")
     };
 
-    // Create `./response` in this directory
-    let writer = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create_new(true)
-        .open("response")
-        .expect("unable to create `./response` in this directory");
-
-    let required_output_length = match COMPRESS_THE_OUTPUT {
-        UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-        UseCompression::No => {
-            Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE
-                + Bls12CeremonyParameters::PUBLIC_KEY_SIZE
-        }
+    let required_output_length =
+        params.file_byte_size(TranscriptFile::Response, COMPRESS_THE_OUTPUT);
+
+    let response_header = match params.file_header() {
+        true => Some(FileHeader::next(
+            challenge.header.as_ref(),
+            TranscriptFile::Response,
+            COMPRESS_THE_OUTPUT,
+            params,
+        )),
+        false => None,
     };
+    let response_offset = body_offset(response_header.as_ref());
 
     writer
-        .set_len(required_output_length as u64)
+        .set_len(response_offset + required_output_length as u64)
         .expect("must make output file large enough");
 
+    if let Some(header) = response_header {
+        header
+            .write(&writer)
+            .expect("unable to write the header of `./response`");
+    }
+
     let mut writable_map = unsafe {
         MmapOptions::new()
+            .offset(response_offset)
             .map_mut(&writer)
             .expect("unable to create a memory map for output")
     };
@@ -146,7 +155,7 @@ fn main() {
     println!("Calculating previous contribution hash...");
 
     let current_accumulator_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(&readable_map);
+        BatchedAccumulator::<E>::calculate_hash(&readable_map, &mut progress);
 
     {
         println!("Contributing on top of the hash:");
@@ -171,26 +180,30 @@ fn main() {
     }
 
     // Construct our keypair using the RNG we created above
-    let (pubkey, privkey) = keypair(&mut rng, current_accumulator_hash.as_ref());
+    let (pubkey, privkey) =
+        keypair_for_mode(&mut rng, current_accumulator_hash.as_ref(), params.mode())
+            .expect("unable to construct a keypair");
 
     // Perform the transformation
     println!("Computing and writing your contribution, this could take a while...");
 
     // this computes a transformation and writes it
-    BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::transform(
+    BatchedAccumulator::<E>::transform(
         &readable_map,
         &mut writable_map,
-        INPUT_IS_COMPRESSED,
+        challenge.compression,
         COMPRESS_THE_OUTPUT,
         CHECK_INPUT_CORRECTNESS,
         &privkey,
+        params,
+        &mut progress,
     )
     .expect("must transform with the key");
     println!("Finihsing writing your contribution to `./response`...");
 
     // Write the public key
     pubkey
-        .write::<Bls12CeremonyParameters>(&mut writable_map, COMPRESS_THE_OUTPUT)
+        .write(&mut writable_map, COMPRESS_THE_OUTPUT, params)
         .expect("unable to write public key");
 
     // Get the hash of the contribution, so the user can compare later
@@ -198,7 +211,7 @@ fn main() {
         .make_read_only()
         .expect("must make a map readonly");
     let contribution_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(&output_readonly);
+        BatchedAccumulator::<E>::calculate_hash(&output_readonly, &mut progress);
 
     print!(
         "Done!\n\n\
@@ -5,144 +5,103 @@ extern crate memmap;
 extern crate rand;
 
 use powersoftau_core::batched_accumulator::BatchedAccumulator;
-use powersoftau_core::keypair::keypair;
-use powersoftau_core::parameters::{CheckForCorrectness, UseCompression};
-use powersoftau_core::small_bls12_381::Bls12CeremonyParameters;
+use powersoftau_core::checkpoint::{
+    escrow_private_key, public_key_hash, recover_private_key, ContributionCheckpoint,
+};
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::keypair::{keypair_for_mode, PrivateKey, PublicKey};
+use powersoftau_core::parameters::{
+    CeremonyError, CheckForCorrectness, TranscriptFile, UseCompression,
+};
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
 
 use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
 use memmap::*;
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 use std::io::{Read, Write};
 
 use generic_array::GenericArray;
 use typenum::U64;
 
-use powersoftau_core::parameters::PowersOfTauParameters;
-
-const INPUT_IS_COMPRESSED: UseCompression = UseCompression::No;
 const COMPRESS_THE_OUTPUT: UseCompression = UseCompression::Yes;
 const CHECK_INPUT_CORRECTNESS: CheckForCorrectness = CheckForCorrectness::No;
 
+const RESPONSE: &str = "response";
+const KEY_ESCROW: &str = "response.key";
+
+/// How many times an IO error is retried before giving up on the contribution
+const IO_RETRIES: usize = 5;
+const IO_RETRY_DELAY: Duration = Duration::from_secs(30);
+
 fn main() {
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(std::env::args().skip(1))
+        .expect("Failed to load ceremony configuration");
+
+    // The challenge tells the power of tau and the encoding of its points
+    let challenge = detect_layout("challenge", params.curve())
+        .expect("unable to recognize `./challenge` in this directory");
+    challenge
+        .ensure_role(TranscriptFile::Challenge)
+        .expect("`./challenge` is not a challenge");
+    let params = challenge.parameters(&params);
+
+    params.print_info();
+    println!("`./challenge` is {}", challenge);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => compute_contribution::<Bls12>(&params, &challenge),
+        CurveKind::Bn254 => compute_contribution::<Bn256>(&params, &challenge),
+    }
+}
+
+fn compute_contribution<E: Engine>(params: &RuntimeCeremonyParameters, challenge: &FileLayout) {
+    let mut progress = ConsoleProgress::new();
+
     println!(
         "Will contribute to accumulator for 2^{} powers of tau",
-        Bls12CeremonyParameters::REQUIRED_POWER
+        params.required_power()
     );
     println!(
         "In total will generate up to {} powers",
-        Bls12CeremonyParameters::TAU_POWERS_G1_LENGTH
+        params.tau_powers_g1_length()
     );
 
-    // Create an RNG based on a mixture of system randomness and user-provided randomness
-    let mut rng = {
-        use blake2::{Blake2b, Digest};
-        use byteorder::{BigEndian, ReadBytesExt};
-        use rand::chacha::ChaChaRng;
-        use rand::{OsRng, Rng, SeedableRng};
-
-        let h: GenericArray<u8, U64> = {
-            let mut system_rng = OsRng::new().unwrap();
-            let mut h = Blake2b::default();
-
-            // Gather 1024 bytes of entropy from the system
-            for _ in 0..1024 {
-                let r: u8 = system_rng.gen();
-                Digest::update(&mut h, &[r]);
-            }
-
-            // Ask the user to provide some information for additional entropy
-            let mut user_input = String::new();
-            println!("Type some random text and press [ENTER] to provide additional entropy...");
-            std::io::stdin()
-                .read_line(&mut user_input)
-                .expect("expected to read some random text from the user");
-
-            // Hash it all up to make a seed
-            Digest::update(&mut h, &user_input.as_bytes());
-            h.finalize().into()
-        };
-
-        let mut digest = &h[..];
-
-        // Interpret the first 32 bytes of the digest as 8 32-bit words
-        let mut seed = [0u32; 8];
-        for i in 0..8 {
-            seed[i] = digest
-                .read_u32::<BigEndian>()
-                .expect("digest is large enough for this to work");
-        }
-
-        ChaChaRng::from_seed(&seed)
-    };
-
     // Try to load `./challenge` from disk.
     let reader = OpenOptions::new()
         .read(true)
         .open("challenge")
         .expect("unable open `./challenge` in this directory");
 
-    {
-        let metadata = reader
-            .metadata()
-            .expect("unable to get filesystem metadata for `./challenge`");
-        let expected_challenge_length = match INPUT_IS_COMPRESSED {
-            UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-            UseCompression::No => Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE,
-        };
-
-        if metadata.len() != (expected_challenge_length as u64) {
-            panic!(
-                "The size of `./challenge` should be {}, but it's {}, so something isn't right.",
-                expected_challenge_length,
-                metadata.len()
-            );
-        }
-    }
-
     let readable_map = unsafe {
         MmapOptions::new()
+            .offset(challenge.body_offset())
             .map(&reader)
             .expect("unable to create a memory map for input")
     };
 
-    // Create `./response` in this directory
-    let writer = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create_new(true)
-        .open("response")
-        .expect("unable to create `./response` in this directory");
-
-    let required_output_length = match COMPRESS_THE_OUTPUT {
-        UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-        UseCompression::No => {
-            Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE
-                + Bls12CeremonyParameters::PUBLIC_KEY_SIZE
-        }
-    };
-
-    writer
-        .set_len(required_output_length as u64)
-        .expect("must make output file large enough");
-
-    let mut writable_map = unsafe {
-        MmapOptions::new()
-            .map_mut(&writer)
-            .expect("unable to create a memory map for output")
-    };
-
     println!("Calculating previous contribution hash...");
 
-    assert!(
-        UseCompression::No == INPUT_IS_COMPRESSED,
-        "Hashing the compressed file in not yet defined"
-    );
     let current_accumulator_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(&readable_map);
+        BatchedAccumulator::<E>::calculate_hash(&readable_map, &mut progress);
 
     {
-        println!("`challenge` file contains decompressed points and has a hash:");
+        match challenge.compression {
+            UseCompression::Yes => {
+                println!("`challenge` file contains compressed points and has a hash:")
+            }
+            UseCompression::No => {
+                println!("`challenge` file contains decompressed points and has a hash:")
+            }
+        }
         for line in current_accumulator_hash.as_slice().chunks(16) {
             print!("\t");
             for section in line.chunks(4) {
@@ -153,14 +112,6 @@ fn main() {
             }
             println!("");
         }
-
-        (&mut writable_map[0..])
-            .write(current_accumulator_hash.as_slice())
-            .expect("unable to write a challenge hash to mmap");
-
-        writable_map
-            .flush()
-            .expect("unable to write hash to `./response`");
     }
 
     {
@@ -185,38 +136,96 @@ fn main() {
         }
     }
 
-    // Construct our keypair using the RNG we created above
-    let (pubkey, privkey) = keypair(&mut rng, current_accumulator_hash.as_ref());
+    let checkpoint_path = ContributionCheckpoint::path_for(RESPONSE);
+    let previous_checkpoint = ContributionCheckpoint::load(&checkpoint_path)
+        .expect("unable to read the checkpoint of `./response`");
+
+    let (mut writable_map, privkey, mut checkpoint) = match previous_checkpoint {
+        Some(previous) if Path::new(KEY_ESCROW).exists() => {
+            resume_contribution::<E>(previous, current_accumulator_hash.as_slice(), params)
+        }
+        Some(_) => {
+            println!(
+                "`./response` was left unfinished by an interrupted contribution. Its private key \
+                 only existed in the memory of that process, so the contribution starts over."
+            );
+            fs::remove_file(RESPONSE).expect("unable to remove the unfinished `./response`");
+            fs::remove_file(&checkpoint_path).expect("unable to remove the stale checkpoint");
+            start_contribution::<E>(
+                current_accumulator_hash.as_slice(),
+                challenge.header.as_ref(),
+                params,
+            )
+        }
+        None => start_contribution::<E>(
+            current_accumulator_hash.as_slice(),
+            challenge.header.as_ref(),
+            params,
+        ),
+    };
 
     // Perform the transformation
     println!("Computing and writing your contribution, this could take a while...");
 
-    // this computes a transformation and writes it
-    BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::transform(
-        &readable_map,
-        &mut writable_map,
-        INPUT_IS_COMPRESSED,
-        COMPRESS_THE_OUTPUT,
-        CHECK_INPUT_CORRECTNESS,
-        &privkey,
-    )
-    .expect("must transform with the key");
+    // The key only lives in this process, so IO errors are retried from the last checkpoint
+    // instead of giving up on the work done so far
+    let mut retries = 0;
+    loop {
+        let resume_from = checkpoint.completed_powers;
+        if resume_from > 0 {
+            println!("Resuming from power {}", resume_from);
+        }
+
+        let result = BatchedAccumulator::<E>::resume_transform(
+            &readable_map,
+            &mut writable_map,
+            challenge.compression,
+            COMPRESS_THE_OUTPUT,
+            CHECK_INPUT_CORRECTNESS,
+            &privkey,
+            params,
+            &mut progress,
+            resume_from,
+            &mut |completed_powers| {
+                checkpoint.completed_powers = completed_powers;
+                checkpoint.save(&checkpoint_path)
+            },
+        );
+
+        match result {
+            Ok(()) => break,
+            Err(CeremonyError::Io(e)) if retries < IO_RETRIES => {
+                retries += 1;
+                println!(
+                    "IO error: {}. Retrying in {} seconds ({}/{})...",
+                    e,
+                    IO_RETRY_DELAY.as_secs(),
+                    retries,
+                    IO_RETRIES
+                );
+                thread::sleep(IO_RETRY_DELAY);
+            }
+            Err(e) => panic!("must transform with the key: {}", e),
+        }
+    }
 
     println!("Finihsing writing your contribution to `./response`...");
 
-    // Write the public key
-    pubkey
-        .write::<Bls12CeremonyParameters>(&mut writable_map, COMPRESS_THE_OUTPUT)
-        .expect("unable to write public key");
-
     writable_map.flush().expect("must flush a memory map");
 
+    // The contribution is complete, nothing is left to resume
+    fs::remove_file(&checkpoint_path).expect("unable to remove the checkpoint");
+    if Path::new(KEY_ESCROW).exists() {
+        fs::remove_file(KEY_ESCROW).expect("unable to remove `./response.key`");
+        println!("Removed the escrowed private key `./response.key`");
+    }
+
     // Get the hash of the contribution, so the user can compare later
     let output_readonly = writable_map
         .make_read_only()
         .expect("must make a map readonly");
     let contribution_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(&output_readonly);
+        BatchedAccumulator::<E>::calculate_hash(&output_readonly, &mut progress);
 
     print!(
         "Done!\n\n\
@@ -237,3 +246,218 @@ fn main() {
 
     println!("Thank you for your participation, much appreciated! :)");
 }
+
+/// Creates `./response` with a fresh key, and records a checkpoint for it before any work is done
+fn start_contribution<E: Engine>(
+    challenge_hash: &[u8],
+    challenge_header: Option<&FileHeader>,
+    params: &RuntimeCeremonyParameters,
+) -> (MmapMut, PrivateKey<E>, ContributionCheckpoint) {
+    // The passphrase is settled before anything is written, so that a typo leaves no files behind
+    let passphrase = match params.key_escrow() {
+        true => {
+            println!(
+                "WARNING: key escrow is enabled. Your private key will be kept in `./response.key`, \
+                 encrypted with a passphrase, until the contribution is complete. Anyone who gets \
+                 hold of that file and the passphrase can undo your contribution."
+            );
+            Some(prompt_new_passphrase())
+        }
+        false => None,
+    };
+
+    let mut rng = user_rng();
+
+    // Create `./response` in this directory
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(RESPONSE)
+        .expect("unable to create `./response` in this directory");
+
+    let response_header = match params.file_header() {
+        true => Some(FileHeader::next(
+            challenge_header,
+            TranscriptFile::Response,
+            COMPRESS_THE_OUTPUT,
+            params,
+        )),
+        false => None,
+    };
+    let response_offset = body_offset(response_header.as_ref());
+
+    writer
+        .set_len(
+            response_offset
+                + params.file_byte_size(TranscriptFile::Response, COMPRESS_THE_OUTPUT) as u64,
+        )
+        .expect("must make output file large enough");
+
+    if let Some(header) = response_header {
+        header
+            .write(&writer)
+            .expect("unable to write the header of `./response`");
+    }
+
+    let mut writable_map = unsafe {
+        MmapOptions::new()
+            .offset(response_offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    (&mut writable_map[0..])
+        .write(challenge_hash)
+        .expect("unable to write a challenge hash to mmap");
+
+    // Construct our keypair using the RNG we created above
+    let (pubkey, privkey) = keypair_for_mode(&mut rng, challenge_hash, params.mode())
+        .expect("unable to construct a keypair");
+
+    // The public key goes in first, so that a resumed run can tell which key the response belongs to
+    pubkey
+        .write(&mut writable_map, COMPRESS_THE_OUTPUT, params)
+        .expect("unable to write public key");
+
+    writable_map
+        .flush()
+        .expect("unable to write hash to `./response`");
+
+    let checkpoint =
+        ContributionCheckpoint::new(challenge_hash, &pubkey, COMPRESS_THE_OUTPUT, params)
+            .expect("unable to create a checkpoint");
+    checkpoint
+        .save(ContributionCheckpoint::path_for(RESPONSE))
+        .expect("unable to write the checkpoint of `./response`");
+
+    if let Some(passphrase) = passphrase {
+        let key_hash = public_key_hash(&pubkey).expect("unable to hash the public key");
+        escrow_private_key(&privkey, &key_hash, &passphrase, KEY_ESCROW)
+            .expect("unable to escrow the private key");
+    }
+
+    (writable_map, privkey, checkpoint)
+}
+
+/// Asks for the passphrase of a new key escrow twice, until both match
+fn prompt_new_passphrase() -> String {
+    loop {
+        let passphrase = rpassword::prompt_password("Passphrase for the key escrow: ")
+            .expect("unable to read the passphrase");
+        let confirmation = rpassword::prompt_password("Repeat the passphrase: ")
+            .expect("unable to read the passphrase");
+        if passphrase == confirmation {
+            return passphrase;
+        }
+        println!("The passphrases don't match, please try again.");
+    }
+}
+
+/// Reopens an unfinished `./response`, recovering its private key from the key escrow
+fn resume_contribution<E: Engine>(
+    checkpoint: ContributionCheckpoint,
+    challenge_hash: &[u8],
+    params: &RuntimeCeremonyParameters,
+) -> (MmapMut, PrivateKey<E>, ContributionCheckpoint) {
+    println!(
+        "Found an unfinished `./response` with {} powers done, resuming it",
+        checkpoint.completed_powers
+    );
+
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(RESPONSE)
+        .expect("unable to open the unfinished `./response`");
+
+    let response = detect_layout(RESPONSE, params.curve())
+        .expect("unable to recognize the unfinished `./response`");
+    if response.role != TranscriptFile::Response
+        || response.compression != COMPRESS_THE_OUTPUT
+        || response.required_power != params.required_power()
+    {
+        panic!(
+            "The unfinished `./response` is {}, so something isn't right.",
+            response
+        );
+    }
+    let response_offset = response.body_offset();
+
+    let pubkey = {
+        let readable_map = unsafe {
+            MmapOptions::new()
+                .offset(response_offset)
+                .map(&writer)
+                .expect("unable to create a memory map for output")
+        };
+        PublicKey::<E>::read(&readable_map, COMPRESS_THE_OUTPUT, params)
+            .expect("unable to read the public key of the unfinished `./response`")
+    };
+
+    let expected =
+        ContributionCheckpoint::new(challenge_hash, &pubkey, COMPRESS_THE_OUTPUT, params)
+            .expect("unable to create a checkpoint");
+    expected
+        .ensure_same_contribution(&checkpoint)
+        .expect("the unfinished `./response` can't be resumed");
+
+    let passphrase = rpassword::prompt_password("Passphrase of the key escrow `./response.key`: ")
+        .expect("unable to read the passphrase");
+    let key_hash = public_key_hash(&pubkey).expect("unable to hash the public key");
+    let privkey = recover_private_key::<E, _>(&key_hash, &passphrase, KEY_ESCROW)
+        .expect("unable to recover the escrowed private key");
+
+    let writable_map = unsafe {
+        MmapOptions::new()
+            .offset(response_offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    (writable_map, privkey, checkpoint)
+}
+
+/// Creates an RNG based on a mixture of system randomness and user-provided randomness
+fn user_rng() -> impl rand::Rng {
+    {
+        use blake2::{Blake2b, Digest};
+        use byteorder::{BigEndian, ReadBytesExt};
+        use rand::chacha::ChaChaRng;
+        use rand::{OsRng, Rng, SeedableRng};
+
+        let h: GenericArray<u8, U64> = {
+            let mut system_rng = OsRng::new().unwrap();
+            let mut h = Blake2b::default();
+
+            // Gather 1024 bytes of entropy from the system
+            for _ in 0..1024 {
+                let r: u8 = system_rng.gen();
+                Digest::update(&mut h, &[r]);
+            }
+
+            // Ask the user to provide some information for additional entropy
+            let mut user_input = String::new();
+            println!("Type some random text and press [ENTER] to provide additional entropy...");
+            std::io::stdin()
+                .read_line(&mut user_input)
+                .expect("expected to read some random text from the user");
+
+            // Hash it all up to make a seed
+            Digest::update(&mut h, &user_input.as_bytes());
+            h.finalize().into()
+        };
+
+        let mut digest = &h[..];
+
+        // Interpret the first 32 bytes of the digest as 8 32-bit words
+        let mut seed = [0u32; 8];
+        for i in 0..8 {
+            seed[i] = digest
+                .read_u32::<BigEndian>()
+                .expect("digest is large enough for this to work");
+        }
+
+        ChaChaRng::from_seed(&seed)
+    }
+}
@@ -0,0 +1,209 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::beacon::BeaconRecord;
+use powersoftau_core::parameters::TranscriptFile;
+use powersoftau_core::ptau::{export_ptau, ptau_byte_size, PtauContribution};
+use powersoftau_core::transcript::check_next_challenge;
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+const SCRATCH: &str = "export.challenge";
+
+fn main() {
+    // The final accumulator and the `.ptau` file come first, followed by the responses of the
+    // transcript in the order they were contributed. Every other argument configures the
+    // ceremony.
+    let mut files = vec![];
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    if files.len() < 2 {
+        println!("Usage: export_ptau <final challenge or response> <output.ptau> [<response>...]");
+        println!("The responses go into the contributions section, in the order given.");
+        std::process::exit(2);
+    }
+    let responses = files.split_off(2);
+    let output = files.pop().unwrap();
+    let input = files.pop().unwrap();
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The final accumulator tells the power of tau and the encoding of its points
+    let layout =
+        detect_layout(&input, params.curve()).expect("unable to recognize the final accumulator");
+    let params = layout.parameters(&params);
+
+    params.print_info();
+    println!("`{}` is {}", input.display(), layout);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => export::<Bls12>(&params, &layout, &input, &output, &responses),
+        CurveKind::Bn254 => export::<Bn256>(&params, &layout, &input, &output, &responses),
+    }
+}
+
+fn map(path: &Path, layout: &FileLayout) -> Mmap {
+    let reader = OpenOptions::new()
+        .read(true)
+        .open(path)
+        .unwrap_or_else(|e| panic!("unable to open `{}`: {}", path.display(), e));
+
+    unsafe {
+        MmapOptions::new()
+            .offset(layout.body_offset())
+            .map(&reader)
+            .expect("unable to create a memory map for input")
+    }
+}
+
+fn export<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    layout: &FileLayout,
+    input: &Path,
+    output: &Path,
+    responses: &[PathBuf],
+) {
+    let mut progress = ConsoleProgress::new();
+
+    println!(
+        "Will export the accumulator for 2^{} powers of tau with {} contributions",
+        params.required_power(),
+        responses.len()
+    );
+
+    let input_map = map(input, layout);
+
+    // Every response is followed by the challenge the next response starts with the hash of.
+    // The last one is followed by the final accumulator if that is a challenge, or else by the
+    // challenge the final response starts with the hash of, unless it is the final response
+    // itself. Each of these hashes is derived from the response again.
+    let mut contributions = vec![];
+    let mut following = BatchedAccumulator::<E>::calculate_hash(&input_map, &mut progress);
+    let input_hash = following;
+    if layout.role == TranscriptFile::Response {
+        following.copy_from_slice(&input_map[0..params.hash_size()]);
+    }
+    let mut following_path = input;
+    for (index, path) in responses.iter().enumerate().rev() {
+        let response = detect_layout(path, params.curve())
+            .unwrap_or_else(|e| panic!("unable to recognize `{}`: {}", path.display(), e));
+        response
+            .ensure_role(TranscriptFile::Response)
+            .unwrap_or_else(|e| panic!("`{}` is not a response: {}", path.display(), e));
+        let response_map = map(path, &response);
+
+        // The beacon of a recorded beacon contribution has to check out before it is named
+        let record = BeaconRecord::load(BeaconRecord::path_for(path))
+            .expect("unable to read a beacon record");
+        if let Some(ref record) = record {
+            record.verify().unwrap_or_else(|e| {
+                panic!(
+                    "the beacon record of `{}` is invalid: {}",
+                    path.display(),
+                    e
+                )
+            });
+        }
+        let beacon = record.is_some();
+
+        let is_input = index + 1 == responses.len()
+            && layout.role == TranscriptFile::Response
+            && BatchedAccumulator::<E>::calculate_hash(&response_map, &mut progress) == input_hash;
+        let next_challenge = match is_input {
+            true => Default::default(),
+            false => {
+                check_next_challenge::<E>(
+                    path,
+                    following.as_slice(),
+                    Path::new(SCRATCH),
+                    &response.parameters(params),
+                    &mut progress,
+                )
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "`{}` does not build on the challenge after `{}`: {}",
+                        following_path.display(),
+                        path.display(),
+                        e
+                    )
+                });
+                following
+            }
+        };
+        let contribution = PtauContribution::<E>::read(
+            &response_map,
+            response.compression,
+            next_challenge,
+            beacon,
+            &response.parameters(params),
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "unable to read the contribution of `{}`: {}",
+                path.display(),
+                e
+            )
+        });
+
+        following.copy_from_slice(&response_map[0..params.hash_size()]);
+        following_path = path;
+        contributions.push(contribution);
+    }
+    contributions.reverse();
+
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(output)
+        .unwrap_or_else(|e| panic!("unable to create `{}`: {}", output.display(), e));
+    let size = ptau_byte_size(&contributions, params).expect("unable to encode the contributions");
+    writer
+        .set_len(size as u64)
+        .expect("must make output file large enough");
+
+    let mut writable_map = unsafe {
+        MmapOptions::new()
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    export_ptau(
+        &input_map,
+        layout.compression,
+        params.check_for_correctness(),
+        &contributions,
+        &mut writable_map,
+        params,
+        &mut progress,
+    )
+    .expect("must export the accumulator");
+
+    println!(
+        "Done! `{}` holds the accumulator for 2^{} powers of tau in the snarkjs format.",
+        output.display(),
+        params.required_power()
+    );
+}
@@ -0,0 +1,153 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::parameters::{TranscriptFile, UseCompression};
+use powersoftau_core::ptau::{import_ptau, read_ptau_info};
+use powersoftau_core::{ConsoleProgress, CurveKind, RuntimeCeremonyParameters};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    // The `.ptau` file and the challenge are given in that order, and every other argument
+    // configures the ceremony
+    let mut files = vec![];
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    let (input, output) = match files.as_slice() {
+        [input, output] => (input.clone(), output.clone()),
+        _ => {
+            println!("Usage: import_ptau <input.ptau> <challenge>");
+            println!("The challenge can be contributed to and verified like any other.");
+            std::process::exit(2);
+        }
+    };
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    let reader = OpenOptions::new()
+        .read(true)
+        .open(&input)
+        .expect("unable to open the .ptau file");
+    let ptau_map = unsafe {
+        MmapOptions::new()
+            .map(&reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    // The .ptau file tells the power of tau, the curve comes from the configuration
+    let info = read_ptau_info(&ptau_map, params.curve()).expect("unable to read the .ptau file");
+    let params = params.with_required_power(info.power);
+
+    params.print_info();
+    println!(
+        "`{}` holds 2^{} powers of tau with {} contributions",
+        input.display(),
+        info.power,
+        info.contributions
+    );
+
+    match params.curve() {
+        CurveKind::Bls12_381 => import::<Bls12>(&params, &ptau_map, info.contributions, &output),
+        CurveKind::Bn254 => import::<Bn256>(&params, &ptau_map, info.contributions, &output),
+    }
+}
+
+fn import<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    ptau_map: &Mmap,
+    contributions: usize,
+    output: &Path,
+) {
+    let mut progress = ConsoleProgress::new();
+
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(output)
+        .expect("unable to create the challenge file");
+
+    // The challenge counts the contributions of the snarkjs transcript
+    let header = match params.file_header() {
+        true => Some(FileHeader::new(
+            TranscriptFile::Challenge,
+            UseCompression::No,
+            contributions as u64,
+            params,
+        )),
+        false => None,
+    };
+    let offset = body_offset(header.as_ref());
+
+    writer
+        .set_len(offset + params.accumulator_byte_size() as u64)
+        .expect("must make output file large enough");
+
+    if let Some(header) = header {
+        header
+            .write(&writer)
+            .expect("unable to write the header of the challenge");
+    }
+
+    let writable_map = unsafe {
+        MmapOptions::new()
+            .offset(offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    let challenge_map = import_ptau::<E>(ptau_map, writable_map, params, &mut progress)
+        .expect("must import the .ptau file");
+
+    println!("The challenge starts with the BLAKE2b hash of the .ptau file:");
+    for line in challenge_map[0..params.hash_size()].chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
+            }
+            print!(" ");
+        }
+        println!("");
+    }
+
+    let challenge_hash = BatchedAccumulator::<E>::calculate_hash(&challenge_map, &mut progress);
+
+    println!("Here's the BLAKE2b hash of the challenge:");
+    for line in challenge_hash.as_slice().chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
+            }
+            print!(" ");
+        }
+        println!("");
+    }
+
+    println!(
+        "Done! `{}` holds the accumulator for 2^{} powers of tau as an uncompressed challenge.",
+        output.display(),
+        params.required_power()
+    );
+}
@@ -2,26 +2,36 @@ extern crate bellman;
 extern crate memmap;
 
 use powersoftau_core::batched_accumulator::BatchedAccumulator;
-use powersoftau_core::parameters::UseCompression;
-use powersoftau_core::small_bls12_381::Bls12CeremonyParameters;
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::parameters::TranscriptFile;
 use powersoftau_core::utils::blank_hash;
-use powersoftau_core::RuntimeCeremonyParameters;
+use powersoftau_core::{ConsoleProgress, CurveKind, RuntimeCeremonyParameters};
 
 use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
 use memmap::*;
 use std::fs::OpenOptions;
 use std::io::Write;
 
-
 fn main() {
     // Load ceremony configuration
-    let params = RuntimeCeremonyParameters::from_env_with_defaults()
+    let params = RuntimeCeremonyParameters::from_env_and_args(std::env::args().skip(1))
         .expect("Failed to load ceremony configuration");
-    
+
     params.print_info();
-    
+
+    match params.curve() {
+        CurveKind::Bls12_381 => new_challenge::<Bls12>(&params),
+        CurveKind::Bn254 => new_challenge::<Bn256>(&params),
+    }
+}
+
+fn new_challenge<E: Engine>(params: &RuntimeCeremonyParameters) {
+    let mut progress = ConsoleProgress::new();
+
     let compress_new_challenge = params.use_compression();
-    
+
     println!(
         "Will generate an empty accumulator for 2^{} powers of tau",
         params.required_power()
@@ -38,18 +48,32 @@ fn main() {
         .open("challenge")
         .expect("unable to create `./challenge`");
 
-    let expected_challenge_length = match compress_new_challenge {
-        UseCompression::Yes => {
-            params.contribution_byte_size() - params.public_key_size()
-        }
-        UseCompression::No => params.accumulator_byte_size(),
+    let expected_challenge_length =
+        params.file_byte_size(TranscriptFile::Challenge, compress_new_challenge);
+
+    let header = match params.file_header() {
+        true => Some(FileHeader::new(
+            TranscriptFile::Challenge,
+            compress_new_challenge,
+            0,
+            params,
+        )),
+        false => None,
     };
+    let offset = body_offset(header.as_ref());
 
-    file.set_len(expected_challenge_length as u64)
+    file.set_len(offset + expected_challenge_length as u64)
         .expect("unable to allocate large enough file");
 
+    if let Some(header) = header {
+        header
+            .write(&file)
+            .expect("unable to write the header of `./challenge`");
+    }
+
     let mut writable_map = unsafe {
         MmapOptions::new()
+            .offset(offset)
             .map_mut(&file)
             .expect("unable to create a memory map")
     };
@@ -75,9 +99,11 @@ fn main() {
         println!("");
     }
 
-    BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::generate_initial(
+    BatchedAccumulator::<E>::generate_initial(
         &mut writable_map,
         compress_new_challenge,
+        params,
+        &mut progress,
     )
     .expect("generation of initial accumulator is successful");
     writable_map
@@ -89,7 +115,7 @@ fn main() {
         .make_read_only()
         .expect("must make a map readonly");
     let contribution_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(&output_readonly);
+        BatchedAccumulator::<E>::calculate_hash(&output_readonly, &mut progress);
 
     println!("Empty contribution is formed with a hash:");
 
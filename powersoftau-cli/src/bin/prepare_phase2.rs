@@ -0,0 +1,121 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::lagrange::{lagrange_byte_size, lagrange_path_for, write_lagrange};
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    // The final accumulator comes first, optionally followed by the directory the files go to.
+    // Every other argument configures the ceremony.
+    let mut files = vec![];
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    let (input, directory) = match files.as_slice() {
+        [input] => (input.clone(), PathBuf::from(".")),
+        [input, directory] => (input.clone(), directory.clone()),
+        _ => {
+            println!("Usage: prepare_phase2 <final challenge or response> [<output directory>]");
+            println!("Writes phase1radix2m0 up to phase1radix2m<power>, one per domain size.");
+            std::process::exit(2);
+        }
+    };
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The final accumulator tells the power of tau and the encoding of its points
+    let layout =
+        detect_layout(&input, params.curve()).expect("unable to recognize the final accumulator");
+    let params = layout.parameters(&params);
+
+    params.print_info();
+    println!("`{}` is {}", input.display(), layout);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => prepare::<Bls12>(&params, &layout, &input, &directory),
+        CurveKind::Bn254 => prepare::<Bn256>(&params, &layout, &input, &directory),
+    }
+}
+
+fn prepare<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    layout: &FileLayout,
+    input: &Path,
+    directory: &Path,
+) {
+    let mut progress = ConsoleProgress::new();
+
+    let reader = OpenOptions::new()
+        .read(true)
+        .open(input)
+        .expect("unable to open the final accumulator");
+    let input_map = unsafe {
+        MmapOptions::new()
+            .offset(layout.body_offset())
+            .map(&reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    for power in 0..=params.required_power() {
+        let path = lagrange_path_for(directory, power);
+        println!(
+            "Writing the Lagrange basis for 2^{} points to `{}`",
+            power,
+            path.display()
+        );
+
+        let writer = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .unwrap_or_else(|e| panic!("unable to create `{}`: {}", path.display(), e));
+        writer
+            .set_len(lagrange_byte_size(power, params) as u64)
+            .expect("must make output file large enough");
+
+        let mut writable_map = unsafe {
+            MmapOptions::new()
+                .map_mut(&writer)
+                .expect("unable to create a memory map for output")
+        };
+
+        write_lagrange::<E>(
+            &input_map,
+            layout.compression,
+            params.check_for_correctness(),
+            &mut writable_map,
+            power,
+            params,
+            &mut progress,
+        )
+        .expect("must write the Lagrange basis");
+    }
+
+    println!(
+        "Done! `{}` holds the Lagrange bases for every domain up to 2^{} points.",
+        directory.display(),
+        params.required_power()
+    );
+}
@@ -0,0 +1,231 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::parameters::TranscriptFile;
+use powersoftau_core::reduction::ReductionNote;
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    // The source and reduced files are given in that order, `--power` is the power to reduce
+    // to, and every other argument configures the ceremony
+    let mut files = vec![];
+    let mut power = None;
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--power" {
+            power = Some(args.next().expect("missing value for --power"));
+        } else if let Some(value) = arg.strip_prefix("--power=") {
+            power = Some(value.to_string());
+        } else if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    let (source_path, reduced_path, power) = match (files.as_slice(), power) {
+        ([source, reduced], Some(power)) => (
+            source.clone(),
+            reduced.clone(),
+            power
+                .parse::<usize>()
+                .expect("--power takes the power of two to reduce to"),
+        ),
+        _ => {
+            println!("Usage: reduce_power --power <power> <source> <reduced>");
+            println!("The source is a verified challenge or response of a larger power.");
+            std::process::exit(2);
+        }
+    };
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The source tells the power of tau and the encoding of its points
+    let source =
+        detect_layout(&source_path, params.curve()).expect("unable to recognize the source file");
+    let params = source.parameters(&params);
+    if power >= params.required_power() {
+        panic!(
+            "the source is {}, so it can only be reduced to a smaller power than 2^{}",
+            source,
+            params.required_power()
+        );
+    }
+    let reduced_params = params.clone().with_required_power(power);
+
+    params.print_info();
+    println!("`{}` is {}", source_path.display(), source);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => reduce::<Bls12>(
+            &params,
+            &reduced_params,
+            &source,
+            &source_path,
+            &reduced_path,
+        ),
+        CurveKind::Bn254 => reduce::<Bn256>(
+            &params,
+            &reduced_params,
+            &source,
+            &source_path,
+            &reduced_path,
+        ),
+    }
+}
+
+fn reduce<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    reduced_params: &RuntimeCeremonyParameters,
+    source: &FileLayout,
+    source_path: &Path,
+    reduced_path: &Path,
+) {
+    let mut progress = ConsoleProgress::new();
+
+    println!(
+        "Will reduce the accumulator for 2^{} powers of tau to 2^{} powers",
+        params.required_power(),
+        reduced_params.required_power()
+    );
+
+    let reader = OpenOptions::new()
+        .read(true)
+        .open(source_path)
+        .expect("unable to open the source file");
+    let source_map = unsafe {
+        MmapOptions::new()
+            .offset(source.body_offset())
+            .map(&reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    let source_hash = BatchedAccumulator::<E>::calculate_hash(&source_map, &mut progress);
+
+    println!("Hash of the source file:");
+    for line in source_hash.as_slice().chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
+            }
+            print!(" ");
+        }
+        println!("");
+    }
+
+    // The reduced accumulator is written as a challenge with the point encoding of the ceremony
+    // configuration
+    let compression = params.use_compression();
+
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(reduced_path)
+        .expect("unable to create the reduced file");
+
+    let header = match params.file_header() {
+        true => Some(FileHeader::next(
+            source.header.as_ref(),
+            TranscriptFile::Challenge,
+            compression,
+            reduced_params,
+        )),
+        false => None,
+    };
+    let offset = body_offset(header.as_ref());
+
+    writer
+        .set_len(
+            offset + reduced_params.file_byte_size(TranscriptFile::Challenge, compression) as u64,
+        )
+        .expect("must make output file large enough");
+
+    if let Some(header) = header {
+        header
+            .write(&writer)
+            .expect("unable to write the header of the reduced file");
+    }
+
+    let mut writable_map = unsafe {
+        MmapOptions::new()
+            .offset(offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    // The hash of the source takes the place of the hash of a previous response, so that the
+    // reduced accumulator is linked to the transcript
+    (&mut writable_map[0..])
+        .write_all(source_hash.as_slice())
+        .expect("unable to write the source hash to mmap");
+
+    BatchedAccumulator::<E>::reduce_power(
+        &source_map,
+        &mut writable_map,
+        source.compression,
+        compression,
+        params.check_for_correctness(),
+        params,
+        reduced_params,
+        &mut progress,
+    )
+    .expect("must reduce the accumulator");
+
+    writable_map.flush().expect("must flush the memory map");
+
+    let reduced_map = writable_map
+        .make_read_only()
+        .expect("must make a map readonly");
+    let reduced_hash = BatchedAccumulator::<E>::calculate_hash(&reduced_map, &mut progress);
+
+    println!("Here's the BLAKE2b hash of the reduced file:");
+    for line in reduced_hash.as_slice().chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
+            }
+            print!(" ");
+        }
+        println!("");
+    }
+
+    let note_path = ReductionNote::path_for(reduced_path);
+    ReductionNote::new(
+        params.curve(),
+        params.required_power(),
+        reduced_params.required_power(),
+        source_hash.as_slice(),
+        reduced_hash.as_slice(),
+    )
+    .save(&note_path)
+    .expect("unable to write the reduction note");
+
+    println!(
+        "Done! `{}` holds the accumulator for 2^{} powers, and `{}` links it to `{}`.",
+        reduced_path.display(),
+        reduced_params.required_power(),
+        note_path.display(),
+        source_path.display()
+    );
+}
@@ -0,0 +1,218 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::config::CeremonyMode;
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::parameters::{TranscriptFile, UseCompression};
+use powersoftau_core::split::{final_hash, split_final, SplitManifest, GROTH16_SRS, TAU_ONLY_SRS};
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+fn main() {
+    // The final accumulator comes first, optionally followed by the directory the files go to.
+    // Every other argument configures the ceremony.
+    let mut files = vec![];
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    let (input, directory) = match files.as_slice() {
+        [input] => (input.clone(), PathBuf::from(".")),
+        [input, directory] => (input.clone(), directory.clone()),
+        _ => {
+            println!("Usage: split_final <final challenge or response> [<output directory>]");
+            println!(
+                "Writes {}, {} and a manifest linking both to the transcript.",
+                TAU_ONLY_SRS, GROTH16_SRS
+            );
+            std::process::exit(2);
+        }
+    };
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The final accumulator tells the power of tau and the encoding of its points
+    let layout =
+        detect_layout(&input, params.curve()).expect("unable to recognize the final accumulator");
+    let params = layout.parameters(&params);
+
+    params.print_info();
+    println!("`{}` is {}", input.display(), layout);
+
+    // Checked before any file is created, so that a refused input leaves nothing behind
+    if params.mode() != CeremonyMode::Full {
+        panic!(
+            "`{}` is a tau-only accumulator, with no alpha and beta to split off",
+            input.display()
+        );
+    }
+
+    match params.curve() {
+        CurveKind::Bls12_381 => split::<Bls12>(&params, &layout, &input, &directory),
+        CurveKind::Bn254 => split::<Bn256>(&params, &layout, &input, &directory),
+    }
+}
+
+/// Creates the challenge at `path` for `params` with a header following the one of the final
+/// accumulator, and maps its body
+fn create_challenge(
+    path: &Path,
+    layout: &FileLayout,
+    params: &RuntimeCeremonyParameters,
+) -> (File, MmapMut) {
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .unwrap_or_else(|e| panic!("unable to create `{}`: {}", path.display(), e));
+
+    let header = match params.file_header() {
+        true => Some(FileHeader::next(
+            layout.header.as_ref(),
+            TranscriptFile::Challenge,
+            UseCompression::No,
+            params,
+        )),
+        false => None,
+    };
+    let offset = body_offset(header.as_ref());
+
+    writer
+        .set_len(
+            offset + params.file_byte_size(TranscriptFile::Challenge, UseCompression::No) as u64,
+        )
+        .expect("must make output file large enough");
+
+    if let Some(header) = header {
+        header.write(&writer).unwrap_or_else(|e| {
+            panic!("unable to write the header of `{}`: {}", path.display(), e)
+        });
+    }
+
+    let writable_map = unsafe {
+        MmapOptions::new()
+            .offset(offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
+
+    (writer, writable_map)
+}
+
+fn split<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    layout: &FileLayout,
+    input: &Path,
+    directory: &Path,
+) {
+    let mut progress = ConsoleProgress::new();
+
+    let reader = OpenOptions::new()
+        .read(true)
+        .open(input)
+        .expect("unable to open the final accumulator");
+    let input_map = unsafe {
+        MmapOptions::new()
+            .offset(layout.body_offset())
+            .map(&reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    let final_hash = final_hash::<E>(&input_map, layout.role, params, &mut progress)
+        .expect("unable to find the final hash of the transcript");
+
+    println!("Final hash of the transcript:");
+    for line in final_hash.as_slice().chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
+            }
+            print!(" ");
+        }
+        println!("");
+    }
+
+    let tau_only_params = params.clone().with_mode(CeremonyMode::TauOnly);
+    let tau_only_path = directory.join(TAU_ONLY_SRS);
+    let groth16_path = directory.join(GROTH16_SRS);
+    let (_tau_only_file, mut tau_only_map) =
+        create_challenge(&tau_only_path, layout, &tau_only_params);
+    let (_groth16_file, mut groth16_map) = create_challenge(&groth16_path, layout, params);
+
+    println!(
+        "Writing the tau-only SRS to `{}` and the Groth16 SRS to `{}`",
+        tau_only_path.display(),
+        groth16_path.display()
+    );
+
+    split_final::<E>(
+        &input_map,
+        layout.compression,
+        params.check_for_correctness(),
+        final_hash.as_slice(),
+        &mut tau_only_map,
+        &mut groth16_map,
+        params,
+        &mut progress,
+    )
+    .expect("must split the final accumulator");
+
+    let mut hashes = vec![];
+    for (path, writable_map) in [(&tau_only_path, tau_only_map), (&groth16_path, groth16_map)] {
+        let map = writable_map
+            .make_read_only()
+            .expect("must make a map readonly");
+        let hash = BatchedAccumulator::<E>::calculate_hash(&map, &mut progress);
+
+        println!("Here's the BLAKE2b hash of `{}`:", path.display());
+        for line in hash.as_slice().chunks(16) {
+            print!("\t");
+            for section in line.chunks(4) {
+                for b in section {
+                    print!("{:02x}", b);
+                }
+                print!(" ");
+            }
+            println!("");
+        }
+        hashes.push(hash);
+    }
+
+    let manifest_path = SplitManifest::path_for(directory);
+    SplitManifest::new(
+        params.curve(),
+        params.required_power(),
+        final_hash.as_slice(),
+        hashes[0].as_slice(),
+        hashes[1].as_slice(),
+    )
+    .save(&manifest_path)
+    .expect("unable to write the SRS manifest");
+
+    println!(
+        "Done! `{}` links both files to the final hash of the transcript.",
+        manifest_path.display()
+    );
+}
@@ -0,0 +1,181 @@
+extern crate bellman;
+extern crate memmap;
+
+use powersoftau_core::beacon::{verify_beacon, BeaconRecord};
+use powersoftau_core::parameters::TranscriptFile;
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::*;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+const RECOMPUTED_RESPONSE: &str = "response.recomputed";
+
+fn main() {
+    // `--recompute true` computes the beacon contribution again, every other argument
+    // configures the ceremony
+    let mut recompute = false;
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--recompute" => args.next().expect("missing value for --recompute"),
+            _ => match arg.strip_prefix("--recompute=") {
+                Some(value) => value.to_string(),
+                None => {
+                    config_args.push(arg);
+                    continue;
+                }
+            },
+        };
+        recompute = value
+            .parse()
+            .expect("--recompute takes either true or false");
+    }
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The files tell the power of tau and the encoding of their points
+    let challenge = detect_layout("challenge", params.curve())
+        .expect("unable to recognize `./challenge` in this directory");
+    challenge
+        .ensure_role(TranscriptFile::Challenge)
+        .expect("`./challenge` is not a challenge");
+    let response = detect_layout("response", params.curve())
+        .expect("unable to recognize `./response` in this directory");
+    response
+        .ensure_role(TranscriptFile::Response)
+        .expect("`./response` is not a response");
+    if response.required_power != challenge.required_power || response.mode != challenge.mode {
+        panic!(
+            "`./challenge` is {}, but `./response` is {}, so something isn't right.",
+            challenge, response
+        );
+    }
+    let params = challenge.parameters(&params);
+
+    params.print_info();
+    println!("`./challenge` is {}", challenge);
+    println!("`./response` is {}", response);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => {
+            verify_beacon_contribution::<Bls12>(&params, &challenge, &response, recompute)
+        }
+        CurveKind::Bn254 => {
+            verify_beacon_contribution::<Bn256>(&params, &challenge, &response, recompute)
+        }
+    }
+}
+
+fn verify_beacon_contribution<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    challenge: &FileLayout,
+    response: &FileLayout,
+    recompute: bool,
+) {
+    let mut progress = ConsoleProgress::new();
+
+    // The beacon recorded next to the response has to be the announced one of the configuration
+    let record = BeaconRecord::load(BeaconRecord::path_for("response"))
+        .expect("unable to read `./response.beacon`");
+    let result = match record {
+        Some(ref record) => {
+            println!("Using the beacon recorded in `./response.beacon`");
+            println!("Beacon: {}", record.beacon);
+            println!("Taken from {}", record.provenance);
+            match record.checkpoints.len() {
+                0 => println!("Recomputing the beacon..."),
+                segments => println!(
+                    "Checking {} segments of the beacon iterations in parallel...",
+                    segments
+                ),
+            }
+
+            record
+                .ensure_announced(params.beacon())
+                .expect("the beacon recorded in `./response.beacon` is not the announced one");
+            record
+                .verify()
+                .expect("the beacon recorded in `./response.beacon` is invalid")
+        }
+        None => {
+            let (beacon, provenance) = params
+                .beacon()
+                .resolve()
+                .expect("unable to take the beacon from its source");
+            println!("Beacon: {}", beacon);
+            println!("Taken from {}", provenance);
+            println!("Recomputing the beacon...");
+
+            beacon.iterate(&mut |_, _| {}).expect("unusable beacon")
+        }
+    };
+
+    print!("Final result of beacon: ");
+    for b in result.iter() {
+        print!("{:02x}", b);
+    }
+    println!("");
+
+    // Try to load `./challenge` and `./response` from disk.
+    let challenge_reader = OpenOptions::new()
+        .read(true)
+        .open("challenge")
+        .expect("unable open `./challenge` in this directory");
+    let challenge_readable_map = unsafe {
+        MmapOptions::new()
+            .offset(challenge.body_offset())
+            .map(&challenge_reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    let response_reader = OpenOptions::new()
+        .read(true)
+        .open("response")
+        .expect("unable open `./response` in this directory");
+    let response_readable_map = unsafe {
+        MmapOptions::new()
+            .offset(response.body_offset())
+            .map(&response_reader)
+            .expect("unable to create a memory map for input")
+    };
+
+    if recompute {
+        println!("Will compute the beacon contribution again, this could take a while...");
+    }
+
+    let verification = verify_beacon::<E>(
+        &challenge_readable_map,
+        &response_readable_map,
+        challenge.compression,
+        response.compression,
+        &result,
+        match recompute {
+            true => Some(Path::new(RECOMPUTED_RESPONSE)),
+            false => None,
+        },
+        params,
+        &mut progress,
+    );
+
+    match verification {
+        Ok(()) if recompute => {
+            println!("`./response` is exactly the beacon contribution on top of `./challenge`")
+        }
+        Ok(()) => println!(
+            "The public key of `./response` is derived from the beacon on top of `./challenge`"
+        ),
+        Err(e) => {
+            println!("{}", e);
+            panic!("INVALID BEACON CONTRIBUTION!!!");
+        }
+    }
+}
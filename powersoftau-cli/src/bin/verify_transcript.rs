@@ -0,0 +1,183 @@
+extern crate bellman;
+
+use powersoftau_core::beacon::BeaconRecord;
+use powersoftau_core::parameters::TranscriptFile;
+use powersoftau_core::transcript::verify_transcript;
+use powersoftau_core::{detect_layout, ConsoleProgress, CurveKind, RuntimeCeremonyParameters};
+
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use std::path::{Path, PathBuf};
+
+const SCRATCH: &str = "transcript.challenge";
+
+fn main() {
+    // Response files are given in order, every other argument configures the ceremony
+    let mut responses = vec![];
+    let mut beacon = None;
+    let mut config_args = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--beacon" {
+            beacon = Some(PathBuf::from(
+                args.next().expect("missing value for --beacon"),
+            ));
+        } else if let Some(path) = arg.strip_prefix("--beacon=") {
+            beacon = Some(PathBuf::from(path));
+        } else if arg.starts_with("--") {
+            let takes_value = !arg.contains('=');
+            config_args.push(arg);
+            if takes_value {
+                config_args.extend(args.next());
+            }
+        } else {
+            responses.push(PathBuf::from(arg));
+        }
+    }
+    responses.extend(beacon.clone());
+    if responses.is_empty() {
+        println!("Usage: verify_transcript [--beacon <beacon response>] <response>...");
+        println!("The responses are given in the order they were contributed.");
+        std::process::exit(2);
+    }
+
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(config_args)
+        .expect("Failed to load ceremony configuration");
+
+    // The first response tells the power of tau of the whole transcript
+    let first = detect_layout(&responses[0], params.curve())
+        .expect("unable to recognize the first response");
+    first
+        .ensure_role(TranscriptFile::Response)
+        .expect("the first file is not a response");
+    let params = first.parameters(&params);
+
+    params.print_info();
+
+    let valid = match params.curve() {
+        CurveKind::Bls12_381 => verify::<Bls12>(&params, &responses, beacon.as_deref()),
+        CurveKind::Bn254 => verify::<Bn256>(&params, &responses, beacon.as_deref()),
+    };
+
+    if !valid {
+        std::process::exit(1);
+    }
+}
+
+fn verify<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    responses: &[PathBuf],
+    beacon: Option<&Path>,
+) -> bool {
+    let mut progress = ConsoleProgress::new();
+
+    // The beacon recorded next to the beacon response has to be the announced one of the
+    // configuration
+    let iterated_beacon = beacon.map(|beacon| {
+        let record_path = BeaconRecord::path_for(beacon);
+        let record = BeaconRecord::load(&record_path)
+            .unwrap_or_else(|e| panic!("unable to read `{}`: {}", record_path.display(), e));
+        let result = match record {
+            Some(record) => {
+                println!("Using the beacon recorded in `{}`", record_path.display());
+                println!("Beacon: {}", record.beacon);
+                println!("Taken from {}", record.provenance);
+
+                record
+                    .ensure_announced(params.beacon())
+                    .unwrap_or_else(|e| {
+                        panic!(
+                            "the beacon recorded in `{}` is not the announced one: {}",
+                            record_path.display(),
+                            e
+                        )
+                    });
+                record.verify().unwrap_or_else(|e| {
+                    panic!(
+                        "the beacon recorded in `{}` is invalid: {}",
+                        record_path.display(),
+                        e
+                    )
+                })
+            }
+            None => {
+                let (beacon, provenance) = params
+                    .beacon()
+                    .resolve()
+                    .expect("unable to take the beacon from its source");
+                println!("Beacon: {}", beacon);
+                println!("Taken from {}", provenance);
+                println!("Recomputing the beacon...");
+
+                beacon.iterate(&mut |_, _| {}).expect("unusable beacon")
+            }
+        };
+
+        print!("Final result of beacon: ");
+        for b in result.iter() {
+            print!("{:02x}", b);
+        }
+        println!("");
+
+        result
+    });
+
+    println!(
+        "Will verify a transcript of {} contributions to accumulator for 2^{} powers of tau",
+        responses.len(),
+        params.required_power()
+    );
+
+    let report = verify_transcript::<E, _>(
+        responses,
+        iterated_beacon.as_ref(),
+        Path::new(SCRATCH),
+        params,
+        &mut progress,
+    )
+    .expect("unable to verify the transcript");
+
+    for (index, contribution) in report.contributions.iter().enumerate() {
+        let label = match beacon {
+            Some(beacon) if contribution.path == beacon => "beacon".to_string(),
+            _ => format!("#{}", index + 1),
+        };
+        match contribution.result {
+            Ok(()) => println!(
+                "Contribution {} `{}`: OK",
+                label,
+                contribution.path.display()
+            ),
+            Err(ref e) => println!(
+                "Contribution {} `{}`: FAILED, {}",
+                label,
+                contribution.path.display(),
+                e
+            ),
+        }
+
+        if let Some(hash) = contribution.response_hash {
+            println!("Here's the BLAKE2b hash of this response:");
+            for line in hash.as_slice().chunks(16) {
+                print!("\t");
+                for section in line.chunks(4) {
+                    for b in section {
+                        print!("{:02x}", b);
+                    }
+                    print!(" ");
+                }
+                println!("");
+            }
+        }
+    }
+
+    if report.is_valid() {
+        println!("Transcript verification succeeded!");
+    } else {
+        println!("INVALID TRANSCRIPT!!!");
+    }
+
+    report.is_valid()
+}
@@ -5,26 +5,68 @@ extern crate memmap;
 extern crate rand;
 
 use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::checkpoint::VerificationCheckpoint;
+use powersoftau_core::header::{body_offset, FileHeader};
 use powersoftau_core::keypair::PublicKey;
-use powersoftau_core::parameters::{CheckForCorrectness, UseCompression};
-use powersoftau_core::small_bls12_381::Bls12CeremonyParameters;
+use powersoftau_core::parameters::{CheckForCorrectness, TranscriptFile};
+use powersoftau_core::{
+    detect_layout, ConsoleProgress, CurveKind, FileLayout, RuntimeCeremonyParameters,
+};
 
 use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
 use memmap::*;
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
 
 use std::io::{Read, Write};
 
-use powersoftau_core::parameters::PowersOfTauParameters;
-
-const PREVIOUS_CHALLENGE_IS_COMPRESSED: UseCompression = UseCompression::No;
-const CONTRIBUTION_IS_COMPRESSED: UseCompression = UseCompression::Yes;
-const COMPRESS_NEW_CHALLENGE: UseCompression = UseCompression::No;
+const RESPONSE: &str = "response";
 
 fn main() {
+    // Load ceremony configuration
+    let params = RuntimeCeremonyParameters::from_env_and_args(std::env::args().skip(1))
+        .expect("Failed to load ceremony configuration");
+
+    // The files tell the power of tau and the encoding of their points
+    let challenge = detect_layout("challenge", params.curve())
+        .expect("unable to recognize `./challenge` in this directory");
+    challenge
+        .ensure_role(TranscriptFile::Challenge)
+        .expect("`./challenge` is not a challenge");
+    let response = detect_layout(RESPONSE, params.curve())
+        .expect("unable to recognize `./response` in this directory");
+    response
+        .ensure_role(TranscriptFile::Response)
+        .expect("`./response` is not a response");
+    if response.required_power != challenge.required_power || response.mode != challenge.mode {
+        panic!(
+            "`./challenge` is {}, but `./response` is {}, so something isn't right.",
+            challenge, response
+        );
+    }
+    let params = challenge.parameters(&params);
+
+    params.print_info();
+    println!("`./challenge` is {}", challenge);
+    println!("`./response` is {}", response);
+
+    match params.curve() {
+        CurveKind::Bls12_381 => verify_contribution::<Bls12>(&params, &challenge, &response),
+        CurveKind::Bn254 => verify_contribution::<Bn256>(&params, &challenge, &response),
+    }
+}
+
+fn verify_contribution<E: Engine>(
+    params: &RuntimeCeremonyParameters,
+    challenge: &FileLayout,
+    response: &FileLayout,
+) {
+    let mut progress = ConsoleProgress::new();
+
     println!(
-        "Will verify and decompress a contribution to accumulator for 2^{} powers of tau",
-        Bls12CeremonyParameters::REQUIRED_POWER
+        "Will verify a contribution to accumulator for 2^{} powers of tau",
+        params.required_power()
     );
 
     // Try to load `./challenge` from disk.
@@ -33,25 +75,9 @@ fn main() {
         .open("challenge")
         .expect("unable open `./challenge` in this directory");
 
-    {
-        let metadata = challenge_reader
-            .metadata()
-            .expect("unable to get filesystem metadata for `./challenge`");
-        let expected_challenge_length = match PREVIOUS_CHALLENGE_IS_COMPRESSED {
-            UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-            UseCompression::No => Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE,
-        };
-        if metadata.len() != (expected_challenge_length as u64) {
-            panic!(
-                "The size of `./challenge` should be {}, but it's {}, so something isn't right.",
-                expected_challenge_length,
-                metadata.len()
-            );
-        }
-    }
-
     let challenge_readable_map = unsafe {
         MmapOptions::new()
+            .offset(challenge.body_offset())
             .map(&challenge_reader)
             .expect("unable to create a memory map for input")
     };
@@ -59,31 +85,12 @@ fn main() {
     // Try to load `./response` from disk.
     let response_reader = OpenOptions::new()
         .read(true)
-        .open("response")
+        .open(RESPONSE)
         .expect("unable open `./response` in this directory");
 
-    {
-        let metadata = response_reader
-            .metadata()
-            .expect("unable to get filesystem metadata for `./response`");
-        let expected_response_length = match CONTRIBUTION_IS_COMPRESSED {
-            UseCompression::Yes => Bls12CeremonyParameters::CONTRIBUTION_BYTE_SIZE,
-            UseCompression::No => {
-                Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE
-                    + Bls12CeremonyParameters::PUBLIC_KEY_SIZE
-            }
-        };
-        if metadata.len() != (expected_response_length as u64) {
-            panic!(
-                "The size of `./response` should be {}, but it's {}, so something isn't right.",
-                expected_response_length,
-                metadata.len()
-            );
-        }
-    }
-
     let response_readable_map = unsafe {
         MmapOptions::new()
+            .offset(response.body_offset())
             .map(&response_reader)
             .expect("unable to create a memory map for input")
     };
@@ -93,9 +100,7 @@ fn main() {
     // Check that contribution is correct
 
     let current_accumulator_hash =
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(
-            &challenge_readable_map,
-        );
+        BatchedAccumulator::<E>::calculate_hash(&challenge_readable_map, &mut progress);
 
     println!("Hash of the `challenge` file for verification:");
     for line in current_accumulator_hash.as_slice().chunks(16) {
@@ -131,14 +136,16 @@ fn main() {
             println!("");
         }
 
-        if &response_challenge_hash[..] != current_accumulator_hash.as_slice() {
-            panic!("Hash chain failure. This is not the right response.");
+        if let Err(e) = BatchedAccumulator::<E>::check_hash_chain(
+            &response_readable_map,
+            current_accumulator_hash.as_slice(),
+        ) {
+            panic!("{}. This is not the right response.", e);
         }
     }
 
-    let response_hash = BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(
-        &response_readable_map,
-    );
+    let response_hash =
+        BatchedAccumulator::<E>::calculate_hash(&response_readable_map, &mut progress);
 
     println!("Hash of the `response` file for verification:");
     for line in response_hash.as_slice().chunks(16) {
@@ -153,11 +160,8 @@ fn main() {
     }
 
     // get the contributor's public key
-    let public_key = PublicKey::<Bls12>::read::<Bls12CeremonyParameters>(
-        &response_readable_map,
-        CONTRIBUTION_IS_COMPRESSED,
-    )
-    .expect("wasn't able to deserialize the response file's public key");
+    let public_key = PublicKey::<E>::read(&response_readable_map, response.compression, params)
+        .expect("wasn't able to deserialize the response file's public key");
 
     // check that it follows the protocol
 
@@ -165,90 +169,154 @@ fn main() {
         "Verifying a contribution to contain proper powers and correspond to the public key..."
     );
 
-    let valid = BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::verify_transformation(
+    // Verified powers are recorded as the verification goes, so that an interrupted verification
+    // of the same files picks up where it stopped
+    let checkpoint_path = VerificationCheckpoint::path_for(RESPONSE);
+    let mut checkpoint = VerificationCheckpoint::new(
+        current_accumulator_hash.as_slice(),
+        response_hash.as_slice(),
+        challenge.compression,
+        response.compression,
+        params,
+    );
+    let previous_checkpoint = VerificationCheckpoint::load(&checkpoint_path)
+        .expect("unable to read the verification checkpoint of `./response`");
+    if let Some(previous) = previous_checkpoint {
+        match checkpoint.ensure_same_verification(&previous) {
+            Ok(()) => {
+                println!(
+                    "Resuming the verification from power {}",
+                    previous.verified.verified_powers
+                );
+                checkpoint = previous;
+            }
+            Err(e) => println!("Ignoring the verification checkpoint: {}", e),
+        }
+    }
+
+    let mut verified = checkpoint.verified.clone();
+    let verification = BatchedAccumulator::<E>::resume_verify_transformation(
         &challenge_readable_map,
         &response_readable_map,
         &public_key,
         current_accumulator_hash.as_slice(),
-        PREVIOUS_CHALLENGE_IS_COMPRESSED,
-        CONTRIBUTION_IS_COMPRESSED,
+        challenge.compression,
+        response.compression,
         CheckForCorrectness::No,
         CheckForCorrectness::Yes,
-    );
+        params,
+        &mut progress,
+        &mut verified,
+        &mut |verified| {
+            checkpoint.verified = verified.clone();
+            checkpoint.save(&checkpoint_path)
+        },
+    )
+    .and_then(|()| {
+        checkpoint.verified = verified;
+        checkpoint.ensure_complete(
+            current_accumulator_hash.as_slice(),
+            response_hash.as_slice(),
+            params,
+        )
+    });
 
-    if !valid {
-        println!("Verification failed, contribution was invalid somehow.");
+    if let Err(e) = verification {
+        println!("{}", e);
         panic!("INVALID CONTRIBUTION!!!");
-    } else {
-        println!("Verification succeeded!");
     }
 
-    if COMPRESS_NEW_CHALLENGE == UseCompression::Yes {
-        println!("Don't need to recompress the contribution, please copy `./response` as `./new_challenge`");
-    } else {
-        println!("Verification succeeded! Writing to `./new_challenge`...");
-
-        // Create `./new_challenge` in this directory
-        let writer = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create_new(true)
-            .open("new_challenge")
-            .expect("unable to create `./new_challenge` in this directory");
-
-        // Recomputation stips the public key and uses hashing to link with the previous contibution after decompression
-        writer
-            .set_len(Bls12CeremonyParameters::ACCUMULATOR_BYTE_SIZE as u64)
-            .expect("must make output file large enough");
-
-        let mut writable_map = unsafe {
-            MmapOptions::new()
-                .map_mut(&writer)
-                .expect("unable to create a memory map for output")
-        };
-
-        {
-            (&mut writable_map[0..])
-                .write(response_hash.as_slice())
-                .expect("unable to write a default hash to mmap");
-
-            writable_map
-                .flush()
-                .expect("unable to write hash to `./new_challenge`");
-        }
+    fs::remove_file(&checkpoint_path).expect("unable to remove the verification checkpoint");
 
-        BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::decompress(
-            &response_readable_map,
-            &mut writable_map,
-            CheckForCorrectness::No,
+    println!("Verification succeeded! Writing to `./new_challenge`...");
+
+    // The new challenge is written with the point encoding of the ceremony configuration
+    let new_challenge_compression = params.use_compression();
+
+    // Create `./new_challenge` in this directory
+    let writer = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open("new_challenge")
+        .expect("unable to create `./new_challenge` in this directory");
+
+    let new_challenge_header = match params.file_header() {
+        true => Some(FileHeader::next(
+            response.header.as_ref(),
+            TranscriptFile::Challenge,
+            new_challenge_compression,
+            params,
+        )),
+        false => None,
+    };
+    let new_challenge_offset = body_offset(new_challenge_header.as_ref());
+
+    // Recomputation strips the public key and uses hashing to link with the previous contribution
+    writer
+        .set_len(
+            new_challenge_offset
+                + params.file_byte_size(TranscriptFile::Challenge, new_challenge_compression)
+                    as u64,
         )
-        .expect("must decompress a response for a new challenge");
+        .expect("must make output file large enough");
 
-        writable_map.flush().expect("must flush the memory map");
+    if let Some(header) = new_challenge_header {
+        header
+            .write(&writer)
+            .expect("unable to write the header of `./new_challenge`");
+    }
 
-        let new_challenge_readable_map = writable_map
-            .make_read_only()
-            .expect("must make a map readonly");
+    let mut writable_map = unsafe {
+        MmapOptions::new()
+            .offset(new_challenge_offset)
+            .map_mut(&writer)
+            .expect("unable to create a memory map for output")
+    };
 
-        let recompressed_hash =
-            BatchedAccumulator::<Bls12, Bls12CeremonyParameters>::calculate_hash(
-                &new_challenge_readable_map,
-            );
+    {
+        (&mut writable_map[0..])
+            .write(response_hash.as_slice())
+            .expect("unable to write a default hash to mmap");
 
-        println!("Here's the BLAKE2b hash of the decompressed participant's response as `new_challenge` file:");
+        writable_map
+            .flush()
+            .expect("unable to write hash to `./new_challenge`");
+    }
 
-        for line in recompressed_hash.as_slice().chunks(16) {
-            print!("\t");
-            for section in line.chunks(4) {
-                for b in section {
-                    print!("{:02x}", b);
-                }
-                print!(" ");
+    BatchedAccumulator::<E>::convert(
+        &response_readable_map,
+        &mut writable_map,
+        response.compression,
+        new_challenge_compression,
+        CheckForCorrectness::No,
+        params,
+        &mut progress,
+    )
+    .expect("must convert a response into a new challenge");
+
+    writable_map.flush().expect("must flush the memory map");
+
+    let new_challenge_readable_map = writable_map
+        .make_read_only()
+        .expect("must make a map readonly");
+
+    let new_challenge_hash =
+        BatchedAccumulator::<E>::calculate_hash(&new_challenge_readable_map, &mut progress);
+
+    println!("Here's the BLAKE2b hash of the participant's response as `new_challenge` file:");
+
+    for line in new_challenge_hash.as_slice().chunks(16) {
+        print!("\t");
+        for section in line.chunks(4) {
+            for b in section {
+                print!("{:02x}", b);
             }
-            println!("");
+            print!(" ");
         }
-
-        println!("Done! `./new_challenge` contains the new challenge file. The other files");
-        println!("were left alone.");
+        println!("");
     }
+
+    println!("Done! `./new_challenge` contains the new challenge file. The other files");
+    println!("were left alone.");
 }
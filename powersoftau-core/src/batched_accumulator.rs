@@ -17,7 +17,7 @@ use bellman::pairing::*;
 use blake2::{Blake2b, Digest};
 use byteorder::{BigEndian, ReadBytesExt};
 use generic_array::GenericArray;
-use itertools::Itertools;
+use itertools::{Itertools, MinMaxResult};
 use memmap::{Mmap, MmapMut};
 use rand::chacha::ChaChaRng;
 use rand::{Rand, Rng, SeedableRng};
@@ -26,10 +26,32 @@ use std::io::{self, Read, Write};
 use std::sync::{Arc, Mutex};
 use typenum::consts::U64;
 
+use super::checkpoint::{decode_point, encode_point, VerifiedPowers};
+use super::config::CeremonyMode;
 use super::keypair::*;
 use super::parameters::*;
+use super::progress::{Phase, PointCounter, ProgressSink};
+use super::runtime_parameters::RuntimeCeremonyParameters;
 use super::utils::*;
 
+/// Turns the result of `minmax` over a chunk of indices into its inclusive bounds.
+/// A chunk may consist of a single index when the batch size does not divide the range.
+fn chunk_bounds(chunk: MinMaxResult<usize>) -> Option<(usize, usize)> {
+    match chunk {
+        MinMaxResult::MinMax(start, end) => Some((start, end)),
+        MinMaxResult::OneElement(index) => Some((index, index)),
+        MinMaxResult::NoElements => None,
+    }
+}
+
+/// Error for a range of powers that the chunking produced no elements for
+fn empty_chunk(start: usize, end: usize) -> CeremonyError {
+    CeremonyError::InvalidParameters(format!(
+        "range of powers from {} to {} produced an empty chunk",
+        start, end
+    ))
+}
+
 pub enum AccumulatorState {
     Empty,
     NonEmpty,
@@ -43,7 +65,7 @@ pub enum AccumulatorState {
 ///
 /// * (τ, τ<sup>2</sup>, ..., τ<sup>2<sup>22</sup> - 2</sup>, α, ατ, ατ<sup>2</sup>, ..., ατ<sup>2<sup>21</sup> - 1</sup>, β, βτ, βτ<sup>2</sup>, ..., βτ<sup>2<sup>21</sup> - 1</sup>)<sub>1</sub>
 /// * (β, τ, τ<sup>2</sup>, ..., τ<sup>2<sup>21</sup> - 1</sup>)<sub>2</sub>
-pub struct BatchedAccumulator<E: Engine, P: PowersOfTauParameters> {
+pub struct BatchedAccumulator<E: Engine> {
     /// tau^0, tau^1, tau^2, ..., tau^{TAU_POWERS_G1_LENGTH - 1}
     pub tau_powers_g1: Vec<E::G1Affine>,
     /// tau^0, tau^1, tau^2, ..., tau^{TAU_POWERS_LENGTH - 1}
@@ -56,28 +78,58 @@ pub struct BatchedAccumulator<E: Engine, P: PowersOfTauParameters> {
     pub beta_g2: E::G2Affine,
     /// Hash chain hash
     pub hash: GenericArray<u8, U64>,
-    /// Keep parameters here as a marker
-    marker: std::marker::PhantomData<P>,
+    /// Ceremony parameters that define the layout of the accumulator
+    parameters: RuntimeCeremonyParameters,
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     /// Calcualte the contibution hash from the resulting file. Original powers of tau implementaiton
     /// used a specially formed writer to write to the file and calculate a hash on the fly, but memory-constrained
     /// implementation now writes without a particular order, so plain recalculation at the end
-    /// of the procedure is more efficient
-    pub fn calculate_hash(input_map: &Mmap) -> GenericArray<u8, U64> {
+    /// of the procedure is more efficient.
+    ///
+    /// The hash covers the bytes of a file as they are, so a compressed challenge and an uncompressed one
+    /// holding the same accumulator hash differently. A response is chained to the exact challenge file it
+    /// was computed on, and must be verified against that same file.
+    pub fn calculate_hash(
+        input_map: &Mmap,
+        progress: &mut dyn ProgressSink,
+    ) -> GenericArray<u8, U64> {
         let chunk_size = 1 << 30; // read by 1GB from map
         let mut hasher = Blake2b::default();
+        let mut hashed = 0;
         for chunk in input_map.chunks(chunk_size) {
             Digest::update(&mut hasher, &chunk);
+            hashed += chunk.len() as u64;
+            progress.bytes_hashed(hashed, input_map.len() as u64);
         }
 
         hasher.finalize().into()
     }
+
+    /// Checks that a response was computed on top of the challenge with `challenge_hash`,
+    /// which every response starts with.
+    pub fn check_hash_chain(
+        response_map: &Mmap,
+        challenge_hash: &[u8],
+    ) -> Result<(), CeremonyError> {
+        let response_challenge_hash =
+            response_map
+                .get(0..challenge_hash.len())
+                .ok_or(CeremonyError::SizeMismatch {
+                    expected: challenge_hash.len(),
+                    actual: response_map.len(),
+                })?;
+        if response_challenge_hash != challenge_hash {
+            return Err(CeremonyError::HashChainMismatch);
+        }
+
+        Ok(())
+    }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
-    pub fn empty() -> Self {
+impl<E: Engine> BatchedAccumulator<E> {
+    pub fn empty(parameters: &RuntimeCeremonyParameters) -> Self {
         Self {
             tau_powers_g1: vec![],
             tau_powers_g2: vec![],
@@ -85,40 +137,45 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
             beta_tau_powers_g1: vec![],
             beta_g2: E::G2Affine::zero(),
             hash: blank_hash(),
-            marker: std::marker::PhantomData::<P> {},
+            parameters: parameters.clone(),
         }
     }
+
+    /// Parameters of the ceremony this accumulator belongs to
+    pub fn parameters(&self) -> &RuntimeCeremonyParameters {
+        &self.parameters
+    }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
-    fn g1_size(compression: UseCompression) -> usize {
+impl<E: Engine> BatchedAccumulator<E> {
+    fn g1_size(&self, compression: UseCompression) -> usize {
         match compression {
             UseCompression::Yes => {
-                return P::G1_COMPRESSED_BYTE_SIZE;
+                return self.parameters.g1_compressed_byte_size();
             }
             UseCompression::No => {
-                return P::G1_UNCOMPRESSED_BYTE_SIZE;
+                return self.parameters.g1_uncompressed_byte_size();
             }
         }
     }
 
-    fn g2_size(compression: UseCompression) -> usize {
+    fn g2_size(&self, compression: UseCompression) -> usize {
         match compression {
             UseCompression::Yes => {
-                return P::G2_COMPRESSED_BYTE_SIZE;
+                return self.parameters.g2_compressed_byte_size();
             }
             UseCompression::No => {
-                return P::G2_UNCOMPRESSED_BYTE_SIZE;
+                return self.parameters.g2_uncompressed_byte_size();
             }
         }
     }
 
-    fn get_size(element_type: ElementType, compression: UseCompression) -> usize {
+    fn get_size(&self, element_type: ElementType, compression: UseCompression) -> usize {
         let size = match element_type {
             ElementType::AlphaG1 | ElementType::BetaG1 | ElementType::TauG1 => {
-                Self::g1_size(compression)
+                self.g1_size(compression)
             }
-            ElementType::BetaG2 | ElementType::TauG2 => Self::g2_size(compression),
+            ElementType::BetaG2 | ElementType::TauG2 => self.g2_size(compression),
         };
 
         size
@@ -128,42 +185,44 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
     /// HASH_SIZE bytes for the hash of the contribution
     /// TAU_POWERS_G1_LENGTH of G1 points
     /// TAU_POWERS_LENGTH of G2 points
-    /// TAU_POWERS_LENGTH of G1 points for alpha
-    /// TAU_POWERS_LENGTH of G1 points for beta
-    /// One G2 point for beta
+    /// TAU_POWERS_LENGTH of G1 points for alpha, none in a tau-only ceremony
+    /// TAU_POWERS_LENGTH of G1 points for beta, none in a tau-only ceremony
+    /// One G2 point for beta, none in a tau-only ceremony
     /// Public key appended to the end of file, but it's irrelevant for an accumulator itself
 
     fn calculate_mmap_position(
+        &self,
         index: usize,
         element_type: ElementType,
         compression: UseCompression,
-    ) -> usize {
-        let g1_size = Self::g1_size(compression);
-        let g2_size = Self::g2_size(compression);
-        let required_tau_g1_power = P::TAU_POWERS_G1_LENGTH;
-        let required_power = P::TAU_POWERS_LENGTH;
+    ) -> Result<usize, CeremonyError> {
+        let g1_size = self.g1_size(compression);
+        let g2_size = self.g2_size(compression);
+        let required_tau_g1_power = self.parameters.tau_powers_g1_length();
+        let required_power = self.parameters.tau_powers_length();
+        let alpha_beta_length = self.parameters.alpha_beta_length();
         let position = match element_type {
             ElementType::TauG1 => {
                 let mut position = 0;
                 position += g1_size * index;
-                assert!(
-                    index < P::TAU_POWERS_G1_LENGTH,
-                    "Index of TauG1 element written must not exceed {}, while it's {}",
-                    P::TAU_POWERS_G1_LENGTH,
-                    index
-                );
+                if index >= required_tau_g1_power {
+                    return Err(CeremonyError::InvalidParameters(format!(
+                        "Index of TauG1 element written must not exceed {}, while it's {}",
+                        required_tau_g1_power, index
+                    )));
+                }
 
                 position
             }
             ElementType::TauG2 => {
                 let mut position = 0;
                 position += g1_size * required_tau_g1_power;
-                assert!(
-                    index < P::TAU_POWERS_LENGTH,
-                    "Index of TauG2 element written must not exceed {}, while it's {}",
-                    P::TAU_POWERS_LENGTH,
-                    index
-                );
+                if index >= required_power {
+                    return Err(CeremonyError::InvalidParameters(format!(
+                        "Index of TauG2 element written must not exceed {}, while it's {}",
+                        required_power, index
+                    )));
+                }
                 position += g2_size * index;
 
                 position
@@ -172,12 +231,12 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                 let mut position = 0;
                 position += g1_size * required_tau_g1_power;
                 position += g2_size * required_power;
-                assert!(
-                    index < P::TAU_POWERS_LENGTH,
-                    "Index of AlphaG1 element written must not exceed {}, while it's {}",
-                    P::TAU_POWERS_LENGTH,
-                    index
-                );
+                if index >= alpha_beta_length {
+                    return Err(CeremonyError::InvalidParameters(format!(
+                        "Index of AlphaG1 element written must not exceed {}, while it's {}",
+                        alpha_beta_length, index
+                    )));
+                }
                 position += g1_size * index;
 
                 position
@@ -186,13 +245,13 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                 let mut position = 0;
                 position += g1_size * required_tau_g1_power;
                 position += g2_size * required_power;
-                position += g1_size * required_power;
-                assert!(
-                    index < P::TAU_POWERS_LENGTH,
-                    "Index of BetaG1 element written must not exceed {}, while it's {}",
-                    P::TAU_POWERS_LENGTH,
-                    index
-                );
+                position += g1_size * alpha_beta_length;
+                if index >= alpha_beta_length {
+                    return Err(CeremonyError::InvalidParameters(format!(
+                        "Index of BetaG1 element written must not exceed {}, while it's {}",
+                        alpha_beta_length, index
+                    )));
+                }
                 position += g1_size * index;
 
                 position
@@ -201,19 +260,26 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                 let mut position = 0;
                 position += g1_size * required_tau_g1_power;
                 position += g2_size * required_power;
-                position += g1_size * required_power;
-                position += g1_size * required_power;
+                position += g1_size * alpha_beta_length;
+                position += g1_size * alpha_beta_length;
+                if self.parameters.beta_g2_length() == 0 {
+                    return Err(CeremonyError::InvalidParameters(
+                        "There is no BetaG2 element in a tau-only ceremony".to_string(),
+                    ));
+                }
 
                 position
             }
         };
 
-        position + P::HASH_SIZE
+        Ok(position + self.parameters.hash_size())
     }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     /// Verifies a transformation of the `Accumulator` with the `PublicKey`, given a 64-byte transcript `digest`.
+    /// Returns the first check that the contribution failed as `CeremonyError::Verification`.
+    #[allow(clippy::too_many_arguments)]
     pub fn verify_transformation(
         input_map: &Mmap,
         output_map: &Mmap,
@@ -223,34 +289,88 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         output_is_compressed: UseCompression,
         check_input_for_correctness: CheckForCorrectness,
         check_output_for_correctness: CheckForCorrectness,
-    ) -> bool {
-        use itertools::MinMaxResult::MinMax;
-        assert_eq!(digest.len(), 64);
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        Self::resume_verify_transformation(
+            input_map,
+            output_map,
+            key,
+            digest,
+            input_is_compressed,
+            output_is_compressed,
+            check_input_for_correctness,
+            check_output_for_correctness,
+            parameters,
+            progress,
+            &mut VerifiedPowers::default(),
+            &mut |_| Ok(()),
+        )
+    }
+
+    /// Same as `verify_transformation`, but skips the powers that `verified` records as verified
+    /// by an interrupted run on the same files. After every chunk `verified` is brought up to date
+    /// and passed to `on_chunk`, e.g. to record a checkpoint.
+    #[allow(clippy::too_many_arguments)]
+    pub fn resume_verify_transformation(
+        input_map: &Mmap,
+        output_map: &Mmap,
+        key: &PublicKey<E>,
+        digest: &[u8],
+        input_is_compressed: UseCompression,
+        output_is_compressed: UseCompression,
+        check_input_for_correctness: CheckForCorrectness,
+        check_output_for_correctness: CheckForCorrectness,
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+        verified: &mut VerifiedPowers,
+        on_chunk: &mut dyn FnMut(&VerifiedPowers) -> Result<(), CeremonyError>,
+    ) -> Result<(), CeremonyError> {
+        if digest.len() != 64 {
+            return Err(CeremonyError::InvalidParameters(format!(
+                "transcript digest must be 64 bytes long, while it's {}",
+                digest.len()
+            )));
+        }
+
+        // Alpha and beta are only checked in a full ceremony
+        let full = parameters.mode() == CeremonyMode::Full;
 
-        let tau_g2_s = compute_g2_s::<E>(&digest, &key.tau_g1.0, &key.tau_g1.1, 0);
-        let alpha_g2_s = compute_g2_s::<E>(&digest, &key.alpha_g1.0, &key.alpha_g1.1, 1);
-        let beta_g2_s = compute_g2_s::<E>(&digest, &key.beta_g1.0, &key.beta_g1.1, 2);
+        let tau_g2_s = compute_g2_s::<E>(&digest, &key.tau_g1.0, &key.tau_g1.1, 0)?;
 
         // Check the proofs-of-knowledge for tau/alpha/beta
 
         // g1^s / g1^(s*x) = g2^s / g2^(s*x)
         if !same_ratio(key.tau_g1, (tau_g2_s, key.tau_g2)) {
-            println!("Invalid ratio key.tau_g1, (tau_g2_s, key.tau_g2)");
-            return false;
+            return Err(VerificationError::TauProofOfKnowledge.into());
         }
-        if !same_ratio(key.alpha_g1, (alpha_g2_s, key.alpha_g2)) {
-            println!("Invalid ratio key.alpha_g1, (alpha_g2_s, key.alpha_g2)");
-            return false;
+        let (alpha_g2_s, beta_g2_s) = match full {
+            true => (
+                compute_g2_s::<E>(&digest, &key.alpha_g1.0, &key.alpha_g1.1, 1)?,
+                compute_g2_s::<E>(&digest, &key.beta_g1.0, &key.beta_g1.1, 2)?,
+            ),
+            false => (E::G2Affine::zero(), E::G2Affine::zero()),
+        };
+        if full && !same_ratio(key.alpha_g1, (alpha_g2_s, key.alpha_g2)) {
+            return Err(VerificationError::AlphaProofOfKnowledge.into());
         }
-        if !same_ratio(key.beta_g1, (beta_g2_s, key.beta_g2)) {
-            println!("Invalid ratio key.beta_g1, (beta_g2_s, key.beta_g2)");
-            return false;
+        if full && !same_ratio(key.beta_g1, (beta_g2_s, key.beta_g2)) {
+            return Err(VerificationError::BetaProofOfKnowledge.into());
         }
 
         // Load accumulators AND perform computations
 
-        let mut before = Self::empty();
-        let mut after = Self::empty();
+        let mut before = Self::empty(parameters);
+        let mut after = Self::empty(parameters);
+
+        let challenge_error = |error| VerificationError::Deserialization {
+            file: TranscriptFile::Challenge,
+            error: Box::new(error),
+        };
+        let response_error = |error| VerificationError::Deserialization {
+            file: TranscriptFile::Response,
+            error: Box::new(error),
+        };
 
         // these checks only touch a part of the accumulator, so read two elements
 
@@ -264,7 +384,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     check_input_for_correctness,
                     &input_map,
                 )
-                .expect("must read a first chunk from `challenge`");
+                .map_err(challenge_error)?;
             after
                 .read_chunk(
                     0,
@@ -273,16 +393,14 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     check_output_for_correctness,
                     &output_map,
                 )
-                .expect("must read a first chunk from `response`");
+                .map_err(response_error)?;
 
             // Check the correctness of the generators for tau powers
             if after.tau_powers_g1[0] != E::G1Affine::one() {
-                println!("tau_powers_g1[0] != 1");
-                return false;
+                return Err(VerificationError::TauG1Generator.into());
             }
             if after.tau_powers_g2[0] != E::G2Affine::one() {
-                println!("tau_powers_g2[0] != 1");
-                return false;
+                return Err(VerificationError::TauG2Generator.into());
             }
 
             // Did the participant multiply the previous tau by the new one?
@@ -290,33 +408,35 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                 (before.tau_powers_g1[1], after.tau_powers_g1[1]),
                 (tau_g2_s, key.tau_g2),
             ) {
-                println!("Invalid ratio (before.tau_powers_g1[1], after.tau_powers_g1[1]), (tau_g2_s, key.tau_g2)");
-                return false;
+                return Err(VerificationError::TauMultiplication.into());
             }
 
             // Did the participant multiply the previous alpha by the new one?
-            if !same_ratio(
-                (before.alpha_tau_powers_g1[0], after.alpha_tau_powers_g1[0]),
-                (alpha_g2_s, key.alpha_g2),
-            ) {
-                println!("Invalid ratio (before.alpha_tau_powers_g1[0], after.alpha_tau_powers_g1[0]), (alpha_g2_s, key.alpha_g2)");
-                return false;
+            if full
+                && !same_ratio(
+                    (before.alpha_tau_powers_g1[0], after.alpha_tau_powers_g1[0]),
+                    (alpha_g2_s, key.alpha_g2),
+                )
+            {
+                return Err(VerificationError::AlphaMultiplication.into());
             }
 
             // Did the participant multiply the previous beta by the new one?
-            if !same_ratio(
-                (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]),
-                (beta_g2_s, key.beta_g2),
-            ) {
-                println!("Invalid ratio (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]), (beta_g2_s, key.beta_g2)");
-                return false;
+            if full
+                && !same_ratio(
+                    (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]),
+                    (beta_g2_s, key.beta_g2),
+                )
+            {
+                return Err(VerificationError::BetaMultiplication.into());
             }
-            if !same_ratio(
-                (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]),
-                (before.beta_g2, after.beta_g2),
-            ) {
-                println!("Invalid ratio (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]), (before.beta_g2, after.beta_g2)");
-                return false;
+            if full
+                && !same_ratio(
+                    (before.beta_tau_powers_g1[0], after.beta_tau_powers_g1[0]),
+                    (before.beta_g2, after.beta_g2),
+                )
+            {
+                return Err(VerificationError::BetaG2Multiplication.into());
             }
         }
 
@@ -328,16 +448,52 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         // Read by parts and just verify same ratios. Cause of two fixed variables above with tau_powers_g2_1 = tau_powers_g2_0 ^ s
         // one does not need to care about some overlapping
 
+        let resume_from = verified.verified_powers;
+        if resume_from > parameters.tau_powers_g1_length() {
+            return Err(CeremonyError::Checkpoint(format!(
+                "{} powers are recorded as verified, but there are only {}",
+                resume_from,
+                parameters.tau_powers_g1_length()
+            )));
+        }
+
+        // The boundary points of powers that are already verified come from the checkpoint
         let mut tau_powers_last_first_chunks = vec![E::G1Affine::zero(); 2];
-        for chunk in &(0..P::TAU_POWERS_LENGTH)
+        let boundaries = [
+            parameters.tau_powers_length(),
+            parameters.tau_powers_length() + 1,
+        ];
+        for i in 0..2 {
+            match verified.tau_powers_last_first_chunks[i] {
+                Some(ref encoded) => tau_powers_last_first_chunks[i] = decode_point(encoded)?,
+                None if resume_from >= boundaries[i] => {
+                    return Err(CeremonyError::Checkpoint(
+                        "a verified boundary point of TauG1 is missing".to_string(),
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        let mut progress = PointCounter::new(progress, Phase::Verify, parameters);
+        if resume_from > 0 {
+            progress.advance_powers(resume_from.min(parameters.tau_powers_length()));
+            progress.advance(
+                ElementType::TauG1,
+                resume_from.saturating_sub(parameters.tau_powers_length()),
+            );
+        }
+
+        for chunk in &(resume_from.min(parameters.tau_powers_length())
+            ..parameters.tau_powers_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.empirical_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 // extra 1 to ensure intersection between chunks and ensure we don't overflow
                 let size = end - start
                     + 1
-                    + if end == P::TAU_POWERS_LENGTH - 1 {
+                    + if end == parameters.tau_powers_length() - 1 {
                         0
                     } else {
                         1
@@ -350,10 +506,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                         check_input_for_correctness,
                         &input_map,
                     )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from `challenge`",
-                        start, end
-                    ));
+                    .map_err(challenge_error)?;
                 after
                     .read_chunk(
                         start,
@@ -362,58 +515,71 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                         check_output_for_correctness,
                         &output_map,
                     )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from `response`",
-                        start, end
-                    ));
+                    .map_err(response_error)?;
+
+                let powers_ratio = |element_type| VerificationError::PowersRatio {
+                    element_type,
+                    start,
+                    end,
+                };
 
                 // Are the powers of tau correct?
                 if !same_ratio(
-                    power_pairs(&after.tau_powers_g1),
+                    power_pairs(&after.tau_powers_g1)?,
                     (tau_powers_g2_0, tau_powers_g2_1),
                 ) {
-                    println!("Invalid ratio power_pairs(&after.tau_powers_g1), (tau_powers_g2_0, tau_powers_g2_1)");
-                    return false;
+                    return Err(powers_ratio(ElementType::TauG1).into());
                 }
                 if !same_ratio(
-                    power_pairs(&after.tau_powers_g2),
+                    power_pairs(&after.tau_powers_g2)?,
                     (tau_powers_g1_0, tau_powers_g1_1),
                 ) {
-                    println!("Invalid ratio power_pairs(&after.tau_powers_g2), (tau_powers_g1_0, tau_powers_g1_1)");
-                    return false;
+                    return Err(powers_ratio(ElementType::TauG2).into());
                 }
-                if !same_ratio(
-                    power_pairs(&after.alpha_tau_powers_g1),
-                    (tau_powers_g2_0, tau_powers_g2_1),
-                ) {
-                    println!("Invalid ratio power_pairs(&after.alpha_tau_powers_g1), (tau_powers_g2_0, tau_powers_g2_1)");
-                    return false;
+                if full
+                    && !same_ratio(
+                        power_pairs(&after.alpha_tau_powers_g1)?,
+                        (tau_powers_g2_0, tau_powers_g2_1),
+                    )
+                {
+                    return Err(powers_ratio(ElementType::AlphaG1).into());
                 }
-                if !same_ratio(
-                    power_pairs(&after.beta_tau_powers_g1),
-                    (tau_powers_g2_0, tau_powers_g2_1),
-                ) {
-                    println!("Invalid ratio power_pairs(&after.beta_tau_powers_g1), (tau_powers_g2_0, tau_powers_g2_1)");
-                    return false;
+                if full
+                    && !same_ratio(
+                        power_pairs(&after.beta_tau_powers_g1)?,
+                        (tau_powers_g2_0, tau_powers_g2_1),
+                    )
+                {
+                    return Err(powers_ratio(ElementType::BetaG1).into());
                 }
-                if end == P::TAU_POWERS_LENGTH - 1 {
+                if end == parameters.tau_powers_length() - 1 {
                     tau_powers_last_first_chunks[0] = after.tau_powers_g1[size - 1];
+                    verified.tau_powers_last_first_chunks[0] =
+                        Some(encode_point(&tau_powers_last_first_chunks[0]));
                 }
-                println!("Done processing {} powers of tau", end);
+                verified.verified_powers = end + 1;
+                on_chunk(verified)?;
+
+                progress.advance_powers(end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(VerificationError::ChunkRange {
+                    start: 0,
+                    end: parameters.tau_powers_length(),
+                }
+                .into());
             }
         }
 
-        for chunk in &(P::TAU_POWERS_LENGTH..P::TAU_POWERS_G1_LENGTH)
+        for chunk in &(resume_from.max(parameters.tau_powers_length())
+            ..parameters.tau_powers_g1_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.tau_g1_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 // extra 1 to ensure intersection between chunks and ensure we don't overflow
                 let size = end - start
                     + 1
-                    + if end == P::TAU_POWERS_G1_LENGTH - 1 {
+                    + if end == parameters.tau_powers_g1_length() - 1 {
                         0
                     } else {
                         1
@@ -426,10 +592,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                         check_input_for_correctness,
                         &input_map,
                     )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from `challenge`",
-                        start, end
-                    ));
+                    .map_err(challenge_error)?;
                 after
                     .read_chunk(
                         start,
@@ -438,17 +601,14 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                         check_output_for_correctness,
                         &output_map,
                     )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from `response`",
-                        start, end
-                    ));
+                    .map_err(response_error)?;
 
-                assert_eq!(
+                debug_assert_eq!(
                     before.tau_powers_g2.len(),
                     0,
                     "during rest of tau g1 generation tau g2 must be empty"
                 );
-                assert_eq!(
+                debug_assert_eq!(
                     after.tau_powers_g2.len(),
                     0,
                     "during rest of tau g1 generation tau g2 must be empty"
@@ -456,99 +616,324 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
 
                 // Are the powers of tau correct?
                 if !same_ratio(
-                    power_pairs(&after.tau_powers_g1),
+                    power_pairs(&after.tau_powers_g1)?,
                     (tau_powers_g2_0, tau_powers_g2_1),
                 ) {
-                    println!("Invalid ratio power_pairs(&after.tau_powers_g1), (tau_powers_g2_0, tau_powers_g2_1) in extra TauG1 contribution");
-                    return false;
+                    return Err(VerificationError::PowersRatio {
+                        element_type: ElementType::TauG1,
+                        start,
+                        end,
+                    }
+                    .into());
                 }
-                if start == P::TAU_POWERS_LENGTH {
+                if start == parameters.tau_powers_length() {
                     tau_powers_last_first_chunks[1] = after.tau_powers_g1[0];
+                    verified.tau_powers_last_first_chunks[1] =
+                        Some(encode_point(&tau_powers_last_first_chunks[1]));
                 }
-                println!("Done processing {} powers of tau", end);
+                verified.verified_powers = end + 1;
+                on_chunk(verified)?;
+
+                progress.advance(ElementType::TauG1, end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(VerificationError::ChunkRange {
+                    start: parameters.tau_powers_length(),
+                    end: parameters.tau_powers_g1_length(),
+                }
+                .into());
             }
         }
         if !same_ratio(
-            power_pairs(&tau_powers_last_first_chunks),
+            power_pairs(&tau_powers_last_first_chunks)?,
             (tau_powers_g2_0, tau_powers_g2_1),
         ) {
-            println!("Invalid ratio power_pairs(&after.tau_powers_g1), (tau_powers_g2_0, tau_powers_g2_1) in TauG1 contribution intersection");
+            return Err(VerificationError::TauG1Boundary.into());
         }
-        true
+        Ok(())
     }
 
     pub fn decompress(
         input_map: &Mmap,
         output_map: &mut MmapMut,
         check_input_for_correctness: CheckForCorrectness,
-    ) -> io::Result<()> {
-        use itertools::MinMaxResult::MinMax;
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        Self::convert(
+            input_map,
+            output_map,
+            UseCompression::Yes,
+            UseCompression::No,
+            check_input_for_correctness,
+            parameters,
+            progress,
+        )
+    }
+
+    /// Writes the points of the accumulator in `input_map` to `output_map` with `output_compression`,
+    /// e.g. to derive a new challenge from a verified response. The hash at the start of the output
+    /// is left alone, and so is a public key following the points of the input. Points encoded the
+    /// same way on both sides are copied as they are, after `check_points` if
+    /// `check_input_for_correctness` asks for it.
+    pub fn convert(
+        input_map: &Mmap,
+        output_map: &mut MmapMut,
+        input_compression: UseCompression,
+        output_compression: UseCompression,
+        check_input_for_correctness: CheckForCorrectness,
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        let phase = match output_compression {
+            UseCompression::Yes => Phase::Compress,
+            UseCompression::No => Phase::Decompress,
+        };
+
+        if input_compression == output_compression {
+            // Points are encoded the same way on both sides, so they are copied as they are
+            if check_input_for_correctness == CheckForCorrectness::Yes {
+                Self::check_points(input_map, input_compression, parameters, progress)?;
+            }
+            let points = parameters.hash_size()
+                ..parameters.file_byte_size(TranscriptFile::Challenge, output_compression);
+            let input_points =
+                input_map
+                    .get(points.clone())
+                    .ok_or(CeremonyError::SizeMismatch {
+                        expected: points.end,
+                        actual: input_map.len(),
+                    })?;
+            let actual = output_map.len();
+            output_map
+                .get_mut(points.clone())
+                .ok_or(CeremonyError::SizeMismatch {
+                    expected: points.end,
+                    actual,
+                })?
+                .copy_from_slice(input_points);
+
+            let mut progress = PointCounter::new(progress, phase, parameters);
+            progress.advance_powers(parameters.tau_powers_length());
+            progress.advance(
+                ElementType::TauG1,
+                parameters.tau_powers_g1_length() - parameters.tau_powers_length(),
+            );
+
+            return Ok(());
+        }
 
-        let mut accumulator = Self::empty();
+        let mut accumulator = Self::empty(parameters);
 
-        for chunk in &(0..P::TAU_POWERS_LENGTH)
+        let mut progress = PointCounter::new(progress, phase, parameters);
+        for chunk in &(0..parameters.tau_powers_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.empirical_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
-                accumulator
-                    .read_chunk(
-                        start,
-                        size,
-                        UseCompression::Yes,
-                        check_input_for_correctness,
-                        &input_map,
-                    )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from source of decompression",
-                        start, end
-                    ));
-                accumulator.write_chunk(start, UseCompression::No, output_map)?;
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_compression,
+                    check_input_for_correctness,
+                    &input_map,
+                )?;
+                accumulator.write_chunk(start, output_compression, output_map)?;
+                progress.advance_powers(end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(0, parameters.tau_powers_length()));
             }
         }
 
-        for chunk in &(P::TAU_POWERS_LENGTH..P::TAU_POWERS_G1_LENGTH)
+        for chunk in &(parameters.tau_powers_length()..parameters.tau_powers_g1_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.tau_g1_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
-                accumulator
-                    .read_chunk(
-                        start,
-                        size,
-                        UseCompression::Yes,
-                        check_input_for_correctness,
-                        &input_map,
-                    )
-                    .expect(&format!(
-                        "must read a chunk from {} to {} from source of decompression",
-                        start, end
-                    ));
-                assert_eq!(
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_compression,
+                    check_input_for_correctness,
+                    &input_map,
+                )?;
+                debug_assert_eq!(
                     accumulator.tau_powers_g2.len(),
                     0,
                     "during rest of tau g1 generation tau g2 must be empty"
                 );
-                assert_eq!(
+                debug_assert_eq!(
                     accumulator.alpha_tau_powers_g1.len(),
                     0,
                     "during rest of tau g1 generation alpha*tau in g1 must be empty"
                 );
-                assert_eq!(
+                debug_assert_eq!(
                     accumulator.beta_tau_powers_g1.len(),
                     0,
                     "during rest of tau g1 generation beta*tau in g1 must be empty"
                 );
 
-                accumulator.write_chunk(start, UseCompression::No, output_map)?;
+                accumulator.write_chunk(start, output_compression, output_map)?;
+                progress.advance(ElementType::TauG1, end - start + 1);
+            } else {
+                return Err(empty_chunk(
+                    parameters.tau_powers_length(),
+                    parameters.tau_powers_g1_length(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes every point of the accumulator in `input_map` with `CheckForCorrectness::Yes`, so
+    /// that each one has to be on the curve, in the prime order subgroup and not at infinity.
+    pub fn check_points(
+        input_map: &Mmap,
+        input_compression: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        let mut accumulator = Self::empty(parameters);
+
+        let mut progress = PointCounter::new(progress, Phase::Verify, parameters);
+        for chunk in &(0..parameters.tau_powers_length())
+            .into_iter()
+            .chunks(parameters.empirical_batch_size())
+        {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
+                let size = end - start + 1;
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_compression,
+                    CheckForCorrectness::Yes,
+                    input_map,
+                )?;
+                progress.advance_powers(size);
+            } else {
+                return Err(empty_chunk(0, parameters.tau_powers_length()));
+            }
+        }
+
+        for chunk in &(parameters.tau_powers_length()..parameters.tau_powers_g1_length())
+            .into_iter()
+            .chunks(parameters.tau_g1_batch_size())
+        {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
+                let size = end - start + 1;
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_compression,
+                    CheckForCorrectness::Yes,
+                    input_map,
+                )?;
+                progress.advance(ElementType::TauG1, size);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(
+                    parameters.tau_powers_length(),
+                    parameters.tau_powers_g1_length(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the accumulator in `input_map` for 2^n powers as an accumulator for 2^m powers with
+    /// m < n, the power of `reduced_parameters`: the first 2^(m+1) - 1 powers of tau in G1, the
+    /// first 2^m of the others, and beta in G2. The hash at the start of the output is left alone.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reduce_power(
+        input_map: &Mmap,
+        output_map: &mut MmapMut,
+        input_compression: UseCompression,
+        output_compression: UseCompression,
+        check_input_for_correctness: CheckForCorrectness,
+        parameters: &RuntimeCeremonyParameters,
+        reduced_parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        if reduced_parameters.curve() != parameters.curve()
+            || reduced_parameters.required_power() >= parameters.required_power()
+        {
+            return Err(CeremonyError::InvalidParameters(format!(
+                "an accumulator for 2^{} powers on {} can not be reduced to 2^{} powers on {}",
+                parameters.required_power(),
+                parameters.curve(),
+                reduced_parameters.required_power(),
+                reduced_parameters.curve()
+            )));
+        }
+
+        // Points are read at their position in the input, and written through an accumulator of
+        // the reduced layout
+        let mut accumulator = Self::empty(parameters);
+        let mut reduced = Self::empty(reduced_parameters);
+
+        let mut progress = PointCounter::new(progress, Phase::Reduce, reduced_parameters);
+        for chunk in &(0..reduced_parameters.tau_powers_length())
+            .into_iter()
+            .chunks(reduced_parameters.empirical_batch_size())
+        {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
+                let size = end - start + 1;
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_compression,
+                    check_input_for_correctness,
+                    &input_map,
+                )?;
+                reduced.tau_powers_g1 = std::mem::take(&mut accumulator.tau_powers_g1);
+                reduced.tau_powers_g2 = std::mem::take(&mut accumulator.tau_powers_g2);
+                reduced.alpha_tau_powers_g1 = std::mem::take(&mut accumulator.alpha_tau_powers_g1);
+                reduced.beta_tau_powers_g1 = std::mem::take(&mut accumulator.beta_tau_powers_g1);
+                reduced.beta_g2 = accumulator.beta_g2;
+                reduced.write_chunk(start, output_compression, output_map)?;
+                progress.advance_powers(size);
+            } else {
+                return Err(empty_chunk(0, reduced_parameters.tau_powers_length()));
+            }
+        }
+
+        // The input holds all the other points at these indices as well, only tau in G1 is read
+        for chunk in &(reduced_parameters.tau_powers_length()
+            ..reduced_parameters.tau_powers_g1_length())
+            .into_iter()
+            .chunks(reduced_parameters.tau_g1_batch_size())
+        {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
+                let size = end - start + 1;
+                reduced.tau_powers_g1 = match input_compression {
+                    UseCompression::Yes => accumulator
+                        .read_points_chunk::<<E::G1Affine as CurveAffine>::Compressed>(
+                            start,
+                            size,
+                            ElementType::TauG1,
+                            input_compression,
+                            check_input_for_correctness,
+                            &input_map,
+                        )?,
+                    UseCompression::No => accumulator
+                        .read_points_chunk::<<E::G1Affine as CurveAffine>::Uncompressed>(
+                            start,
+                            size,
+                            ElementType::TauG1,
+                            input_compression,
+                            check_input_for_correctness,
+                            &input_map,
+                        )?,
+                };
+                reduced.write_all(start, output_compression, ElementType::TauG1, output_map)?;
+                progress.advance(ElementType::TauG1, size);
+            } else {
+                return Err(empty_chunk(
+                    reduced_parameters.tau_powers_length(),
+                    reduced_parameters.tau_powers_g1_length(),
+                ));
             }
         }
 
@@ -556,7 +941,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
     }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     pub fn read_chunk(
         &mut self,
         from: usize,
@@ -564,7 +949,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         compression: UseCompression,
         checked: CheckForCorrectness,
         input_map: &Mmap,
-    ) -> Result<(), DeserializationError> {
+    ) -> Result<(), CeremonyError> {
         self.tau_powers_g1 = match compression {
             UseCompression::Yes => self
                 .read_points_chunk::<<E::G1Affine as CurveAffine>::Compressed>(
@@ -650,6 +1035,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         };
 
         self.beta_g2 = match compression {
+            _ if self.parameters.beta_g2_length() == 0 => E::G2Affine::zero(),
             UseCompression::Yes => {
                 let points = self.read_points_chunk::<<E::G2Affine as CurveAffine>::Compressed>(
                     0,
@@ -687,7 +1073,7 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         compression: UseCompression,
         checked: CheckForCorrectness,
         input_map: &Mmap,
-    ) -> Result<Vec<ENC::Affine>, DeserializationError> {
+    ) -> Result<Vec<ENC::Affine>, CeremonyError> {
         // Read the encoded elements
         let mut res = vec![ENC::empty(); size];
 
@@ -695,24 +1081,36 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
             let index = from + i;
             match element_type {
                 ElementType::TauG1 => {
-                    if index >= P::TAU_POWERS_G1_LENGTH {
+                    if index >= self.parameters.tau_powers_g1_length() {
+                        return Ok(vec![]);
+                    }
+                }
+                ElementType::TauG2 => {
+                    if index >= self.parameters.tau_powers_length() {
+                        return Ok(vec![]);
+                    }
+                }
+                ElementType::AlphaG1 | ElementType::BetaG1 => {
+                    if index >= self.parameters.alpha_beta_length() {
                         return Ok(vec![]);
                     }
                 }
-                ElementType::AlphaG1
-                | ElementType::BetaG1
-                | ElementType::BetaG2
-                | ElementType::TauG2 => {
-                    if index >= P::TAU_POWERS_LENGTH {
+                ElementType::BetaG2 => {
+                    if self.parameters.beta_g2_length() == 0
+                        || index >= self.parameters.tau_powers_length()
+                    {
                         return Ok(vec![]);
                     }
                 }
             };
-            let position = Self::calculate_mmap_position(index, element_type, compression);
-            let element_size = Self::get_size(element_type, compression);
-            let mut memory_slice = input_map
-                .get(position..position + element_size)
-                .expect("must read point data from file");
+            let position = self.calculate_mmap_position(index, element_type, compression)?;
+            let element_size = self.get_size(element_type, compression);
+            let mut memory_slice = input_map.get(position..position + element_size).ok_or(
+                CeremonyError::SizeMismatch {
+                    expected: position + element_size,
+                    actual: input_map.len(),
+                },
+            )?;
             memory_slice.read_exact(encoded.as_mut())?;
         }
 
@@ -729,15 +1127,16 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         let decoding_error = Arc::new(Mutex::new(None));
 
         crossbeam::scope(|scope| {
-            for (source, target) in res
+            for (chunk_index, (source, target)) in res
                 .chunks(chunk_size)
                 .zip(res_affine.chunks_mut(chunk_size))
+                .enumerate()
             {
                 let decoding_error = decoding_error.clone();
 
                 scope.spawn(move || {
-                    assert_eq!(source.len(), target.len());
-                    for (source, target) in source.iter().zip(target.iter_mut()) {
+                    debug_assert_eq!(source.len(), target.len());
+                    for (i, (source, target)) in source.iter().zip(target.iter_mut()).enumerate() {
                         match {
                             // If we're a participant, we don't need to check all of the
                             // elements in the accumulator, which saves a lot of time.
@@ -767,7 +1166,9 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                                 *target = source;
                             }
                             Err(e) => {
-                                *decoding_error.lock().unwrap() = Some(e);
+                                let index = from + chunk_index * chunk_size + i;
+                                *decoding_error.lock().unwrap() =
+                                    Some(CeremonyError::element(element_type, index, e));
                             }
                         }
                     }
@@ -775,33 +1176,37 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
             }
         });
 
+        if let Some(e) = Arc::try_unwrap(decoding_error)
+            .unwrap()
+            .into_inner()
+            .unwrap()
+        {
+            return Err(e);
+        }
+
         // extra check that during the decompression all the the initially initialized infinitu points
         // were replaced with something
-        for decoded in res_affine.iter() {
+        for (i, decoded) in res_affine.iter().enumerate() {
             if decoded.is_zero() {
-                return Err(DeserializationError::PointAtInfinity);
+                return Err(CeremonyError::PointAtInfinity {
+                    element_type,
+                    index: from + i,
+                });
             }
         }
 
-        match Arc::try_unwrap(decoding_error)
-            .unwrap()
-            .into_inner()
-            .unwrap()
-        {
-            Some(e) => Err(e),
-            None => Ok(res_affine),
-        }
+        Ok(res_affine)
     }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     fn write_all(
         &mut self,
         chunk_start: usize,
         compression: UseCompression,
         element_type: ElementType,
         output_map: &mut MmapMut,
-    ) -> io::Result<()> {
+    ) -> Result<(), CeremonyError> {
         match element_type {
             ElementType::TauG1 => {
                 for (i, c) in self.tau_powers_g1.clone().iter().enumerate() {
@@ -851,36 +1256,51 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         compression: UseCompression,
         element_type: ElementType,
         output_map: &mut MmapMut,
-    ) -> io::Result<()>
+    ) -> Result<(), CeremonyError>
     where
         C: CurveAffine<Engine = E, Scalar = E::Fr>,
     {
         match element_type {
             ElementType::TauG1 => {
-                if index >= P::TAU_POWERS_G1_LENGTH {
+                if index >= self.parameters.tau_powers_g1_length() {
                     return Ok(());
                 }
             }
-            ElementType::AlphaG1
-            | ElementType::BetaG1
-            | ElementType::BetaG2
-            | ElementType::TauG2 => {
-                if index >= P::TAU_POWERS_LENGTH {
+            ElementType::TauG2 => {
+                if index >= self.parameters.tau_powers_length() {
+                    return Ok(());
+                }
+            }
+            ElementType::AlphaG1 | ElementType::BetaG1 => {
+                if index >= self.parameters.alpha_beta_length() {
+                    return Ok(());
+                }
+            }
+            ElementType::BetaG2 => {
+                if self.parameters.beta_g2_length() == 0
+                    || index >= self.parameters.tau_powers_length()
+                {
                     return Ok(());
                 }
             }
         };
 
+        let position = self.calculate_mmap_position(index, element_type, compression)?;
+        let size = self.get_size(element_type, compression);
+        let map_size = output_map.len();
+        let target =
+            output_map
+                .get_mut(position..position + size)
+                .ok_or(CeremonyError::SizeMismatch {
+                    expected: position + size,
+                    actual: map_size,
+                })?;
         match compression {
             UseCompression::Yes => {
-                let position = Self::calculate_mmap_position(index, element_type, compression);
-                // let size = self.get_size(element_type, compression);
-                (&mut output_map[position..]).write(p.into_compressed().as_ref())?;
+                target.copy_from_slice(p.into_compressed().as_ref());
             }
             UseCompression::No => {
-                let position = Self::calculate_mmap_position(index, element_type, compression);
-                // let size = self.get_size(element_type, compression);
-                (&mut output_map[position..]).write(p.into_uncompressed().as_ref())?;
+                target.copy_from_slice(p.into_uncompressed().as_ref());
             }
         };
 
@@ -893,9 +1313,9 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         chunk_start: usize,
         compression: UseCompression,
         output_map: &mut MmapMut,
-    ) -> io::Result<()> {
+    ) -> Result<(), CeremonyError> {
         self.write_all(chunk_start, compression, ElementType::TauG1, output_map)?;
-        if chunk_start < P::TAU_POWERS_LENGTH {
+        if chunk_start < self.parameters.tau_powers_length() {
             self.write_all(chunk_start, compression, ElementType::TauG2, output_map)?;
             self.write_all(chunk_start, compression, ElementType::AlphaG1, output_map)?;
             self.write_all(chunk_start, compression, ElementType::BetaG1, output_map)?;
@@ -906,13 +1326,14 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
     }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     /// Transforms the accumulator with a private key.
     /// Due to large amount of data in a previous accumulator even in the compressed form
     /// this function can now work on compressed input. Output can be made in any form
     /// WARNING: Contributor does not have to check that values from challenge file were serialized
     /// corrently, but we may want to enforce it if a ceremony coordinator does not recompress the previous
     /// contribution into the new challenge file
+    #[allow(clippy::too_many_arguments)]
     pub fn transform(
         input_map: &Mmap,
         output_map: &mut MmapMut,
@@ -920,17 +1341,54 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
         compress_the_output: UseCompression,
         check_input_for_correctness: CheckForCorrectness,
         key: &PrivateKey<E>,
-    ) -> io::Result<()> {
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        Self::resume_transform(
+            input_map,
+            output_map,
+            input_is_compressed,
+            compress_the_output,
+            check_input_for_correctness,
+            key,
+            parameters,
+            progress,
+            0,
+            &mut |_| Ok(()),
+        )
+    }
+
+    /// Same as `transform`, but skips the powers below `resume_from` that an interrupted run with
+    /// the same key has already written. Chunks are written in order, and once a chunk is flushed
+    /// `on_chunk` is called with the number of powers written so far, e.g. to record a checkpoint.
+    #[allow(clippy::too_many_arguments)]
+    pub fn resume_transform(
+        input_map: &Mmap,
+        output_map: &mut MmapMut,
+        input_is_compressed: UseCompression,
+        compress_the_output: UseCompression,
+        check_input_for_correctness: CheckForCorrectness,
+        key: &PrivateKey<E>,
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+        resume_from: usize,
+        on_chunk: &mut dyn FnMut(usize) -> Result<(), CeremonyError>,
+    ) -> Result<(), CeremonyError> {
         /// Exponentiate a large number of points, with an optional coefficient to be applied to the
         /// exponent.
         fn batch_exp<EE: Engine, C: CurveAffine<Engine = EE, Scalar = EE::Fr>>(
             bases: &mut [C],
             exp: &[C::Scalar],
             coeff: Option<&C::Scalar>,
-        ) {
-            assert_eq!(bases.len(), exp.len());
+            element_type: ElementType,
+            start: usize,
+        ) -> Result<(), CeremonyError> {
+            debug_assert_eq!(bases.len(), exp.len());
             let mut projective = vec![C::Projective::zero(); bases.len()];
-            let chunk_size = bases.len() / num_cpus::get();
+            let mut chunk_size = bases.len() / num_cpus::get();
+            if chunk_size == 0 {
+                chunk_size = 1;
+            }
 
             // Perform wNAF over multiple cores, placing results into `projective`.
             crossbeam::scope(|scope| {
@@ -967,38 +1425,52 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
             });
 
             // Turn it all back into affine points
-            for (projective, affine) in projective.iter().zip(bases.iter_mut()) {
+            // A contribution that produces a point at infinity has to be re-run
+            for (i, (projective, affine)) in projective.iter().zip(bases.iter_mut()).enumerate() {
                 *affine = projective.into_affine();
-                assert!(
-                    !affine.is_zero(),
-                    "your contribution happed to produce a point at infinity, please re-run"
-                );
+                if affine.is_zero() {
+                    return Err(CeremonyError::PointAtInfinity {
+                        element_type,
+                        index: start + i,
+                    });
+                }
             }
+
+            Ok(())
         }
 
-        let mut accumulator = Self::empty();
+        let mut accumulator = Self::empty(parameters);
 
-        use itertools::MinMaxResult::MinMax;
+        let mut progress = PointCounter::new(progress, Phase::Transform, parameters);
+        if resume_from > 0 {
+            progress.advance_powers(resume_from.min(parameters.tau_powers_length()));
+            progress.advance(
+                ElementType::TauG1,
+                resume_from.saturating_sub(parameters.tau_powers_length()),
+            );
+        }
 
-        for chunk in &(0..P::TAU_POWERS_LENGTH)
+        for chunk in &(resume_from.min(parameters.tau_powers_length())
+            ..parameters.tau_powers_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.empirical_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
-                accumulator
-                    .read_chunk(
-                        start,
-                        size,
-                        input_is_compressed,
-                        check_input_for_correctness,
-                        &input_map,
-                    )
-                    .expect("must read a first chunk");
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_is_compressed,
+                    check_input_for_correctness,
+                    &input_map,
+                )?;
 
                 // Construct the powers of tau
                 let mut taupowers = vec![E::Fr::zero(); size];
-                let chunk_size = size / num_cpus::get();
+                let mut chunk_size = size / num_cpus::get();
+                if chunk_size == 0 {
+                    chunk_size = 1;
+                }
 
                 // Construct exponents in parallel
                 crossbeam::scope(|scope| {
@@ -1014,47 +1486,68 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     }
                 });
 
-                batch_exp::<E, _>(&mut accumulator.tau_powers_g1, &taupowers[0..], None);
-                batch_exp::<E, _>(&mut accumulator.tau_powers_g2, &taupowers[0..], None);
                 batch_exp::<E, _>(
-                    &mut accumulator.alpha_tau_powers_g1,
+                    &mut accumulator.tau_powers_g1,
                     &taupowers[0..],
-                    Some(&key.alpha),
-                );
+                    None,
+                    ElementType::TauG1,
+                    start,
+                )?;
                 batch_exp::<E, _>(
-                    &mut accumulator.beta_tau_powers_g1,
+                    &mut accumulator.tau_powers_g2,
                     &taupowers[0..],
-                    Some(&key.beta),
-                );
-                accumulator.beta_g2 = accumulator.beta_g2.mul(key.beta).into_affine();
-                assert!(
-                    !accumulator.beta_g2.is_zero(),
-                    "your contribution happed to produce a point at infinity, please re-run"
-                );
+                    None,
+                    ElementType::TauG2,
+                    start,
+                )?;
+                if parameters.mode() == CeremonyMode::Full {
+                    batch_exp::<E, _>(
+                        &mut accumulator.alpha_tau_powers_g1,
+                        &taupowers[0..],
+                        Some(&key.alpha),
+                        ElementType::AlphaG1,
+                        start,
+                    )?;
+                    batch_exp::<E, _>(
+                        &mut accumulator.beta_tau_powers_g1,
+                        &taupowers[0..],
+                        Some(&key.beta),
+                        ElementType::BetaG1,
+                        start,
+                    )?;
+                    accumulator.beta_g2 = accumulator.beta_g2.mul(key.beta).into_affine();
+                    if accumulator.beta_g2.is_zero() {
+                        return Err(CeremonyError::PointAtInfinity {
+                            element_type: ElementType::BetaG2,
+                            index: 0,
+                        });
+                    }
+                }
                 accumulator.write_chunk(start, compress_the_output, output_map)?;
+                output_map.flush()?;
+                on_chunk(end + 1)?;
 
-                println!("Done processing {} powers of tau", end);
+                progress.advance_powers(end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(0, parameters.tau_powers_length()));
             }
         }
 
-        for chunk in &(P::TAU_POWERS_LENGTH..P::TAU_POWERS_G1_LENGTH)
+        for chunk in &(resume_from.max(parameters.tau_powers_length())
+            ..parameters.tau_powers_g1_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.tau_g1_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
-                accumulator
-                    .read_chunk(
-                        start,
-                        size,
-                        input_is_compressed,
-                        check_input_for_correctness,
-                        &input_map,
-                    )
-                    .expect("must read a first chunk");
-                assert_eq!(
+                accumulator.read_chunk(
+                    start,
+                    size,
+                    input_is_compressed,
+                    check_input_for_correctness,
+                    &input_map,
+                )?;
+                debug_assert_eq!(
                     accumulator.tau_powers_g2.len(),
                     0,
                     "during rest of tau g1 generation tau g2 must be empty"
@@ -1062,7 +1555,10 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
 
                 // Construct the powers of tau
                 let mut taupowers = vec![E::Fr::zero(); size];
-                let chunk_size = size / num_cpus::get();
+                let mut chunk_size = size / num_cpus::get();
+                if chunk_size == 0 {
+                    chunk_size = 1;
+                }
 
                 // Construct exponents in parallel
                 crossbeam::scope(|scope| {
@@ -1078,17 +1574,32 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     }
                 });
 
-                batch_exp::<E, _>(&mut accumulator.tau_powers_g1, &taupowers[0..], None);
-                accumulator.beta_g2 = accumulator.beta_g2.mul(key.beta).into_affine();
-                assert!(
-                    !accumulator.beta_g2.is_zero(),
-                    "your contribution happed to produce a point at infinity, please re-run"
-                );
+                batch_exp::<E, _>(
+                    &mut accumulator.tau_powers_g1,
+                    &taupowers[0..],
+                    None,
+                    ElementType::TauG1,
+                    start,
+                )?;
+                if parameters.mode() == CeremonyMode::Full {
+                    accumulator.beta_g2 = accumulator.beta_g2.mul(key.beta).into_affine();
+                    if accumulator.beta_g2.is_zero() {
+                        return Err(CeremonyError::PointAtInfinity {
+                            element_type: ElementType::BetaG2,
+                            index: 0,
+                        });
+                    }
+                }
                 accumulator.write_chunk(start, compress_the_output, output_map)?;
+                output_map.flush()?;
+                on_chunk(end + 1)?;
 
-                println!("Done processing {} powers of tau", end);
+                progress.advance(ElementType::TauG1, end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(
+                    parameters.tau_powers_length(),
+                    parameters.tau_powers_g1_length(),
+                ));
             }
         }
 
@@ -1096,19 +1607,20 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
     }
 }
 
-impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
+impl<E: Engine> BatchedAccumulator<E> {
     /// Transforms the accumulator with a private key.
     pub fn generate_initial(
         output_map: &mut MmapMut,
         compress_the_output: UseCompression,
-    ) -> io::Result<()> {
-        use itertools::MinMaxResult::MinMax;
-
-        for chunk in &(0..P::TAU_POWERS_LENGTH)
+        parameters: &RuntimeCeremonyParameters,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        let mut progress = PointCounter::new(progress, Phase::GenerateInitial, parameters);
+        for chunk in &(0..parameters.tau_powers_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.empirical_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
                 let mut accumulator = Self {
                     tau_powers_g1: vec![E::G1Affine::one(); size],
@@ -1117,21 +1629,21 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     beta_tau_powers_g1: vec![E::G1Affine::one(); size],
                     beta_g2: E::G2Affine::one(),
                     hash: blank_hash(),
-                    marker: std::marker::PhantomData::<P> {},
+                    parameters: parameters.clone(),
                 };
 
                 accumulator.write_chunk(start, compress_the_output, output_map)?;
-                println!("Done processing {} powers of tau", end);
+                progress.advance_powers(end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(0, parameters.tau_powers_length()));
             }
         }
 
-        for chunk in &(P::TAU_POWERS_LENGTH..P::TAU_POWERS_G1_LENGTH)
+        for chunk in &(parameters.tau_powers_length()..parameters.tau_powers_g1_length())
             .into_iter()
-            .chunks(P::EMPIRICAL_BATCH_SIZE)
+            .chunks(parameters.tau_g1_batch_size())
         {
-            if let MinMax(start, end) = chunk.minmax() {
+            if let Some((start, end)) = chunk_bounds(chunk.minmax()) {
                 let size = end - start + 1;
                 let mut accumulator = Self {
                     tau_powers_g1: vec![E::G1Affine::one(); size],
@@ -1140,16 +1652,657 @@ impl<E: Engine, P: PowersOfTauParameters> BatchedAccumulator<E, P> {
                     beta_tau_powers_g1: vec![],
                     beta_g2: E::G2Affine::one(),
                     hash: blank_hash(),
-                    marker: std::marker::PhantomData::<P> {},
+                    parameters: parameters.clone(),
                 };
 
                 accumulator.write_chunk(start, compress_the_output, output_map)?;
-                println!("Done processing {} powers of tau", end);
+                progress.advance(ElementType::TauG1, end - start + 1);
             } else {
-                panic!("Chunk does not have a min and max");
+                return Err(empty_chunk(
+                    parameters.tau_powers_length(),
+                    parameters.tau_powers_g1_length(),
+                ));
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CeremonyConfig;
+    use crate::progress::NoProgress;
+    use std::ops::Range;
+
+    /// A 2^2 ceremony: TauG1 has 7 elements, every other vector has 4
+    fn test_parameters(empirical_batch_size: usize) -> RuntimeCeremonyParameters {
+        let mut config = CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 2;
+        RuntimeCeremonyParameters::new(config).with_empirical_batch_size(empirical_batch_size)
+    }
+
+    /// Builds a fresh uncompressed challenge and a compressed response on top of it
+    fn contribute(
+        parameters: &RuntimeCeremonyParameters,
+    ) -> (Mmap, Mmap, PublicKey<Bn256>, GenericArray<u8, U64>) {
+        contribute_on(UseCompression::No, parameters)
+    }
+
+    /// Builds a fresh challenge with `challenge_compression` and a compressed response on top of it
+    fn contribute_on(
+        challenge_compression: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> (Mmap, Mmap, PublicKey<Bn256>, GenericArray<u8, U64>) {
+        let mut challenge = MmapMut::map_anon(
+            parameters.file_byte_size(TranscriptFile::Challenge, challenge_compression),
+        )
+        .unwrap();
+        (&mut challenge[0..])
+            .write(blank_hash().as_slice())
+            .unwrap();
+        BatchedAccumulator::<Bn256>::generate_initial(
+            &mut challenge,
+            challenge_compression,
+            parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        let challenge = challenge.make_read_only().unwrap();
+        let challenge_hash =
+            BatchedAccumulator::<Bn256>::calculate_hash(&challenge, &mut NoProgress);
+
+        let mut response = MmapMut::map_anon(parameters.contribution_byte_size()).unwrap();
+        (&mut response[0..])
+            .write(challenge_hash.as_slice())
+            .unwrap();
+
+        let mut rng = ChaChaRng::from_seed(&[1u32, 2, 3, 4]);
+        let (pubkey, privkey) =
+            keypair_for_mode::<_, Bn256>(&mut rng, challenge_hash.as_ref(), parameters.mode())
+                .unwrap();
+        BatchedAccumulator::<Bn256>::transform(
+            &challenge,
+            &mut response,
+            challenge_compression,
+            UseCompression::Yes,
+            CheckForCorrectness::Yes,
+            &privkey,
+            parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        pubkey
+            .write(&mut response, UseCompression::Yes, parameters)
+            .unwrap();
+
+        (
+            challenge,
+            response.make_read_only().unwrap(),
+            pubkey,
+            challenge_hash,
+        )
+    }
+
+    fn verify(
+        challenge: &Mmap,
+        response: &Mmap,
+        pubkey: &PublicKey<Bn256>,
+        challenge_hash: &GenericArray<u8, U64>,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<(), CeremonyError> {
+        BatchedAccumulator::<Bn256>::verify_transformation(
+            challenge,
+            response,
+            pubkey,
+            challenge_hash.as_slice(),
+            UseCompression::No,
+            UseCompression::Yes,
+            CheckForCorrectness::Yes,
+            CheckForCorrectness::Yes,
+            parameters,
+            &mut NoProgress,
+        )
+    }
+
+    fn copy(map: &Mmap) -> Mmap {
+        let mut copy = MmapMut::map_anon(map.len()).unwrap();
+        copy.copy_from_slice(map);
+        copy.make_read_only().unwrap()
+    }
+
+    fn double<C: CurveAffine>(point: &mut C) {
+        let mut projective = point.into_projective();
+        projective.double();
+        *point = projective.into_affine();
+    }
+
+    /// Doubles every element of `element_type` with an index in `range` inside of the response.
+    /// Doubling a whole tail keeps the ratio between the tampered elements intact, so only
+    /// the checks that overlap the start of the range can notice it.
+    fn double_elements(
+        response: Mmap,
+        element_type: ElementType,
+        range: Range<usize>,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Mmap {
+        let mut response = response;
+        for index in range {
+            let mut accumulator = BatchedAccumulator::<Bn256>::empty(parameters);
+            accumulator
+                .read_chunk(
+                    index,
+                    1,
+                    UseCompression::Yes,
+                    CheckForCorrectness::No,
+                    &response,
+                )
+                .unwrap();
+            match element_type {
+                ElementType::TauG1 => double(&mut accumulator.tau_powers_g1[0]),
+                ElementType::TauG2 => double(&mut accumulator.tau_powers_g2[0]),
+                ElementType::AlphaG1 => double(&mut accumulator.alpha_tau_powers_g1[0]),
+                ElementType::BetaG1 => double(&mut accumulator.beta_tau_powers_g1[0]),
+                ElementType::BetaG2 => double(&mut accumulator.beta_g2),
+            }
+            let mut writable = response.make_mut().unwrap();
+            accumulator
+                .write_chunk(index, UseCompression::Yes, &mut writable)
+                .unwrap();
+            response = writable.make_read_only().unwrap();
+        }
+
+        response
+    }
+
+    fn element_length(element_type: ElementType, parameters: &RuntimeCeremonyParameters) -> usize {
+        match element_type {
+            ElementType::TauG1 => parameters.tau_powers_g1_length(),
+            ElementType::BetaG2 => 1,
+            _ => parameters.tau_powers_length(),
+        }
+    }
+
+    const ALL_ELEMENTS: [ElementType; 5] = [
+        ElementType::TauG1,
+        ElementType::TauG2,
+        ElementType::AlphaG1,
+        ElementType::BetaG1,
+        ElementType::BetaG2,
+    ];
+
+    #[test]
+    fn test_valid_contribution_is_accepted() {
+        for batch_size in 1..=8 {
+            let parameters = test_parameters(batch_size);
+            let (challenge, response, pubkey, hash) = contribute(&parameters);
+
+            assert!(
+                verify(&challenge, &response, &pubkey, &hash, &parameters).is_ok(),
+                "valid contribution rejected with batch size {}",
+                batch_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_tampered_point_is_rejected() {
+        for &batch_size in &[1, 2, 3, 1 << 21] {
+            let parameters = test_parameters(batch_size);
+            let (challenge, response, pubkey, hash) = contribute(&parameters);
+
+            for &element_type in ALL_ELEMENTS.iter() {
+                for index in 0..element_length(element_type, &parameters) {
+                    let tampered = double_elements(
+                        copy(&response),
+                        element_type,
+                        index..index + 1,
+                        &parameters,
+                    );
+
+                    assert!(
+                        verify(&challenge, &tampered, &pubkey, &hash, &parameters).is_err(),
+                        "tampered {} at {} accepted with batch size {}",
+                        element_type,
+                        index,
+                        batch_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tau_only_contribution_is_verified() {
+        for &batch_size in &[1, 3, 1 << 21] {
+            let parameters = test_parameters(batch_size).with_mode(CeremonyMode::TauOnly);
+            let (challenge, response, pubkey, hash) = contribute(&parameters);
+            assert!(
+                response.len() < test_parameters(batch_size).contribution_byte_size(),
+                "tau-only response holds alpha and beta"
+            );
+
+            // The public key is read back from the response without alpha and beta
+            let published =
+                PublicKey::<Bn256>::read(&response, UseCompression::Yes, &parameters).unwrap();
+            assert!(published == pubkey);
+            verify(&challenge, &response, &published, &hash, &parameters).unwrap();
+
+            for &element_type in &[ElementType::TauG1, ElementType::TauG2] {
+                for index in 0..element_length(element_type, &parameters) {
+                    let tampered = double_elements(
+                        copy(&response),
+                        element_type,
+                        index..index + 1,
+                        &parameters,
+                    );
+
+                    assert!(
+                        verify(&challenge, &tampered, &published, &hash, &parameters).is_err(),
+                        "tampered {} at {} accepted with batch size {}",
+                        element_type,
+                        index,
+                        batch_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tau_g1_tail_is_rejected_at_boundary() {
+        for &batch_size in &[1, 2, 3, 1 << 21] {
+            let parameters = test_parameters(batch_size);
+            let (challenge, response, pubkey, hash) = contribute(&parameters);
+
+            let tampered = double_elements(
+                response,
+                ElementType::TauG1,
+                parameters.tau_powers_length()..parameters.tau_powers_g1_length(),
+                &parameters,
+            );
+
+            match verify(&challenge, &tampered, &pubkey, &hash, &parameters) {
+                Err(CeremonyError::Verification(VerificationError::TauG1Boundary)) => {}
+                other => panic!(
+                    "expected a boundary failure with batch size {}, got {:?}",
+                    batch_size, other
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_points_swapped_at_boundary_are_rejected() {
+        let parameters = test_parameters(2);
+        let (challenge, response, pubkey, hash) = contribute(&parameters);
+        let boundary = parameters.tau_powers_length();
+
+        let mut last = BatchedAccumulator::<Bn256>::empty(&parameters);
+        last.read_chunk(
+            boundary - 1,
+            1,
+            UseCompression::Yes,
+            CheckForCorrectness::No,
+            &response,
+        )
+        .unwrap();
+        let mut first = BatchedAccumulator::<Bn256>::empty(&parameters);
+        first
+            .read_chunk(
+                boundary,
+                1,
+                UseCompression::Yes,
+                CheckForCorrectness::No,
+                &response,
+            )
+            .unwrap();
+
+        std::mem::swap(&mut last.tau_powers_g1, &mut first.tau_powers_g1);
+        let mut writable = response.make_mut().unwrap();
+        last.write_chunk(boundary - 1, UseCompression::Yes, &mut writable)
+            .unwrap();
+        first
+            .write_chunk(boundary, UseCompression::Yes, &mut writable)
+            .unwrap();
+        let tampered = writable.make_read_only().unwrap();
+
+        assert!(verify(&challenge, &tampered, &pubkey, &hash, &parameters).is_err());
+    }
+
+    #[test]
+    fn test_tails_are_rejected_at_chunk_edges() {
+        for &batch_size in &[1, 2, 3] {
+            let parameters = test_parameters(batch_size);
+            let (challenge, response, pubkey, hash) = contribute(&parameters);
+
+            // every chunk edge inside of a region, where the tampered tail ends with its region
+            let mut tails = vec![];
+            for &element_type in ALL_ELEMENTS.iter() {
+                if element_type == ElementType::BetaG2 {
+                    continue;
+                }
+                for start in (batch_size..parameters.tau_powers_length()).step_by(batch_size) {
+                    tails.push((element_type, start..parameters.tau_powers_length()));
+                }
+            }
+            for start in (parameters.tau_powers_length() + batch_size
+                ..parameters.tau_powers_g1_length())
+                .step_by(batch_size)
+            {
+                tails.push((ElementType::TauG1, start..parameters.tau_powers_g1_length()));
+            }
+
+            for (element_type, range) in tails {
+                let tampered =
+                    double_elements(copy(&response), element_type, range.clone(), &parameters);
+
+                assert!(
+                    verify(&challenge, &tampered, &pubkey, &hash, &parameters).is_err(),
+                    "tampered {} tail {:?} accepted with batch size {}",
+                    element_type,
+                    range,
+                    batch_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated_response_is_an_error() {
+        let parameters = test_parameters(2);
+        let (_, response, _, _) = contribute(&parameters);
+
+        let mut truncated = MmapMut::map_anon(response.len() - 1).unwrap();
+        truncated.copy_from_slice(&response[..response.len() - 1]);
+        let truncated = truncated.make_read_only().unwrap();
+
+        match PublicKey::<Bn256>::read(&truncated, UseCompression::Yes, &parameters) {
+            Err(CeremonyError::SizeMismatch { .. }) => {}
+            other => panic!("expected a size mismatch, got {:?}", other.map(|_| ())),
+        }
+
+        let mut accumulator = BatchedAccumulator::<Bn256>::empty(&parameters);
+        let mut short = MmapMut::map_anon(parameters.hash_size()).unwrap();
+        match accumulator.write_chunk(0, UseCompression::No, &mut short) {
+            Err(CeremonyError::SizeMismatch { .. }) => {}
+            other => panic!("expected a size mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_interrupted_transform_resumes_to_the_same_response() {
+        let parameters = test_parameters(1);
+        let (challenge, response, _, challenge_hash) = contribute(&parameters);
+        let mut rng = ChaChaRng::from_seed(&[1u32, 2, 3, 4]);
+        let (_, privkey) = keypair::<_, Bn256>(&mut rng, challenge_hash.as_ref()).unwrap();
+        let points = parameters.hash_size()
+            ..parameters.contribution_byte_size() - parameters.public_key_size();
+
+        for interrupt_at in 1..parameters.tau_powers_g1_length() {
+            let mut resumed = MmapMut::map_anon(parameters.contribution_byte_size()).unwrap();
+            let mut completed = 0;
+            let interrupted = BatchedAccumulator::<Bn256>::resume_transform(
+                &challenge,
+                &mut resumed,
+                UseCompression::No,
+                UseCompression::Yes,
+                CheckForCorrectness::Yes,
+                &privkey,
+                &parameters,
+                &mut NoProgress,
+                0,
+                &mut |powers| {
+                    completed = powers;
+                    if powers >= interrupt_at {
+                        Err(io::Error::new(io::ErrorKind::Other, "interrupted").into())
+                    } else {
+                        Ok(())
+                    }
+                },
+            );
+            assert!(interrupted.is_err());
+            assert_eq!(completed, interrupt_at);
+
+            // The batch size may differ between runs
+            BatchedAccumulator::<Bn256>::resume_transform(
+                &challenge,
+                &mut resumed,
+                UseCompression::No,
+                UseCompression::Yes,
+                CheckForCorrectness::Yes,
+                &privkey,
+                &test_parameters(2),
+                &mut NoProgress,
+                completed,
+                &mut |_| Ok(()),
+            )
+            .unwrap();
+
+            assert!(
+                resumed[points.clone()] == response[points.clone()],
+                "resuming at power {} changed the response",
+                interrupt_at
+            );
+        }
+    }
+
+    #[test]
+    fn test_interrupted_verification_resumes() {
+        let parameters = test_parameters(1);
+        let (challenge, response, pubkey, challenge_hash) = contribute(&parameters);
+        let tampered = double_elements(
+            copy(&response),
+            ElementType::TauG1,
+            parameters.tau_powers_length()..parameters.tau_powers_g1_length(),
+            &parameters,
+        );
+
+        for interrupt_at in 1..parameters.tau_powers_g1_length() {
+            for (response, valid) in [(&response, true), (&tampered, false)] {
+                let mut verified = VerifiedPowers::default();
+                let interrupted = BatchedAccumulator::<Bn256>::resume_verify_transformation(
+                    &challenge,
+                    response,
+                    &pubkey,
+                    challenge_hash.as_slice(),
+                    UseCompression::No,
+                    UseCompression::Yes,
+                    CheckForCorrectness::Yes,
+                    CheckForCorrectness::Yes,
+                    &parameters,
+                    &mut NoProgress,
+                    &mut verified,
+                    &mut |verified| {
+                        if verified.verified_powers >= interrupt_at {
+                            Err(io::Error::new(io::ErrorKind::Other, "interrupted").into())
+                        } else {
+                            Ok(())
+                        }
+                    },
+                );
+                assert!(interrupted.is_err());
+                assert_eq!(verified.verified_powers, interrupt_at);
+
+                // The batch size may differ between runs
+                let resumed = BatchedAccumulator::<Bn256>::resume_verify_transformation(
+                    &challenge,
+                    response,
+                    &pubkey,
+                    challenge_hash.as_slice(),
+                    UseCompression::No,
+                    UseCompression::Yes,
+                    CheckForCorrectness::Yes,
+                    CheckForCorrectness::Yes,
+                    &test_parameters(2),
+                    &mut NoProgress,
+                    &mut verified,
+                    &mut |_| Ok(()),
+                );
+                assert_eq!(
+                    resumed.is_ok(),
+                    valid,
+                    "resuming at power {} gave {:?}",
+                    interrupt_at,
+                    resumed
+                );
+                if valid {
+                    assert_eq!(verified.verified_powers, parameters.tau_powers_g1_length());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_contribution_on_compressed_challenge_is_accepted() {
+        for &batch_size in &[1, 2, 3, 8] {
+            let parameters = test_parameters(batch_size);
+            let (challenge, response, pubkey, challenge_hash) =
+                contribute_on(UseCompression::Yes, &parameters);
+
+            BatchedAccumulator::<Bn256>::check_hash_chain(&response, challenge_hash.as_slice())
+                .unwrap();
+            BatchedAccumulator::<Bn256>::verify_transformation(
+                &challenge,
+                &response,
+                &pubkey,
+                challenge_hash.as_slice(),
+                UseCompression::Yes,
+                UseCompression::Yes,
+                CheckForCorrectness::Yes,
+                CheckForCorrectness::Yes,
+                &parameters,
+                &mut NoProgress,
+            )
+            .unwrap();
+
+            let tampered = double_elements(response, ElementType::AlphaG1, 1..2, &parameters);
+            assert!(BatchedAccumulator::<Bn256>::verify_transformation(
+                &challenge,
+                &tampered,
+                &pubkey,
+                challenge_hash.as_slice(),
+                UseCompression::Yes,
+                UseCompression::Yes,
+                CheckForCorrectness::Yes,
+                CheckForCorrectness::Yes,
+                &parameters,
+                &mut NoProgress,
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_new_challenge_is_written_with_either_encoding() {
+        let parameters = test_parameters(3);
+        let (_, response, _, _) = contribute(&parameters);
+        let response_hash = BatchedAccumulator::<Bn256>::calculate_hash(&response, &mut NoProgress);
+
+        let new_challenge = |input: &Mmap, input_compression, output_compression| {
+            let mut output = MmapMut::map_anon(
+                parameters.file_byte_size(TranscriptFile::Challenge, output_compression),
+            )
+            .unwrap();
+            (&mut output[0..]).write(response_hash.as_slice()).unwrap();
+            BatchedAccumulator::<Bn256>::convert(
+                input,
+                &mut output,
+                input_compression,
+                output_compression,
+                CheckForCorrectness::Yes,
+                &parameters,
+                &mut NoProgress,
+            )
+            .unwrap();
+            output.make_read_only().unwrap()
+        };
+
+        // A compressed new challenge is the response without its public key
+        let compressed = new_challenge(&response, UseCompression::Yes, UseCompression::Yes);
+        let points = parameters.hash_size()
+            ..parameters.file_byte_size(TranscriptFile::Challenge, UseCompression::Yes);
+        assert_eq!(&compressed[points.clone()], &response[points]);
+
+        let uncompressed = new_challenge(&response, UseCompression::Yes, UseCompression::No);
+        let recompressed = new_challenge(&uncompressed, UseCompression::No, UseCompression::Yes);
+        assert_eq!(&recompressed[..], &compressed[..]);
+        let decompressed = new_challenge(&compressed, UseCompression::Yes, UseCompression::No);
+        assert_eq!(&decompressed[..], &uncompressed[..]);
+
+        // Points copied as they are are still checked, so a point off the curve is rejected
+        let mut tampered = MmapMut::map_anon(uncompressed.len()).unwrap();
+        tampered.copy_from_slice(&uncompressed);
+        tampered[uncompressed.len() - 1] ^= 1;
+        let tampered = tampered.make_read_only().unwrap();
+        let mut output = MmapMut::map_anon(uncompressed.len()).unwrap();
+        assert!(BatchedAccumulator::<Bn256>::convert(
+            &tampered,
+            &mut output,
+            UseCompression::No,
+            UseCompression::No,
+            CheckForCorrectness::Yes,
+            &parameters,
+            &mut NoProgress,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reduced_accumulator_holds_the_first_powers() {
+        let parameters = test_parameters(3).with_required_power(3);
+        let reduced_parameters = parameters.clone().with_required_power(2);
+        let (_, response, _, _) = contribute(&parameters);
+
+        let mut reduced = MmapMut::map_anon(
+            reduced_parameters.file_byte_size(TranscriptFile::Challenge, UseCompression::No),
+        )
+        .unwrap();
+        BatchedAccumulator::<Bn256>::reduce_power(
+            &response,
+            &mut reduced,
+            UseCompression::Yes,
+            UseCompression::No,
+            CheckForCorrectness::Yes,
+            &parameters,
+            &reduced_parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        let reduced = reduced.make_read_only().unwrap();
+
+        let read = |map: &Mmap, compression, parameters, from, size| {
+            let mut accumulator = BatchedAccumulator::<Bn256>::empty(parameters);
+            accumulator
+                .read_chunk(from, size, compression, CheckForCorrectness::Yes, map)
+                .unwrap();
+            accumulator
+        };
+        let original = read(&response, UseCompression::Yes, &parameters, 0, 4);
+        let first = read(&reduced, UseCompression::No, &reduced_parameters, 0, 4);
+        assert_eq!(first.tau_powers_g1, original.tau_powers_g1);
+        assert_eq!(first.tau_powers_g2, original.tau_powers_g2);
+        assert_eq!(first.alpha_tau_powers_g1, original.alpha_tau_powers_g1);
+        assert_eq!(first.beta_tau_powers_g1, original.beta_tau_powers_g1);
+        assert_eq!(first.beta_g2, original.beta_g2);
+
+        // The rest of tau in G1, up to 2^3 - 1 powers
+        let original = read(&response, UseCompression::Yes, &parameters, 4, 3);
+        let rest = read(&reduced, UseCompression::No, &reduced_parameters, 4, 3);
+        assert_eq!(rest.tau_powers_g1, original.tau_powers_g1);
+
+        let mut larger = MmapMut::map_anon(parameters.contribution_byte_size()).unwrap();
+        assert!(BatchedAccumulator::<Bn256>::reduce_power(
+            &reduced,
+            &mut larger,
+            UseCompression::No,
+            UseCompression::Yes,
+            CheckForCorrectness::Yes,
+            &reduced_parameters,
+            &parameters,
+            &mut NoProgress,
+        )
+        .is_err());
+    }
+}
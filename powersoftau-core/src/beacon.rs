@@ -0,0 +1,890 @@
+use bellman::pairing::Engine;
+use blake2::Blake2b;
+use byteorder::{BigEndian, ReadBytesExt};
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use hex_literal::hex;
+use memmap::{Mmap, MmapMut};
+use rand::chacha::ChaChaRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use typenum::consts::U32;
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::beacon_source::{
+    decode_seed, BeaconProvenance, BeaconSource, BitcoinBeacon, CardanoBeacon, DrandBeacon,
+    HexBeacon,
+};
+use super::keypair::{keypair_for_mode, PrivateKey, PublicKey};
+use super::parameters::{CeremonyError, CheckForCorrectness, TranscriptFile, UseCompression};
+use super::progress::ProgressSink;
+use super::runtime_parameters::RuntimeCeremonyParameters;
+use super::utils::ScratchFile;
+
+/// Hash of Bitcoin block #564321, the default random beacon of the ceremony
+pub const BEACON_BLOCK_HASH: [u8; 32] =
+    hex!("0000000000000000000a558a61ddc8ee4e488d647a747fe4dcc362fe2026c620");
+
+/// By default the beacon is hashed 2^BEACON_ITERATIONS_EXP times before it seeds the RNG
+pub const BEACON_ITERATIONS_EXP: usize = 10;
+
+/// 2^MIN_BEACON_ITERATIONS_EXP intermediate states of the beacon are published
+pub const MIN_BEACON_ITERATIONS_EXP: usize = 10;
+
+/// Largest supported iteration exponent, the iterations are counted in a `u64`
+pub const MAX_BEACON_ITERATIONS_EXP: usize = 63;
+
+/// Hash function the beacon is iterated with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BeaconHash {
+    #[serde(rename = "sha256")]
+    Sha256,
+    #[serde(rename = "blake2b256")]
+    Blake2b256,
+}
+
+impl BeaconHash {
+    fn hash(&self, input: &[u8; 32]) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        match *self {
+            BeaconHash::Sha256 => {
+                let mut h = Sha256::new();
+                h.input(input);
+                h.result(&mut output);
+            }
+            BeaconHash::Blake2b256 => {
+                let mut h = Blake2b::<U32>::default();
+                blake2::Digest::update(&mut h, input);
+                output.copy_from_slice(&blake2::Digest::finalize(h));
+            }
+        }
+
+        output
+    }
+}
+
+impl fmt::Display for BeaconHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BeaconHash::Sha256 => write!(f, "sha256"),
+            BeaconHash::Blake2b256 => write!(f, "blake2b256"),
+        }
+    }
+}
+
+/// Public randomness the beacon value is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeaconSourceKind {
+    /// `value` as it is
+    Hex,
+    /// The hash of `cardano_header`
+    Cardano,
+    /// The hash of `bitcoin_header`
+    Bitcoin,
+    /// The randomness of `drand_round`
+    Drand,
+}
+
+impl fmt::Display for BeaconSourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BeaconSourceKind::Hex => write!(f, "hex"),
+            BeaconSourceKind::Cardano => write!(f, "cardano"),
+            BeaconSourceKind::Bitcoin => write!(f, "bitcoin"),
+            BeaconSourceKind::Drand => write!(f, "drand"),
+        }
+    }
+}
+
+/// Random beacon the final contribution is derived from. Set in the `[beacon]` section of
+/// `ceremony.toml` or with the `--beacon-*` flags.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BeaconParameters {
+    /// Hex encoded 32 byte beacon value, e.g. the hash of a block announced in advance. Taken
+    /// from the source unless that is `hex`.
+    pub value: String,
+    /// The value is hashed 2^iterations_exp times
+    pub iterations_exp: usize,
+    pub hash: BeaconHash,
+    pub source: BeaconSourceKind,
+    /// CBOR encoded Cardano block header whose hash is the beacon value
+    pub cardano_header: Option<String>,
+    /// Slot of the block announced as the beacon, which the header has to be of
+    pub cardano_slot: Option<u64>,
+    /// Number of the block announced as the beacon, which the header has to be of
+    pub cardano_block_number: Option<u64>,
+    /// Raw 80 byte Bitcoin block header whose hash is the beacon value
+    pub bitcoin_header: Option<String>,
+    /// Hash of the Bitcoin block announced as the beacon, which the header has to hash to
+    pub bitcoin_block_hash: Option<String>,
+    /// JSON of a drand round, as served by `/public/<round>`
+    pub drand_round: Option<String>,
+    /// JSON of the drand chain info, as served by `/info`, with the key the round is signed with
+    pub drand_chain_info: Option<String>,
+    /// Hash of the drand chain announced as the source, which the chain info has to hash to
+    pub drand_chain_hash: Option<String>,
+    /// Number of the drand round announced as the beacon, which the round has to be
+    pub drand_round_number: Option<u64>,
+}
+
+impl Default for BeaconParameters {
+    fn default() -> Self {
+        Self {
+            value: hex::encode(BEACON_BLOCK_HASH),
+            iterations_exp: BEACON_ITERATIONS_EXP,
+            hash: BeaconHash::Sha256,
+            source: BeaconSourceKind::Hex,
+            cardano_header: None,
+            cardano_slot: None,
+            cardano_block_number: None,
+            bitcoin_header: None,
+            bitcoin_block_hash: None,
+            drand_round: None,
+            drand_chain_info: None,
+            drand_chain_hash: None,
+            drand_round_number: None,
+        }
+    }
+}
+
+impl BeaconParameters {
+    /// The source the beacon value is taken from
+    pub fn source(&self) -> Result<Box<dyn BeaconSource>, CeremonyError> {
+        let required = |value: &Option<String>, what: &str| {
+            value.clone().map(PathBuf::from).ok_or_else(|| {
+                CeremonyError::InvalidParameters(format!(
+                    "the {} source of the beacon requires the {}",
+                    self.source, what
+                ))
+            })
+        };
+
+        Ok(match self.source {
+            BeaconSourceKind::Hex => Box::new(HexBeacon {
+                value: self.value.clone(),
+            }),
+            BeaconSourceKind::Cardano => match (self.cardano_slot, self.cardano_block_number) {
+                (Some(slot), Some(block_number)) => Box::new(CardanoBeacon {
+                    header: required(&self.cardano_header, "Cardano block header")?,
+                    slot,
+                    block_number,
+                }),
+                _ => {
+                    return Err(CeremonyError::InvalidParameters(
+                        "the slot and number of the announced Cardano block are required"
+                            .to_string(),
+                    ))
+                }
+            },
+            BeaconSourceKind::Bitcoin => Box::new(BitcoinBeacon {
+                header: required(&self.bitcoin_header, "Bitcoin block header")?,
+                block_hash: self.bitcoin_block_hash.clone().ok_or_else(|| {
+                    CeremonyError::InvalidParameters(
+                        "the hash of the announced Bitcoin block is required".to_string(),
+                    )
+                })?,
+            }),
+            BeaconSourceKind::Drand => Box::new(DrandBeacon {
+                round: required(&self.drand_round, "drand round")?,
+                chain_info: required(&self.drand_chain_info, "drand chain info")?,
+                chain_hash: self.drand_chain_hash.clone().ok_or_else(|| {
+                    CeremonyError::InvalidParameters(
+                        "the hash of the announced drand chain is required".to_string(),
+                    )
+                })?,
+                round_number: self.drand_round_number,
+            }),
+        })
+    }
+
+    /// Beacon with the value taken from its source, along with the evidence of where it came
+    /// from
+    pub fn resolve(&self) -> Result<(Self, BeaconProvenance), CeremonyError> {
+        let (seed, provenance) = self.source()?.seed()?;
+
+        Ok((
+            Self {
+                value: hex::encode(seed),
+                ..self.clone()
+            },
+            provenance,
+        ))
+    }
+
+    /// Decodes the beacon value, and checks that the iterations can be published and counted
+    pub fn decode(&self) -> Result<[u8; 32], CeremonyError> {
+        if self.iterations_exp < MIN_BEACON_ITERATIONS_EXP
+            || self.iterations_exp > MAX_BEACON_ITERATIONS_EXP
+        {
+            return Err(CeremonyError::InvalidParameters(format!(
+                "the beacon has to be hashed 2^{} to 2^{} times, not 2^{}",
+                MIN_BEACON_ITERATIONS_EXP, MAX_BEACON_ITERATIONS_EXP, self.iterations_exp
+            )));
+        }
+
+        decode_seed(&self.value, "beacon value")
+    }
+
+    /// Hashes the beacon value 2^iterations_exp times. 1024 of the intermediate states are
+    /// handed to `on_checkpoint` along with their iteration, so that the work can be checked in
+    /// parallel.
+    pub fn iterate(
+        &self,
+        on_checkpoint: &mut dyn FnMut(u64, &[u8; 32]),
+    ) -> Result<[u8; 32], CeremonyError> {
+        let mut cur_hash = self.decode()?;
+        let checkpoint_interval = self.checkpoint_interval();
+        for i in 0..(1u64 << self.iterations_exp) {
+            if i % checkpoint_interval == 0 {
+                on_checkpoint(i, &cur_hash);
+            }
+
+            cur_hash = self.hash.hash(&cur_hash);
+        }
+
+        Ok(cur_hash)
+    }
+
+    /// Number of iterations between two published intermediate states
+    pub fn checkpoint_interval(&self) -> u64 {
+        1u64 << (self.iterations_exp - MIN_BEACON_ITERATIONS_EXP)
+    }
+}
+
+impl fmt::Display for BeaconParameters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} hashed 2^{} times with {}",
+            self.value, self.iterations_exp, self.hash
+        )?;
+        if self.source != BeaconSourceKind::Hex {
+            write!(f, ", from the {} source", self.source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Beacon a response was computed with, saved next to it so that anyone can derive it again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BeaconRecord {
+    pub beacon: BeaconParameters,
+    /// Evidence the beacon value was taken from its source with
+    pub provenance: BeaconProvenance,
+    /// Hex encoded intermediate states handed to `on_checkpoint` by `BeaconParameters::iterate`,
+    /// which split the iterations into segments that are checked independently
+    #[serde(default)]
+    pub checkpoints: Vec<String>,
+    /// Hex encoded result of the iterations, which seeds the RNG
+    pub result: String,
+}
+
+impl BeaconRecord {
+    pub fn new(
+        beacon: &BeaconParameters,
+        provenance: &BeaconProvenance,
+        checkpoints: &[[u8; 32]],
+        result: &[u8; 32],
+    ) -> Self {
+        Self {
+            beacon: beacon.clone(),
+            provenance: provenance.clone(),
+            checkpoints: checkpoints.iter().map(hex::encode).collect(),
+            result: hex::encode(result),
+        }
+    }
+
+    /// Checks that the beacon value comes from the recorded provenance, and that the recorded
+    /// checkpoints and result are the iterations of the beacon, and gives the result. Every
+    /// segment between two checkpoints is hashed independently, spread over all cores. Without
+    /// checkpoints the iterations are simply computed again.
+    pub fn verify(&self) -> Result<[u8; 32], CeremonyError> {
+        let value = self.beacon.decode()?;
+        if self.provenance.seed()? != value {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "the beacon value is not taken from {}",
+                self.provenance
+            )));
+        }
+        let decode = |encoded: &str| -> Result<[u8; 32], CeremonyError> {
+            let mut state = [0u8; 32];
+            hex::decode_to_slice(encoded, &mut state).map_err(|e| {
+                CeremonyError::InvalidParameters(format!("malformed beacon record: {}", e))
+            })?;
+            Ok(state)
+        };
+        let mut states = self
+            .checkpoints
+            .iter()
+            .map(|encoded| decode(encoded))
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = decode(&self.result)?;
+
+        if states.is_empty() {
+            if self.beacon.iterate(&mut |_, _| {})? != result {
+                return Err(CeremonyError::BeaconMismatch(
+                    "the beacon does not hash to the recorded result".to_string(),
+                ));
+            }
+
+            return Ok(result);
+        }
+
+        let segments = 1usize << MIN_BEACON_ITERATIONS_EXP;
+        if states.len() != segments {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "the record has {} checkpoints instead of {}",
+                states.len(),
+                segments
+            )));
+        }
+        if states[0] != value {
+            return Err(CeremonyError::BeaconMismatch(
+                "the first checkpoint is not the beacon value".to_string(),
+            ));
+        }
+        // Segment `i` runs from state `i` to state `i + 1`, the last one to the result
+        states.push(result);
+
+        let interval = self.beacon.checkpoint_interval();
+        let hash = self.beacon.hash;
+        let mut chunk_size = segments / num_cpus::get();
+        if chunk_size == 0 {
+            chunk_size = 1;
+        }
+
+        // The first segment that does not hash to the next checkpoint, if any
+        let mismatch = Arc::new(Mutex::new(None));
+
+        let pairs = states.windows(2).collect::<Vec<_>>();
+        crossbeam::scope(|scope| {
+            for (chunk_index, chunk) in pairs.chunks(chunk_size).enumerate() {
+                let mismatch = mismatch.clone();
+
+                scope.spawn(move || {
+                    for (i, segment) in chunk.iter().enumerate() {
+                        let mut state = segment[0];
+                        for _ in 0..interval {
+                            state = hash.hash(&state);
+                        }
+
+                        if state != segment[1] {
+                            let segment_index = chunk_index * chunk_size + i;
+                            let mut mismatch = mismatch.lock().unwrap();
+                            if mismatch.is_none_or(|first| segment_index < first) {
+                                *mismatch = Some(segment_index);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(segment) = *mismatch.lock().unwrap() {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "segment {} of the beacon iterations does not hash to the next checkpoint",
+                segment
+            )));
+        }
+
+        Ok(result)
+    }
+
+    /// Checks that the recorded beacon is the one announced in `announced`: the same source, the
+    /// same value for a `hex` beacon, the same iterations and hash function, and the same Cardano
+    /// block or drand chain and round, in the beacon as well as in its provenance.
+    pub fn ensure_announced(&self, announced: &BeaconParameters) -> Result<(), CeremonyError> {
+        let differs = |what: &str| {
+            Err(CeremonyError::BeaconMismatch(format!(
+                "the recorded beacon has another {} than the announced one",
+                what
+            )))
+        };
+        let beacon = &self.beacon;
+
+        let source = match self.provenance {
+            BeaconProvenance::Hex { .. } => BeaconSourceKind::Hex,
+            BeaconProvenance::Cardano { .. } => BeaconSourceKind::Cardano,
+            BeaconProvenance::Bitcoin { .. } => BeaconSourceKind::Bitcoin,
+            BeaconProvenance::Drand { .. } => BeaconSourceKind::Drand,
+        };
+        if beacon.source != announced.source || source != announced.source {
+            return differs("source");
+        }
+        if announced.source == BeaconSourceKind::Hex
+            && decode_seed(&beacon.value, "beacon value")?
+                != decode_seed(&announced.value, "announced beacon value")?
+        {
+            return differs("value");
+        }
+        if beacon.iterations_exp != announced.iterations_exp {
+            return differs("number of iterations");
+        }
+        if beacon.hash != announced.hash {
+            return differs("hash function");
+        }
+
+        let (slot, block_number, round_number) = match self.provenance {
+            BeaconProvenance::Cardano {
+                slot, block_number, ..
+            } => (Some(slot), Some(block_number), beacon.drand_round_number),
+            BeaconProvenance::Drand { round, .. } => (
+                beacon.cardano_slot,
+                beacon.cardano_block_number,
+                announced.drand_round_number.and(Some(round)),
+            ),
+            _ => (
+                beacon.cardano_slot,
+                beacon.cardano_block_number,
+                beacon.drand_round_number,
+            ),
+        };
+        if beacon.cardano_slot != announced.cardano_slot || slot != announced.cardano_slot {
+            return differs("Cardano slot");
+        }
+        if beacon.cardano_block_number != announced.cardano_block_number
+            || block_number != announced.cardano_block_number
+        {
+            return differs("Cardano block number");
+        }
+        if beacon.bitcoin_block_hash != announced.bitcoin_block_hash {
+            return differs("Bitcoin block");
+        }
+        if let BeaconProvenance::Bitcoin { ref block_hash, .. } = self.provenance {
+            match announced.bitcoin_block_hash {
+                Some(ref announced_hash)
+                    if decode_seed(announced_hash, "announced Bitcoin block hash")?
+                        == decode_seed(block_hash, "Bitcoin block hash")? => {}
+                _ => return differs("Bitcoin block"),
+            }
+        }
+        if beacon.drand_round_number != announced.drand_round_number
+            || round_number != announced.drand_round_number
+        {
+            return differs("drand round");
+        }
+        if beacon.drand_chain_hash != announced.drand_chain_hash {
+            return differs("drand chain");
+        }
+        if let BeaconProvenance::Drand { ref chain_hash, .. } = self.provenance {
+            match announced.drand_chain_hash {
+                Some(ref announced_hash)
+                    if decode_seed(announced_hash, "announced drand chain hash")?
+                        == decode_seed(chain_hash, "drand chain hash")? => {}
+                _ => return differs("drand chain"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Location of the record of `response`
+    pub fn path_for<P: AsRef<Path>>(response: P) -> PathBuf {
+        let mut path = response.as_ref().as_os_str().to_owned();
+        path.push(".beacon");
+        PathBuf::from(path)
+    }
+
+    /// Reads the record at `path`, if there is one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, CeremonyError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_reader(file).map(Some).map_err(|e| {
+            CeremonyError::InvalidParameters(format!("malformed beacon record: {}", e))
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CeremonyError> {
+        let mut file = File::create(path)?;
+        serde_json::to_writer_pretty(&mut file, self)
+            .map_err(|e| CeremonyError::InvalidParameters(e.to_string()))?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+/// RNG the beacon contribution samples its keypair from, seeded with the iterated beacon
+pub fn beacon_rng(iterated_beacon: &[u8; 32]) -> ChaChaRng {
+    let mut digest = &iterated_beacon[..];
+
+    let mut seed = [0u32; 8];
+    for word in seed.iter_mut() {
+        *word = digest
+            .read_u32::<BigEndian>()
+            .expect("digest is large enough for this to work");
+    }
+
+    ChaChaRng::from_seed(&seed)
+}
+
+/// Checks that the public key of `response` is the one the beacon, which hashes to
+/// `iterated_beacon`, derives on top of the challenge hashing to `challenge_hash`, and returns
+/// the keypair of the beacon.
+pub fn verify_beacon_key<E: Engine>(
+    response: &Mmap,
+    response_compression: UseCompression,
+    challenge_hash: &[u8],
+    iterated_beacon: &[u8; 32],
+    parameters: &RuntimeCeremonyParameters,
+) -> Result<(PublicKey<E>, PrivateKey<E>), CeremonyError> {
+    let mut rng = beacon_rng(iterated_beacon);
+    let (pubkey, privkey) = keypair_for_mode::<_, E>(&mut rng, challenge_hash, parameters.mode())?;
+    let published = PublicKey::<E>::read(response, response_compression, parameters)?;
+    if published != pubkey {
+        return Err(CeremonyError::BeaconMismatch(
+            "the public key of the response is not derived from the beacon".to_string(),
+        ));
+    }
+
+    Ok((pubkey, privkey))
+}
+
+/// Checks that `response` is the contribution of the beacon, which hashes to `iterated_beacon`,
+/// on top of `challenge`: the response builds on the challenge and its public key is the one
+/// the beacon RNG derives.
+///
+/// With a `recompute_path`, the whole contribution is computed again into that file, and the
+/// response has to match it bit for bit. The file is removed afterwards.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_beacon<E: Engine>(
+    challenge: &Mmap,
+    response: &Mmap,
+    challenge_compression: UseCompression,
+    response_compression: UseCompression,
+    iterated_beacon: &[u8; 32],
+    recompute_path: Option<&Path>,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    let challenge_hash = BatchedAccumulator::<E>::calculate_hash(challenge, progress);
+    BatchedAccumulator::<E>::check_hash_chain(response, challenge_hash.as_slice())?;
+
+    let (pubkey, privkey) = verify_beacon_key::<E>(
+        response,
+        response_compression,
+        challenge_hash.as_slice(),
+        iterated_beacon,
+        parameters,
+    )?;
+
+    let recompute_path = match recompute_path {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    // The recomputed response is removed on every path out of here
+    let _scratch = ScratchFile(recompute_path);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(recompute_path)?;
+    file.set_len(parameters.file_byte_size(TranscriptFile::Response, response_compression) as u64)?;
+    let mut recomputed = unsafe { MmapMut::map_mut(&file)? };
+    (&mut recomputed[0..]).write_all(challenge_hash.as_slice())?;
+    BatchedAccumulator::<E>::transform(
+        challenge,
+        &mut recomputed,
+        challenge_compression,
+        response_compression,
+        CheckForCorrectness::No,
+        &privkey,
+        parameters,
+        progress,
+    )?;
+    pubkey.write(&mut recomputed, response_compression, parameters)?;
+    let recomputed = recomputed.make_read_only()?;
+
+    let response_hash = BatchedAccumulator::<E>::calculate_hash(response, progress);
+    let recomputed_hash = BatchedAccumulator::<E>::calculate_hash(&recomputed, progress);
+    if response_hash != recomputed_hash {
+        return Err(CeremonyError::BeaconMismatch(
+            "the recomputed beacon contribution differs from the response".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::Bn256;
+    use std::env;
+
+    use crate::config::CeremonyMode;
+    use crate::keypair::keypair_for_mode;
+    use crate::progress::NoProgress;
+    use crate::test_utils::{bn254_parameters, fresh_challenge};
+
+    #[test]
+    fn test_beacon_response_is_verified() {
+        // The public key of a tau-only contribution has no terms for alpha and beta
+        for &mode in &[CeremonyMode::Full, CeremonyMode::TauOnly] {
+            let parameters = bn254_parameters(2).with_mode(mode);
+            let challenge = fresh_challenge(&parameters);
+            let challenge_hash =
+                BatchedAccumulator::<Bn256>::calculate_hash(&challenge, &mut NoProgress);
+
+            let mut checkpoints = 0;
+            let beacon = BeaconParameters::default()
+                .iterate(&mut |_, _| checkpoints += 1)
+                .unwrap();
+            assert_eq!(checkpoints, 1024);
+
+            let beacon_response = |beacon: &[u8; 32]| {
+                let mut response = MmapMut::map_anon(parameters.contribution_byte_size()).unwrap();
+                (&mut response[0..])
+                    .write_all(challenge_hash.as_slice())
+                    .unwrap();
+                let (pubkey, privkey) = keypair_for_mode::<_, Bn256>(
+                    &mut beacon_rng(beacon),
+                    challenge_hash.as_ref(),
+                    mode,
+                )
+                .unwrap();
+                BatchedAccumulator::<Bn256>::transform(
+                    &challenge,
+                    &mut response,
+                    UseCompression::No,
+                    UseCompression::Yes,
+                    CheckForCorrectness::No,
+                    &privkey,
+                    &parameters,
+                    &mut NoProgress,
+                )
+                .unwrap();
+                pubkey
+                    .write(&mut response, UseCompression::Yes, &parameters)
+                    .unwrap();
+                response.make_read_only().unwrap()
+            };
+            let response = beacon_response(&beacon);
+
+            let recompute_path = env::temp_dir().join(format!(
+                "powersoftau-{}-beacon-recomputed",
+                std::process::id()
+            ));
+            let verify = |beacon: &[u8; 32], recompute_path: Option<&Path>| {
+                verify_beacon::<Bn256>(
+                    &challenge,
+                    &response,
+                    UseCompression::No,
+                    UseCompression::Yes,
+                    beacon,
+                    recompute_path,
+                    &parameters,
+                    &mut NoProgress,
+                )
+            };
+            verify(&beacon, None).unwrap();
+            verify(&beacon, Some(&recompute_path)).unwrap();
+            assert!(!recompute_path.exists());
+
+            // Another beacon gives another keypair
+            let other = BeaconParameters {
+                hash: BeaconHash::Blake2b256,
+                ..BeaconParameters::default()
+            };
+            let other = other.iterate(&mut |_, _| {}).unwrap();
+            assert!(verify(&other, None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_beacon_record_is_verified_by_segments() {
+        let (beacon, provenance) = BeaconParameters {
+            iterations_exp: 12,
+            ..BeaconParameters::default()
+        }
+        .resolve()
+        .unwrap();
+        let mut checkpoints = vec![];
+        let result = beacon
+            .iterate(&mut |_, hash| checkpoints.push(*hash))
+            .unwrap();
+        let record = BeaconRecord::new(&beacon, &provenance, &checkpoints, &result);
+        assert_eq!(record.verify().unwrap(), result);
+        let without_checkpoints = BeaconRecord::new(&beacon, &provenance, &[], &result);
+        assert_eq!(without_checkpoints.verify().unwrap(), result);
+
+        let path = env::temp_dir().join(format!("powersoftau-{}-beacon", std::process::id()));
+        record.save(&path).unwrap();
+        assert_eq!(BeaconRecord::load(&path).unwrap(), Some(record.clone()));
+        fs::remove_file(&path).unwrap();
+
+        let mut tampered = record.clone();
+        tampered.checkpoints[700] = hex::encode([7u8; 32]);
+        match tampered.verify() {
+            Err(CeremonyError::BeaconMismatch(e)) => assert!(e.contains("segment 699")),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut tampered = record.clone();
+        tampered.result = hex::encode([7u8; 32]);
+        assert!(tampered.verify().is_err());
+
+        // The value has to come from the recorded source
+        let mut tampered = record;
+        tampered.provenance = BeaconProvenance::Hex {
+            value: hex::encode([7u8; 32]),
+        };
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn test_beacon_record_has_to_be_announced() {
+        let announced = BeaconParameters::default();
+        let record_of = |beacon: &BeaconParameters| {
+            let (beacon, provenance) = beacon.resolve().unwrap();
+            let result = beacon.iterate(&mut |_, _| {}).unwrap();
+            BeaconRecord::new(&beacon, &provenance, &[], &result)
+        };
+        record_of(&announced).ensure_announced(&announced).unwrap();
+
+        // A record of another beacon is valid on its own, but not the announced one
+        let other_value = record_of(&BeaconParameters {
+            value: hex::encode([7u8; 32]),
+            ..announced.clone()
+        });
+        other_value.verify().unwrap();
+        match other_value.ensure_announced(&announced) {
+            Err(CeremonyError::BeaconMismatch(e)) => assert!(e.contains("value")),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let other_hash = record_of(&BeaconParameters {
+            hash: BeaconHash::Blake2b256,
+            ..announced.clone()
+        });
+        assert!(other_hash.ensure_announced(&announced).is_err());
+        let more_iterations = record_of(&BeaconParameters {
+            iterations_exp: 11,
+            ..announced.clone()
+        });
+        assert!(more_iterations.ensure_announced(&announced).is_err());
+        let other_round = record_of(&BeaconParameters {
+            drand_round_number: Some(42),
+            ..announced.clone()
+        });
+        assert!(other_round.ensure_announced(&announced).is_err());
+        let other_block = record_of(&BeaconParameters {
+            bitcoin_block_hash: Some(hex::encode([7u8; 32])),
+            ..announced.clone()
+        });
+        assert!(other_block.ensure_announced(&announced).is_err());
+    }
+
+    #[test]
+    fn test_beacon_is_taken_from_cardano_header() {
+        use crate::cardano::CardanoHeader;
+        use ciborium::value::Value;
+
+        let body = vec![
+            Value::Integer(42.into()),
+            Value::Integer(1000.into()),
+            Value::Null,
+        ]
+        .into_iter()
+        .chain(std::iter::repeat(Value::Bytes(vec![1; 32])).take(7))
+        .collect();
+        let mut encoded = vec![];
+        ciborium::ser::into_writer(
+            &Value::Array(vec![Value::Array(body), Value::Bytes(vec![2; 448])]),
+            &mut encoded,
+        )
+        .unwrap();
+        let path = env::temp_dir().join(format!("powersoftau-{}-header.cbor", std::process::id()));
+        fs::write(&path, &encoded).unwrap();
+
+        let beacon = BeaconParameters {
+            source: BeaconSourceKind::Cardano,
+            cardano_header: Some(path.display().to_string()),
+            cardano_slot: Some(1000),
+            cardano_block_number: Some(42),
+            ..BeaconParameters::default()
+        };
+        let (resolved, provenance) = beacon.resolve().unwrap();
+        assert_eq!(
+            resolved.decode().unwrap(),
+            CardanoHeader::parse(&encoded).unwrap().hash
+        );
+        assert_eq!(
+            provenance,
+            BeaconProvenance::Cardano {
+                header: hex::encode(&encoded),
+                slot: 1000,
+                block_number: 42,
+            }
+        );
+
+        // The record embeds the header, and has to hold its hash
+        fs::remove_file(&path).unwrap();
+        let mut checkpoints = vec![];
+        let result = resolved
+            .iterate(&mut |_, hash| checkpoints.push(*hash))
+            .unwrap();
+        assert!(
+            BeaconRecord::new(&resolved, &provenance, &checkpoints, &result)
+                .verify()
+                .is_ok()
+        );
+        assert!(
+            BeaconRecord::new(&beacon, &provenance, &checkpoints, &result)
+                .verify()
+                .is_err()
+        );
+        fs::write(&path, &encoded).unwrap();
+
+        let another_slot = BeaconParameters {
+            cardano_slot: Some(1001),
+            ..beacon.clone()
+        };
+        assert!(another_slot.resolve().is_err());
+        let unannounced = BeaconParameters {
+            cardano_block_number: None,
+            ..beacon
+        };
+        assert!(unannounced.resolve().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_beacon_parameters_are_checked() {
+        let beacon = BeaconParameters::default();
+        assert_eq!(beacon.decode().unwrap(), BEACON_BLOCK_HASH);
+
+        let too_few = BeaconParameters {
+            iterations_exp: 9,
+            ..beacon.clone()
+        };
+        assert!(too_few.iterate(&mut |_, _| {}).is_err());
+
+        let too_short = BeaconParameters {
+            value: "00ff".to_string(),
+            ..beacon.clone()
+        };
+        assert!(too_short.decode().is_err());
+
+        let with_prefix = BeaconParameters {
+            value: format!("0x{}", beacon.value),
+            ..beacon
+        };
+        assert_eq!(with_prefix.decode().unwrap(), BEACON_BLOCK_HASH);
+    }
+}
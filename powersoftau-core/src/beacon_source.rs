@@ -0,0 +1,760 @@
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, G2Projective};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use super::cardano::CardanoHeader;
+use super::parameters::CeremonyError;
+
+/// Size of a Bitcoin block header
+const BITCOIN_HEADER_SIZE: usize = 80;
+/// Compact target of the lowest difficulty on the Bitcoin main network. Headers with an easier
+/// target can be mined at will, and are rejected.
+const BITCOIN_MAX_TARGET_BITS: u32 = 0x1d00_ffff;
+
+/// drand scheme with signatures on G2, each one signing the previous signature and the round
+const DRAND_CHAINED: &str = "pedersen-bls-chained";
+/// drand scheme with signatures on G2 over the round alone
+const DRAND_UNCHAINED: &str = "pedersen-bls-unchained";
+/// drand scheme with signatures on G1 over the round alone, e.g. quicknet
+const DRAND_UNCHAINED_G1: &str = "bls-unchained-g1-rfc9380";
+
+const DRAND_DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const DRAND_DST_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Public randomness the value of the beacon is taken from
+pub trait BeaconSource {
+    /// Takes the randomness from the source and checks it. Gives the 32 byte seed of the beacon,
+    /// along with the evidence anyone can derive and check it again from.
+    fn seed(&self) -> Result<([u8; 32], BeaconProvenance), CeremonyError>;
+}
+
+/// Where the seed of a beacon came from. Embedded in the beacon record, so that the seed can be
+/// checked without access to the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum BeaconProvenance {
+    /// A value announced as it is
+    Hex { value: String },
+    /// A CBOR encoded Cardano block header and the block announced as the beacon
+    Cardano {
+        header: String,
+        slot: u64,
+        block_number: u64,
+    },
+    /// A Bitcoin block header and its hash in the usual byte order
+    Bitcoin { header: String, block_hash: String },
+    /// A drand round and the chain info it is signed under, which hashes to the chain hash
+    Drand {
+        chain_hash: String,
+        scheme: String,
+        public_key: String,
+        period: u32,
+        genesis_time: i64,
+        group_hash: String,
+        beacon_id: String,
+        round: u64,
+        signature: String,
+        previous_signature: Option<String>,
+    },
+}
+
+impl BeaconProvenance {
+    /// Derives the seed again from the evidence, checking it on the way
+    pub fn seed(&self) -> Result<[u8; 32], CeremonyError> {
+        match *self {
+            BeaconProvenance::Hex { ref value } => decode_seed(value, "beacon value"),
+            BeaconProvenance::Cardano {
+                ref header,
+                slot,
+                block_number,
+            } => {
+                let header = CardanoHeader::parse(&decode_hex(header, "Cardano header")?)?;
+                header.ensure_announced(slot, block_number)?;
+
+                Ok(header.hash)
+            }
+            BeaconProvenance::Bitcoin {
+                ref header,
+                ref block_hash,
+            } => {
+                let seed = bitcoin_block_hash(&decode_hex(header, "Bitcoin header")?)?;
+                if seed != decode_seed(block_hash, "Bitcoin block hash")? {
+                    return Err(CeremonyError::BeaconMismatch(
+                        "the Bitcoin block hash is not the one of the header".to_string(),
+                    ));
+                }
+
+                Ok(seed)
+            }
+            BeaconProvenance::Drand {
+                ref chain_hash,
+                ref scheme,
+                ref public_key,
+                period,
+                genesis_time,
+                ref group_hash,
+                ref beacon_id,
+                round,
+                ref signature,
+                ref previous_signature,
+            } => {
+                let public_key = decode_hex(public_key, "drand public key")?;
+                let group_hash = decode_hex(group_hash, "drand group hash")?;
+                let info_hash = drand_chain_hash(
+                    period,
+                    genesis_time,
+                    &public_key,
+                    &group_hash,
+                    scheme,
+                    beacon_id,
+                );
+                if info_hash != decode_seed(chain_hash, "drand chain hash")? {
+                    return Err(CeremonyError::BeaconMismatch(
+                        "the drand chain info does not hash to the chain hash".to_string(),
+                    ));
+                }
+
+                let signature = decode_hex(signature, "drand signature")?;
+                let previous_signature = match *previous_signature {
+                    Some(ref previous) => Some(decode_hex(previous, "drand signature")?),
+                    None => None,
+                };
+                verify_drand_signature(
+                    scheme,
+                    &public_key,
+                    round,
+                    &signature,
+                    previous_signature.as_deref(),
+                )?;
+
+                // The randomness of a drand round is the hash of its signature
+                let mut seed = [0u8; 32];
+                seed.copy_from_slice(&Sha256::digest(&signature));
+
+                Ok(seed)
+            }
+        }
+    }
+}
+
+impl fmt::Display for BeaconProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BeaconProvenance::Hex { ref value } => write!(f, "the value {}", value),
+            BeaconProvenance::Cardano {
+                slot, block_number, ..
+            } => write!(
+                f,
+                "the header of Cardano block {} in slot {}",
+                block_number, slot
+            ),
+            BeaconProvenance::Bitcoin { ref block_hash, .. } => {
+                write!(f, "the header of Bitcoin block {}", block_hash)
+            }
+            BeaconProvenance::Drand {
+                ref chain_hash,
+                round,
+                ..
+            } => write!(f, "round {} of drand chain {}", round, chain_hash),
+        }
+    }
+}
+
+/// A value announced in advance, as a hex string
+pub struct HexBeacon {
+    pub value: String,
+}
+
+impl BeaconSource for HexBeacon {
+    fn seed(&self) -> Result<([u8; 32], BeaconProvenance), CeremonyError> {
+        let provenance = BeaconProvenance::Hex {
+            value: self.value.clone(),
+        };
+
+        Ok((provenance.seed()?, provenance))
+    }
+}
+
+/// The Blake2b-256 hash of a CBOR encoded Cardano block header, which has to be of the announced
+/// block
+pub struct CardanoBeacon {
+    pub header: PathBuf,
+    pub slot: u64,
+    pub block_number: u64,
+}
+
+impl BeaconSource for CardanoBeacon {
+    fn seed(&self) -> Result<([u8; 32], BeaconProvenance), CeremonyError> {
+        let provenance = BeaconProvenance::Cardano {
+            header: hex::encode(fs::read(&self.header)?),
+            slot: self.slot,
+            block_number: self.block_number,
+        };
+
+        Ok((provenance.seed()?, provenance))
+    }
+}
+
+/// The hash of a raw 80 byte Bitcoin block header, which has to carry valid proof of work and be
+/// of the announced block
+pub struct BitcoinBeacon {
+    pub header: PathBuf,
+    /// Hex encoded hash of the block announced as the beacon, in the usual byte order
+    pub block_hash: String,
+}
+
+impl BeaconSource for BitcoinBeacon {
+    fn seed(&self) -> Result<([u8; 32], BeaconProvenance), CeremonyError> {
+        let header = fs::read(&self.header)?;
+        let block_hash = bitcoin_block_hash(&header)?;
+        if block_hash != decode_seed(&self.block_hash, "announced Bitcoin block hash")? {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "Bitcoin block {} was announced, but the header is of block {}",
+                self.block_hash,
+                hex::encode(block_hash)
+            )));
+        }
+
+        let provenance = BeaconProvenance::Bitcoin {
+            header: hex::encode(header),
+            block_hash: hex::encode(block_hash),
+        };
+
+        Ok((provenance.seed()?, provenance))
+    }
+}
+
+/// The randomness of a drand round, as served by `/public/<round>`, with the signature checked
+/// against the public key in the chain info, as served by `/info`, which has to hash to the
+/// announced chain hash
+pub struct DrandBeacon {
+    pub round: PathBuf,
+    pub chain_info: PathBuf,
+    /// Hex encoded hash of the chain announced as the source
+    pub chain_hash: String,
+    /// The round announced as the beacon, if any
+    pub round_number: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct DrandRound {
+    round: u64,
+    randomness: String,
+    signature: String,
+    #[serde(default)]
+    previous_signature: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DrandChainInfo {
+    public_key: String,
+    period: u32,
+    genesis_time: i64,
+    hash: String,
+    #[serde(rename = "groupHash")]
+    group_hash: String,
+    #[serde(rename = "schemeID", default = "default_drand_scheme")]
+    scheme: String,
+    #[serde(default)]
+    metadata: DrandMetadata,
+}
+
+#[derive(Deserialize, Default)]
+struct DrandMetadata {
+    #[serde(rename = "beaconID", default)]
+    beacon_id: String,
+}
+
+fn default_drand_scheme() -> String {
+    DRAND_CHAINED.to_string()
+}
+
+impl BeaconSource for DrandBeacon {
+    fn seed(&self) -> Result<([u8; 32], BeaconProvenance), CeremonyError> {
+        let malformed = |what: &str, e: serde_json::Error| {
+            CeremonyError::InvalidParameters(format!("malformed drand {}: {}", what, e))
+        };
+        let round: DrandRound =
+            serde_json::from_slice(&fs::read(&self.round)?).map_err(|e| malformed("round", e))?;
+        let chain_info: DrandChainInfo = serde_json::from_slice(&fs::read(&self.chain_info)?)
+            .map_err(|e| malformed("chain info", e))?;
+
+        if let Some(announced) = self.round_number {
+            if round.round != announced {
+                return Err(CeremonyError::BeaconMismatch(format!(
+                    "drand round {} was announced, but the round file is of round {}",
+                    announced, round.round
+                )));
+            }
+        }
+
+        let chain_hash = decode_seed(&self.chain_hash, "announced drand chain hash")?;
+        if decode_seed(&chain_info.hash, "drand chain hash")? != chain_hash {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "drand chain {} was announced, but the chain info is of chain {}",
+                self.chain_hash, chain_info.hash
+            )));
+        }
+
+        let provenance = BeaconProvenance::Drand {
+            chain_hash: hex::encode(chain_hash),
+            scheme: chain_info.scheme,
+            public_key: chain_info.public_key,
+            period: chain_info.period,
+            genesis_time: chain_info.genesis_time,
+            group_hash: chain_info.group_hash,
+            beacon_id: chain_info.metadata.beacon_id,
+            round: round.round,
+            signature: round.signature,
+            previous_signature: round.previous_signature,
+        };
+        let seed = provenance.seed()?;
+        if seed != decode_seed(&round.randomness, "drand randomness")? {
+            return Err(CeremonyError::BeaconMismatch(
+                "the drand randomness is not the hash of its signature".to_string(),
+            ));
+        }
+
+        Ok((seed, provenance))
+    }
+}
+
+fn decode_hex(value: &str, what: &str) -> Result<Vec<u8>, CeremonyError> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| CeremonyError::InvalidParameters(format!("malformed {}: {}", what, e)))
+}
+
+/// Decodes a hex encoded 32 byte seed
+pub(crate) fn decode_seed(value: &str, what: &str) -> Result<[u8; 32], CeremonyError> {
+    let bytes = decode_hex(value, what)?;
+    let mut seed = [0u8; 32];
+    if bytes.len() != seed.len() {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "the {} has {} bytes instead of 32",
+            what,
+            bytes.len()
+        )));
+    }
+    seed.copy_from_slice(&bytes);
+
+    Ok(seed)
+}
+
+/// Double SHA-256 of a Bitcoin block header, in the byte order block explorers show, after
+/// checking its proof of work
+fn bitcoin_block_hash(header: &[u8]) -> Result<[u8; 32], CeremonyError> {
+    if header.len() != BITCOIN_HEADER_SIZE {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "the Bitcoin block header has {} bytes instead of {}",
+            header.len(),
+            BITCOIN_HEADER_SIZE
+        )));
+    }
+
+    let mut bits = [0u8; 4];
+    bits.copy_from_slice(&header[72..76]);
+    let bits = u32::from_le_bytes(bits);
+    let target = bitcoin_target(bits)
+        .filter(|target| *target <= bitcoin_target(BITCOIN_MAX_TARGET_BITS).unwrap())
+        .ok_or_else(|| {
+            CeremonyError::BeaconMismatch(format!(
+                "the Bitcoin header has the invalid target {:#010x}",
+                bits
+            ))
+        })?;
+
+    // The hash is a little endian number, compared with the target from the most significant end
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(&Sha256::digest(header)));
+    hash.reverse();
+    if hash > target {
+        return Err(CeremonyError::BeaconMismatch(
+            "the Bitcoin header does not meet its proof of work target".to_string(),
+        ));
+    }
+
+    Ok(hash)
+}
+
+/// Big endian 256 bit target of the compact encoding `bits`, if it is a positive number that fits
+fn bitcoin_target(bits: u32) -> Option<[u8; 32]> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+    if mantissa == 0 || bits & 0x0080_0000 != 0 || !(3..=32).contains(&exponent) {
+        return None;
+    }
+
+    // The mantissa takes the `exponent` least significant bytes, the most significant one first
+    let mut target = [0u8; 32];
+    for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+        target[32 - exponent + i] = *byte;
+    }
+
+    Some(target)
+}
+
+fn verify_drand_signature(
+    scheme: &str,
+    public_key: &[u8],
+    round: u64,
+    signature: &[u8],
+    previous_signature: Option<&[u8]>,
+) -> Result<(), CeremonyError> {
+    let malformed = |what: &str| {
+        CeremonyError::InvalidParameters(format!("malformed drand {} for {}", what, scheme))
+    };
+
+    let mut message = Sha256::new();
+    if scheme == DRAND_CHAINED {
+        message.update(previous_signature.ok_or_else(|| malformed("previous signature"))?);
+    }
+    message.update(round.to_be_bytes());
+    let message = message.finalize();
+
+    let valid = match scheme {
+        DRAND_CHAINED | DRAND_UNCHAINED => {
+            let public_key = point_bytes(public_key)
+                .and_then(|bytes| Option::from(G1Affine::from_compressed(&bytes)))
+                .ok_or_else(|| malformed("public key"))?;
+            let signature = point_bytes(signature)
+                .and_then(|bytes| Option::from(G2Affine::from_compressed(&bytes)))
+                .ok_or_else(|| malformed("signature"))?;
+            let hashed = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+                message,
+                DRAND_DST_G2,
+            );
+
+            pairing(&G1Affine::generator(), &signature)
+                == pairing(&public_key, &G2Affine::from(hashed))
+        }
+        DRAND_UNCHAINED_G1 => {
+            let public_key = point_bytes(public_key)
+                .and_then(|bytes| Option::from(G2Affine::from_compressed(&bytes)))
+                .ok_or_else(|| malformed("public key"))?;
+            let signature = point_bytes(signature)
+                .and_then(|bytes| Option::from(G1Affine::from_compressed(&bytes)))
+                .ok_or_else(|| malformed("signature"))?;
+            let hashed = <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+                message,
+                DRAND_DST_G1,
+            );
+
+            pairing(&signature, &G2Affine::generator())
+                == pairing(&G1Affine::from(hashed), &public_key)
+        }
+        _ => {
+            return Err(CeremonyError::InvalidParameters(format!(
+                "unsupported drand scheme {}",
+                scheme
+            )))
+        }
+    };
+
+    if !valid {
+        return Err(CeremonyError::BeaconMismatch(format!(
+            "invalid signature of drand round {}",
+            round
+        )));
+    }
+
+    Ok(())
+}
+
+/// Hash of a drand chain, which identifies it: the SHA-256 hash of its period, genesis time,
+/// public key and group hash, followed by the scheme and the beacon ID unless they are the
+/// defaults
+fn drand_chain_hash(
+    period: u32,
+    genesis_time: i64,
+    public_key: &[u8],
+    group_hash: &[u8],
+    scheme: &str,
+    beacon_id: &str,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(period.to_be_bytes());
+    hasher.update(genesis_time.to_be_bytes());
+    hasher.update(public_key);
+    hasher.update(group_hash);
+    if scheme != DRAND_CHAINED {
+        hasher.update(scheme.as_bytes());
+    }
+    if !beacon_id.is_empty() && beacon_id != "default" {
+        hasher.update(beacon_id.as_bytes());
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+/// Bytes of a compressed point, `None` if there are not `N` of them
+fn point_bytes<const N: usize>(encoded: &[u8]) -> Option<[u8; N]> {
+    let mut bytes = [0u8; N];
+    if encoded.len() != N {
+        return None;
+    }
+    bytes.copy_from_slice(encoded);
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::Scalar;
+    use std::env;
+
+    /// Header of the Bitcoin genesis block
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000\
+        000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    #[test]
+    fn test_bitcoin_header_proof_of_work_is_checked() {
+        let path = env::temp_dir().join(format!("powersoftau-{}-bitcoin", std::process::id()));
+        let mut header = hex::decode(GENESIS_HEADER).unwrap();
+        fs::write(&path, &header).unwrap();
+
+        let (seed, provenance) = BitcoinBeacon {
+            header: path.clone(),
+            block_hash: GENESIS_HASH.to_string(),
+        }
+        .seed()
+        .unwrap();
+        assert_eq!(hex::encode(seed), GENESIS_HASH);
+        assert_eq!(provenance.seed().unwrap(), seed);
+
+        // Valid proof of work is not enough, the header has to be of the announced block
+        let unannounced = BitcoinBeacon {
+            header: path.clone(),
+            block_hash: hex::encode([7u8; 32]),
+        };
+        match unannounced.seed() {
+            Err(CeremonyError::BeaconMismatch(e)) => assert!(e.contains("was announced")),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Another nonce misses the target
+        header[79] ^= 1;
+        fs::write(&path, &header).unwrap();
+        assert!(BitcoinBeacon {
+            header: path.clone(),
+            block_hash: GENESIS_HASH.to_string(),
+        }
+        .seed()
+        .is_err());
+
+        // So does an easier target than the network allows
+        let mut easy = hex::decode(GENESIS_HEADER).unwrap();
+        easy[72..76].copy_from_slice(&0x207f_ffffu32.to_le_bytes());
+        assert!(bitcoin_block_hash(&easy).is_err());
+
+        match bitcoin_block_hash(&easy[..79]) {
+            Err(CeremonyError::InvalidParameters(e)) => {
+                assert!(e.contains("79 bytes instead of 80"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_drand_signatures_are_checked() {
+        let secret = Scalar::from(0x1234_5678_9abc_def0u64);
+        let round = 1_000_000u64;
+        let previous_signature = vec![7u8; 96];
+
+        // A chained round on G2, signed the way a drand node does
+        let message = {
+            let mut message = Sha256::new();
+            message.update(&previous_signature);
+            message.update(round.to_be_bytes());
+            message.finalize()
+        };
+        let signature = G2Affine::from(
+            <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+                message,
+                DRAND_DST_G2,
+            ) * secret,
+        );
+        let public_key = G1Affine::from(G1Affine::generator() * secret);
+        let group_hash = [9u8; 32];
+        let chain_hash = drand_chain_hash(
+            30,
+            1_595_431_050,
+            &public_key.to_compressed(),
+            &group_hash,
+            DRAND_CHAINED,
+            "",
+        );
+
+        let provenance = BeaconProvenance::Drand {
+            chain_hash: hex::encode(chain_hash),
+            scheme: DRAND_CHAINED.to_string(),
+            public_key: hex::encode(public_key.to_compressed()),
+            period: 30,
+            genesis_time: 1_595_431_050,
+            group_hash: hex::encode(group_hash),
+            beacon_id: String::new(),
+            round,
+            signature: hex::encode(signature.to_compressed()),
+            previous_signature: Some(hex::encode(&previous_signature)),
+        };
+        let seed = provenance.seed().unwrap();
+        assert_eq!(&seed[..], &Sha256::digest(&signature.to_compressed())[..]);
+
+        let another_round = match provenance.clone() {
+            BeaconProvenance::Drand {
+                chain_hash,
+                scheme,
+                public_key,
+                period,
+                genesis_time,
+                group_hash,
+                beacon_id,
+                signature,
+                previous_signature,
+                ..
+            } => BeaconProvenance::Drand {
+                chain_hash,
+                scheme,
+                public_key,
+                period,
+                genesis_time,
+                group_hash,
+                beacon_id,
+                round: round + 1,
+                signature,
+                previous_signature,
+            },
+            _ => unreachable!(),
+        };
+        assert!(another_round.seed().is_err());
+
+        // The public key has to be the one of the recorded chain
+        let another_chain = match provenance.clone() {
+            BeaconProvenance::Drand {
+                scheme,
+                public_key,
+                period,
+                genesis_time,
+                group_hash,
+                beacon_id,
+                signature,
+                previous_signature,
+                ..
+            } => BeaconProvenance::Drand {
+                chain_hash: hex::encode([7u8; 32]),
+                scheme,
+                public_key,
+                period,
+                genesis_time,
+                group_hash,
+                beacon_id,
+                round,
+                signature,
+                previous_signature,
+            },
+            _ => unreachable!(),
+        };
+        assert!(another_chain.seed().is_err());
+
+        // The files as served by a drand node
+        let directory = env::temp_dir();
+        let round_path = directory.join(format!("powersoftau-{}-drand-round", std::process::id()));
+        let info_path = directory.join(format!("powersoftau-{}-drand-info", std::process::id()));
+        fs::write(
+            &round_path,
+            format!(
+                r#"{{"round":{},"randomness":"{}","signature":"{}","previous_signature":"{}"}}"#,
+                round,
+                hex::encode(seed),
+                hex::encode(signature.to_compressed()),
+                hex::encode(&previous_signature)
+            ),
+        )
+        .unwrap();
+        let info = |hash: &[u8]| {
+            format!(
+                r#"{{"public_key":"{}","period":30,"genesis_time":1595431050,"hash":"{}","groupHash":"{}","schemeID":"{}"}}"#,
+                hex::encode(public_key.to_compressed()),
+                hex::encode(hash),
+                hex::encode(group_hash),
+                DRAND_CHAINED
+            )
+        };
+        fs::write(&info_path, info(&chain_hash)).unwrap();
+
+        let source = DrandBeacon {
+            round: round_path.clone(),
+            chain_info: info_path.clone(),
+            chain_hash: hex::encode(chain_hash),
+            round_number: Some(round),
+        };
+        assert_eq!(source.seed().unwrap(), (seed, provenance));
+        let unannounced = DrandBeacon {
+            round_number: Some(round - 1),
+            ..source
+        };
+        assert!(unannounced.seed().is_err());
+        let another_chain = DrandBeacon {
+            chain_hash: hex::encode([7u8; 32]),
+            round_number: Some(round),
+            ..unannounced
+        };
+        assert!(another_chain.seed().is_err());
+
+        // Chain info that claims the announced hash, but does not hash to it
+        fs::write(&info_path, info(&[7u8; 32])).unwrap();
+        assert!(another_chain.seed().is_err());
+
+        fs::remove_file(&round_path).unwrap();
+        fs::remove_file(&info_path).unwrap();
+    }
+
+    #[test]
+    fn test_drand_chain_hash_is_the_one_served() {
+        // The League of Entropy mainnet chain, as served by `/info`
+        let public_key = hex::decode(
+            "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31",
+        )
+        .unwrap();
+        let group_hash =
+            hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a")
+                .unwrap();
+        let hash = drand_chain_hash(
+            30,
+            1_595_431_050,
+            &public_key,
+            &group_hash,
+            DRAND_CHAINED,
+            "default",
+        );
+        assert_eq!(
+            hex::encode(hash),
+            "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce"
+        );
+    }
+
+    #[test]
+    fn test_hex_beacon_is_32_bytes() {
+        let (seed, _) = HexBeacon {
+            value: format!("0x{}", GENESIS_HASH),
+        }
+        .seed()
+        .unwrap();
+        assert_eq!(hex::encode(seed), GENESIS_HASH);
+
+        assert!(HexBeacon {
+            value: "00ff".to_string()
+        }
+        .seed()
+        .is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use blake2::{Blake2b, Digest};
+use ciborium::value::Value;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+use typenum::consts::U32;
+
+use super::parameters::CeremonyError;
+
+/// Fields of a Shelley to Alonzo header body, which has the VRF outputs and the operational
+/// certificate inlined
+const SHELLEY_HEADER_BODY_LENGTH: usize = 15;
+/// Fields of a Babbage or Conway header body
+const BABBAGE_HEADER_BODY_LENGTH: usize = 10;
+
+/// A CBOR encoded Cardano block header, the random beacon of the ceremony plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardanoHeader {
+    pub block_number: u64,
+    pub slot: u64,
+    /// Blake2b-256 hash of the encoded header, which is how Cardano identifies the block
+    pub hash: [u8; 32],
+}
+
+impl CardanoHeader {
+    /// Decodes a header, i.e. the array of a header body and its KES signature. The body has to
+    /// have the layout of one of the Shelley based eras.
+    pub fn parse(encoded: &[u8]) -> Result<Self, CeremonyError> {
+        let invalid = |reason: &str| {
+            CeremonyError::InvalidParameters(format!("invalid Cardano header: {}", reason))
+        };
+
+        let mut reader = encoded;
+        let header: Value = ciborium::de::from_reader(&mut reader).map_err(|e| {
+            CeremonyError::InvalidParameters(format!("invalid Cardano header: {}", e))
+        })?;
+        if !reader.is_empty() {
+            return Err(invalid("trailing bytes after the header"));
+        }
+
+        let body = match header {
+            Value::Array(ref header) if header.len() == 2 => match header[0] {
+                Value::Array(ref body) => body,
+                _ => return Err(invalid("the header body is not an array")),
+            },
+            _ => return Err(invalid("not an array of a header body and a signature")),
+        };
+        if body.len() != SHELLEY_HEADER_BODY_LENGTH && body.len() != BABBAGE_HEADER_BODY_LENGTH {
+            return Err(invalid(&format!(
+                "unexpected header body of {} fields",
+                body.len()
+            )));
+        }
+
+        let unsigned = |value: &Value, field: &str| -> Result<u64, CeremonyError> {
+            value
+                .as_integer()
+                .and_then(|value| u64::try_from(value).ok())
+                .ok_or_else(|| invalid(&format!("the {} is not an unsigned integer", field)))
+        };
+        let block_number = unsigned(&body[0], "block number")?;
+        let slot = unsigned(&body[1], "slot")?;
+        match body[2] {
+            Value::Null => {}
+            Value::Bytes(ref hash) if hash.len() == 32 => {}
+            _ => return Err(invalid("the previous block hash is malformed")),
+        }
+
+        let mut hasher = Blake2b::<U32>::default();
+        Digest::update(&mut hasher, encoded);
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+
+        Ok(Self {
+            block_number,
+            slot,
+            hash,
+        })
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, CeremonyError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Checks that this is the header of the block that was announced as the beacon
+    pub fn ensure_announced(&self, slot: u64, block_number: u64) -> Result<(), CeremonyError> {
+        if self.slot != slot || self.block_number != block_number {
+            return Err(CeremonyError::BeaconMismatch(format!(
+                "the header is of block {} in slot {}, but block {} in slot {} was announced",
+                self.block_number, self.slot, block_number, slot
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &Value) -> Vec<u8> {
+        let mut encoded = vec![];
+        ciborium::ser::into_writer(value, &mut encoded).unwrap();
+        encoded
+    }
+
+    fn header(body_length: usize) -> Value {
+        let mut body = vec![
+            Value::Integer(10_000_000.into()),
+            Value::Integer(130_000_000.into()),
+            Value::Bytes(vec![1; 32]),
+        ];
+        body.resize(body_length, Value::Bytes(vec![2; 32]));
+
+        Value::Array(vec![Value::Array(body), Value::Bytes(vec![3; 448])])
+    }
+
+    #[test]
+    fn test_header_is_parsed_and_hashed() {
+        for &length in &[SHELLEY_HEADER_BODY_LENGTH, BABBAGE_HEADER_BODY_LENGTH] {
+            let encoded = encode(&header(length));
+            let parsed = CardanoHeader::parse(&encoded).unwrap();
+            assert_eq!(parsed.block_number, 10_000_000);
+            assert_eq!(parsed.slot, 130_000_000);
+
+            let mut hasher = Blake2b::<U32>::default();
+            Digest::update(&mut hasher, &encoded);
+            assert_eq!(&parsed.hash[..], &hasher.finalize()[..]);
+
+            assert!(parsed.ensure_announced(130_000_000, 10_000_000).is_ok());
+            assert!(parsed.ensure_announced(130_000_001, 10_000_000).is_err());
+        }
+
+        assert!(CardanoHeader::parse(&encode(&header(12))).is_err());
+        let mut trailing = encode(&header(BABBAGE_HEADER_BODY_LENGTH));
+        trailing.push(0);
+        assert!(CardanoHeader::parse(&trailing).is_err());
+        assert!(CardanoHeader::parse(&[0xff, 0x00]).is_err());
+    }
+}
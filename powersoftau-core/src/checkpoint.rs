@@ -0,0 +1,506 @@
+use bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
+use bellman::pairing::{CurveAffine, EncodedPoint, Engine};
+use blake2::{Blake2b, Digest};
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::chacha20poly1305::ChaCha20Poly1305;
+use crypto::scrypt::{scrypt, ScryptParams};
+use generic_array::GenericArray;
+use rand::{OsRng, Rng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use typenum::consts::U64;
+
+use super::curve::CurveKind;
+use super::keypair::{PrivateKey, PublicKey};
+use super::parameters::{CeremonyError, UseCompression};
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Sidecar file recording how far a contribution got, so that an interrupted run can be resumed.
+/// Chunks are written in order, so the completed chunk ranges always form the prefix
+/// `0..completed_powers` of the powers of tau.
+///
+/// The private key is never part of the checkpoint. A run can only be resumed by a process that
+/// still holds the key in memory, or with a key recovered from an explicit `escrow_private_key`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContributionCheckpoint {
+    /// Hex encoded hash of the challenge the contribution is computed on
+    pub challenge_hash: String,
+    /// Hex encoded hash of the public key, binding the checkpoint to a single private key
+    pub public_key_hash: String,
+    pub curve: CurveKind,
+    pub required_power: usize,
+    pub compressed_output: bool,
+    /// Every power below this one is written to the response and flushed
+    pub completed_powers: usize,
+}
+
+impl ContributionCheckpoint {
+    pub fn new<E: Engine>(
+        challenge_hash: &[u8],
+        public_key: &PublicKey<E>,
+        compress_the_output: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<Self, CeremonyError> {
+        Ok(Self {
+            challenge_hash: hex::encode(challenge_hash),
+            public_key_hash: hex::encode(public_key_hash(public_key)?),
+            curve: parameters.curve(),
+            required_power: parameters.required_power(),
+            compressed_output: compress_the_output == UseCompression::Yes,
+            completed_powers: 0,
+        })
+    }
+
+    /// Location of the checkpoint of the response at `response`
+    pub fn path_for<P: AsRef<Path>>(response: P) -> PathBuf {
+        let mut path = response.as_ref().as_os_str().to_owned();
+        path.push(".checkpoint");
+        PathBuf::from(path)
+    }
+
+    /// Reads a checkpoint, if there is one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, CeremonyError> {
+        load(path)
+    }
+
+    /// Replaces the checkpoint at `path`. The new checkpoint is written next to it first,
+    /// so an interruption leaves either the old or the new checkpoint behind.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CeremonyError> {
+        save(self, path)
+    }
+
+    /// Checks that `other` describes the same contribution, regardless of how far each got
+    pub fn ensure_same_contribution(&self, other: &Self) -> Result<(), CeremonyError> {
+        let mismatch = if self.challenge_hash != other.challenge_hash {
+            Some("it was computed on a different challenge")
+        } else if self.public_key_hash != other.public_key_hash {
+            Some("it was computed with a different key")
+        } else if self.curve != other.curve
+            || self.required_power != other.required_power
+            || self.compressed_output != other.compressed_output
+        {
+            Some("it was computed with different ceremony parameters")
+        } else {
+            None
+        };
+
+        match mismatch {
+            Some(reason) => Err(CeremonyError::Checkpoint(reason.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn load<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<Option<T>, CeremonyError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    serde_json::from_reader(file)
+        .map(Some)
+        .map_err(|e| CeremonyError::Checkpoint(format!("malformed checkpoint: {}", e)))
+}
+
+fn save<T: Serialize, P: AsRef<Path>>(checkpoint: &T, path: P) -> Result<(), CeremonyError> {
+    let mut temporary = path.as_ref().as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    {
+        let mut file = File::create(&temporary)?;
+        serde_json::to_writer_pretty(&mut file, checkpoint)
+            .map_err(|e| CeremonyError::Checkpoint(e.to_string()))?;
+        file.sync_all()?;
+    }
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+/// Powers of a response that passed verification so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerifiedPowers {
+    /// Every power below this one is verified
+    pub verified_powers: usize,
+    /// Hex encoded TauG1 powers on both sides of TAU_POWERS_LENGTH. They are verified
+    /// against each other once both regions of TauG1 are.
+    pub tau_powers_last_first_chunks: [Option<String>; 2],
+}
+
+/// Sidecar file recording how far the verification of a response got, so that an interrupted
+/// verification can be resumed. It only applies to the exact challenge and response it names.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationCheckpoint {
+    /// Hex encoded hash of the challenge
+    pub challenge_hash: String,
+    /// Hex encoded hash of the response under verification
+    pub response_hash: String,
+    pub curve: CurveKind,
+    pub required_power: usize,
+    pub compressed_input: bool,
+    pub compressed_output: bool,
+    pub verified: VerifiedPowers,
+}
+
+impl VerificationCheckpoint {
+    pub fn new(
+        challenge_hash: &[u8],
+        response_hash: &[u8],
+        input_is_compressed: UseCompression,
+        output_is_compressed: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Self {
+        Self {
+            challenge_hash: hex::encode(challenge_hash),
+            response_hash: hex::encode(response_hash),
+            curve: parameters.curve(),
+            required_power: parameters.required_power(),
+            compressed_input: input_is_compressed == UseCompression::Yes,
+            compressed_output: output_is_compressed == UseCompression::Yes,
+            verified: VerifiedPowers::default(),
+        }
+    }
+
+    /// Location of the verification checkpoint of the response at `response`
+    pub fn path_for<P: AsRef<Path>>(response: P) -> PathBuf {
+        let mut path = response.as_ref().as_os_str().to_owned();
+        path.push(".verification");
+        PathBuf::from(path)
+    }
+
+    /// Reads a checkpoint, if there is one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, CeremonyError> {
+        load(path)
+    }
+
+    /// Replaces the checkpoint at `path`, see `ContributionCheckpoint::save`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CeremonyError> {
+        save(self, path)
+    }
+
+    /// Checks that `other` describes the verification of the same files, regardless of how far each got
+    pub fn ensure_same_verification(&self, other: &Self) -> Result<(), CeremonyError> {
+        let mismatch = if self.challenge_hash != other.challenge_hash {
+            Some("it was recorded for a different challenge")
+        } else if self.response_hash != other.response_hash {
+            Some("it was recorded for a different response")
+        } else if self.curve != other.curve
+            || self.required_power != other.required_power
+            || self.compressed_input != other.compressed_input
+            || self.compressed_output != other.compressed_output
+        {
+            Some("it was recorded with different ceremony parameters")
+        } else {
+            None
+        };
+
+        match mismatch {
+            Some(reason) => Err(CeremonyError::Checkpoint(reason.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Final step of a verification: succeeds only if every power of the response with
+    /// `response_hash` is recorded as verified against the challenge with `challenge_hash`
+    pub fn ensure_complete(
+        &self,
+        challenge_hash: &[u8],
+        response_hash: &[u8],
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<(), CeremonyError> {
+        if self.challenge_hash != hex::encode(challenge_hash)
+            || self.response_hash != hex::encode(response_hash)
+        {
+            return Err(CeremonyError::Checkpoint(
+                "the verification was recorded for different files".to_string(),
+            ));
+        }
+        if self.verified.verified_powers != parameters.tau_powers_g1_length()
+            || self
+                .verified
+                .tau_powers_last_first_chunks
+                .iter()
+                .any(Option::is_none)
+        {
+            return Err(CeremonyError::Checkpoint(format!(
+                "only {} out of {} powers are verified",
+                self.verified.verified_powers,
+                parameters.tau_powers_g1_length()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Hex encoding of the uncompressed form of a point
+pub(crate) fn encode_point<G: CurveAffine>(point: &G) -> String {
+    hex::encode(point.into_uncompressed().as_ref())
+}
+
+pub(crate) fn decode_point<G: CurveAffine>(encoded: &str) -> Result<G, CeremonyError> {
+    let bytes = hex::decode(encoded)
+        .map_err(|e| CeremonyError::Checkpoint(format!("malformed point: {}", e)))?;
+    let mut repr = G::Uncompressed::empty();
+    if bytes.len() != repr.as_ref().len() {
+        return Err(CeremonyError::Checkpoint(format!(
+            "malformed point: expected {} bytes, found {}",
+            repr.as_ref().len(),
+            bytes.len()
+        )));
+    }
+    repr.as_mut().copy_from_slice(&bytes);
+
+    repr.into_affine()
+        .map_err(|e| CeremonyError::Checkpoint(format!("malformed point: {}", e)))
+}
+
+/// BLAKE2b hash of the serialized public key
+pub fn public_key_hash<E: Engine>(public_key: &PublicKey<E>) -> Result<Vec<u8>, CeremonyError> {
+    let mut serialized = vec![];
+    public_key.serialize(&mut serialized)?;
+
+    let mut hasher = Blake2b::default();
+    Digest::update(&mut hasher, &serialized);
+    let hash: GenericArray<u8, U64> = hasher.finalize();
+
+    Ok(hash.to_vec())
+}
+
+const ESCROW_MAGIC: &[u8; 8] = b"PTAUKEY1";
+const ESCROW_SALT_SIZE: usize = 32;
+const ESCROW_NONCE_SIZE: usize = 8;
+const ESCROW_TAG_SIZE: usize = 16;
+
+fn escrow_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    scrypt(
+        passphrase.as_bytes(),
+        salt,
+        &ScryptParams::new(15, 8, 1),
+        &mut key,
+    );
+
+    key
+}
+
+/// Writes the private key to `path`, encrypted with a key derived from `passphrase` and bound
+/// to the public key with `public_key_hash`.
+///
+/// WARNING: this keeps the toxic waste of the contribution on disk. Anyone who gets hold of the
+/// file and the passphrase can undo the contribution, so it must be deleted once it is done.
+pub fn escrow_private_key<E: Engine, P: AsRef<Path>>(
+    key: &PrivateKey<E>,
+    public_key_hash: &[u8],
+    passphrase: &str,
+    path: P,
+) -> Result<(), CeremonyError> {
+    let mut plaintext = vec![];
+    for scalar in &[key.tau, key.alpha, key.beta] {
+        scalar.into_repr().write_be(&mut plaintext)?;
+    }
+
+    let mut rng = OsRng::new()
+        .map_err(|e| CeremonyError::Checkpoint(format!("no system randomness: {}", e)))?;
+    let mut salt = [0u8; ESCROW_SALT_SIZE];
+    let mut nonce = [0u8; ESCROW_NONCE_SIZE];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let mut ciphertext = vec![0u8; plaintext.len()];
+    let mut tag = [0u8; ESCROW_TAG_SIZE];
+    ChaCha20Poly1305::new(&escrow_key(passphrase, &salt), &nonce, public_key_hash).encrypt(
+        &plaintext,
+        &mut ciphertext,
+        &mut tag,
+    );
+    for byte in plaintext.iter_mut() {
+        *byte = 0;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(ESCROW_MAGIC)?;
+    file.write_all(&salt)?;
+    file.write_all(&nonce)?;
+    file.write_all(&tag)?;
+    file.write_all(&ciphertext)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// Decrypts a private key written by `escrow_private_key` for the public key with `public_key_hash`
+pub fn recover_private_key<E: Engine, P: AsRef<Path>>(
+    public_key_hash: &[u8],
+    passphrase: &str,
+    path: P,
+) -> Result<PrivateKey<E>, CeremonyError> {
+    let mut contents = vec![];
+    File::open(path)?.read_to_end(&mut contents)?;
+
+    let header_size = ESCROW_MAGIC.len() + ESCROW_SALT_SIZE + ESCROW_NONCE_SIZE + ESCROW_TAG_SIZE;
+    let scalar_size = E::Fr::zero().into_repr().as_ref().len() * 8;
+    if contents.len() != header_size + 3 * scalar_size || &contents[0..8] != ESCROW_MAGIC {
+        return Err(CeremonyError::Checkpoint(
+            "the key escrow is not in the expected format".to_string(),
+        ));
+    }
+
+    let (salt, rest) = contents[ESCROW_MAGIC.len()..].split_at(ESCROW_SALT_SIZE);
+    let (nonce, rest) = rest.split_at(ESCROW_NONCE_SIZE);
+    let (tag, ciphertext) = rest.split_at(ESCROW_TAG_SIZE);
+
+    let mut plaintext = vec![0u8; ciphertext.len()];
+    if !ChaCha20Poly1305::new(&escrow_key(passphrase, salt), nonce, public_key_hash).decrypt(
+        ciphertext,
+        &mut plaintext,
+        tag,
+    ) {
+        return Err(CeremonyError::Checkpoint(
+            "wrong passphrase, or the key escrow belongs to another contribution".to_string(),
+        ));
+    }
+
+    let mut scalars = vec![];
+    for encoded in plaintext.chunks(scalar_size) {
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        repr.read_be(encoded)?;
+        let scalar = E::Fr::from_repr(repr)
+            .map_err(|e| CeremonyError::Checkpoint(format!("invalid escrowed key: {}", e)))?;
+        scalars.push(scalar);
+    }
+    for byte in plaintext.iter_mut() {
+        *byte = 0;
+    }
+
+    Ok(PrivateKey {
+        tau: scalars[0],
+        alpha: scalars[1],
+        beta: scalars[2],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::Bn256;
+    use rand::chacha::ChaChaRng;
+    use rand::SeedableRng;
+    use std::env;
+
+    use crate::keypair::keypair;
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("powersoftau-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let parameters = RuntimeCeremonyParameters::default_bn254();
+        let mut rng = ChaChaRng::from_seed(&[1u32, 2, 3, 4]);
+        let (pubkey, _) = keypair::<_, Bn256>(&mut rng, &[0u8; 64]).unwrap();
+
+        let mut checkpoint =
+            ContributionCheckpoint::new(&[1u8; 64], &pubkey, UseCompression::Yes, &parameters)
+                .unwrap();
+        checkpoint.completed_powers = 42;
+
+        let path = temporary_path("checkpoint");
+        assert_eq!(ContributionCheckpoint::load(&path).unwrap(), None);
+        checkpoint.save(&path).unwrap();
+        let loaded = ContributionCheckpoint::load(&path).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded
+            .ensure_same_contribution(&ContributionCheckpoint {
+                completed_powers: 0,
+                ..checkpoint.clone()
+            })
+            .is_ok());
+        assert!(loaded
+            .ensure_same_contribution(&ContributionCheckpoint {
+                challenge_hash: hex::encode([2u8; 64]),
+                ..checkpoint.clone()
+            })
+            .is_err());
+        assert!(loaded
+            .ensure_same_contribution(&ContributionCheckpoint {
+                compressed_output: false,
+                ..checkpoint
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_escrowed_key_is_recovered_with_the_passphrase() {
+        let mut rng = ChaChaRng::from_seed(&[1u32, 2, 3, 4]);
+        let (pubkey, privkey) = keypair::<_, Bn256>(&mut rng, &[0u8; 64]).unwrap();
+        let key_hash = public_key_hash(&pubkey).unwrap();
+
+        let path = temporary_path("escrow");
+        escrow_private_key(&privkey, &key_hash, "correct horse", &path).unwrap();
+        // An existing escrow is never overwritten
+        assert!(escrow_private_key(&privkey, &key_hash, "correct horse", &path).is_err());
+
+        let recovered = recover_private_key::<Bn256, _>(&key_hash, "correct horse", &path);
+        let wrong_passphrase = recover_private_key::<Bn256, _>(&key_hash, "battery staple", &path);
+        let wrong_key = recover_private_key::<Bn256, _>(&[0u8; 64], "correct horse", &path);
+        fs::remove_file(&path).unwrap();
+
+        let recovered = recovered.unwrap();
+        assert!(recovered.tau == privkey.tau);
+        assert!(recovered.alpha == privkey.alpha);
+        assert!(recovered.beta == privkey.beta);
+        assert!(wrong_passphrase.is_err());
+        assert!(wrong_key.is_err());
+    }
+
+    #[test]
+    fn test_verification_is_complete_only_for_the_recorded_files() {
+        let mut parameters = crate::config::CeremonyConfig::default_bn254();
+        parameters.ceremony.required_power = 2;
+        let parameters = RuntimeCeremonyParameters::new(parameters);
+        let generator = encode_point(&<Bn256 as Engine>::G1Affine::one());
+        assert!(
+            decode_point::<<Bn256 as Engine>::G1Affine>(&generator).unwrap()
+                == <Bn256 as Engine>::G1Affine::one()
+        );
+
+        let mut checkpoint = VerificationCheckpoint::new(
+            &[1u8; 64],
+            &[2u8; 64],
+            UseCompression::No,
+            UseCompression::Yes,
+            &parameters,
+        );
+        checkpoint.verified = VerifiedPowers {
+            verified_powers: parameters.tau_powers_g1_length() - 1,
+            tau_powers_last_first_chunks: [Some(generator.clone()), Some(generator)],
+        };
+        assert!(checkpoint
+            .ensure_complete(&[1u8; 64], &[2u8; 64], &parameters)
+            .is_err());
+
+        checkpoint.verified.verified_powers = parameters.tau_powers_g1_length();
+        assert!(checkpoint
+            .ensure_complete(&[1u8; 64], &[2u8; 64], &parameters)
+            .is_ok());
+        assert!(checkpoint
+            .ensure_complete(&[1u8; 64], &[3u8; 64], &parameters)
+            .is_err());
+    }
+}
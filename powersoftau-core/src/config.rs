@@ -1,11 +1,19 @@
-use config::{Config, ConfigError, File};
-use serde::Deserialize;
+use config::builder::DefaultState;
+use config::{Config, ConfigBuilder, ConfigError, File};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
 use std::path::Path;
 
+use crate::beacon::BeaconParameters;
+use crate::curve::CurveKind;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CeremonyConfig {
     pub ceremony: CeremonyParameters,
     pub curve: CurveParameters,
+    /// Random beacon of the final contribution. Only `beacon_constrained` and `verify_beacon` use it.
+    #[serde(default)]
+    pub beacon: BeaconParameters,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -13,15 +21,24 @@ pub struct CeremonyParameters {
     pub required_power: usize,
     pub compression: CompressionSetting,
     pub check_correctness: bool,
+    /// Memory budget in bytes that chunk sizes are derived from, e.g. `max_memory = "8GiB"`
+    #[serde(default, deserialize_with = "deserialize_memory_size")]
+    pub max_memory: Option<u64>,
+    /// Keep an encrypted copy of the private key next to the response, so that an interrupted
+    /// contribution can be resumed by a new process. Off unless explicitly asked for.
+    #[serde(default)]
+    pub key_escrow: bool,
+    /// Write a `FileHeader` in front of new challenge and response files. Files without one are read either way.
+    #[serde(default = "default_file_header")]
+    pub file_header: bool,
+    /// Which vectors the accumulator holds, `full` unless `tau_only` is asked for
+    #[serde(default)]
+    pub mode: CeremonyMode,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct CurveParameters {
-    pub name: String,
-    pub g1_uncompressed_byte_size: usize,
-    pub g2_uncompressed_byte_size: usize,
-    pub g1_compressed_byte_size: usize,
-    pub g2_compressed_byte_size: usize,
+    pub name: CurveKind,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -31,25 +48,75 @@ pub enum CompressionSetting {
     No,
 }
 
+/// Vectors of the accumulator. Groth16 needs alpha and beta next to the powers of tau, while
+/// KZG based systems like PLONK only need the powers of tau.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CeremonyMode {
+    /// TauG1, TauG2, AlphaG1, BetaG1 and BetaG2
+    #[default]
+    Full,
+    /// TauG1 and TauG2 only
+    TauOnly,
+}
+
+impl fmt::Display for CeremonyMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CeremonyMode::Full => write!(f, "full"),
+            CeremonyMode::TauOnly => write!(f, "tau_only"),
+        }
+    }
+}
+
 impl CeremonyConfig {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
         let config = Config::builder()
             .add_source(File::from(path.as_ref()))
             .build()?;
-        
+
         config.try_deserialize()
     }
 
     pub fn from_env_with_defaults() -> Result<Self, ConfigError> {
+        let config = Self::builder_with_defaults()?.build()?;
+        config.try_deserialize()
+    }
+
+    /// Same as `from_env_with_defaults`, with command line flags overriding everything else.
+    /// Every flag takes a value, either as `--flag value` or as `--flag=value`.
+    pub fn from_env_and_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ConfigError> {
+        let mut config_builder = Self::builder_with_defaults()?;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, value) = match arg.find('=') {
+                Some(split) => (arg[..split].to_string(), arg[split + 1..].to_string()),
+                None => {
+                    let value = args.next().ok_or_else(|| {
+                        ConfigError::Message(format!("missing value for {}", arg))
+                    })?;
+                    (arg, value)
+                }
+            };
+            let key = CLI_OVERRIDES
+                .iter()
+                .find(|(name, _)| *name == flag)
+                .map(|(_, key)| *key)
+                .ok_or_else(|| ConfigError::Message(format!("unknown argument {}", flag)))?;
+            config_builder = config_builder.set_override(key, value)?;
+        }
+
+        let config = config_builder.build()?;
+        config.try_deserialize()
+    }
+
+    fn builder_with_defaults() -> Result<ConfigBuilder<DefaultState>, ConfigError> {
         let mut config_builder = Config::builder()
             .set_default("ceremony.required_power", 8)?
             .set_default("ceremony.compression", "no")?
             .set_default("ceremony.check_correctness", true)?
-            .set_default("curve.name", "bls12_381")?
-            .set_default("curve.g1_uncompressed_byte_size", 96)?
-            .set_default("curve.g2_uncompressed_byte_size", 192)?
-            .set_default("curve.g1_compressed_byte_size", 48)?
-            .set_default("curve.g2_compressed_byte_size", 96)?;
+            .set_default("curve.name", "bls12_381")?;
 
         // Try to load from ceremony.toml if it exists
         if Path::new("ceremony.toml").exists() {
@@ -57,11 +124,10 @@ impl CeremonyConfig {
         }
 
         // Override with environment variables
-        config_builder = config_builder
-            .add_source(config::Environment::with_prefix("CEREMONY").separator("__"));
+        config_builder =
+            config_builder.add_source(config::Environment::with_prefix("CEREMONY").separator("__"));
 
-        let config = config_builder.build()?;
-        config.try_deserialize()
+        Ok(config_builder)
     }
 
     pub fn default_bls12_381() -> Self {
@@ -70,14 +136,24 @@ impl CeremonyConfig {
                 required_power: 8,
                 compression: CompressionSetting::No,
                 check_correctness: true,
+                max_memory: None,
+                key_escrow: false,
+                file_header: true,
+                mode: CeremonyMode::Full,
             },
             curve: CurveParameters {
-                name: "bls12_381".to_string(),
-                g1_uncompressed_byte_size: 96,
-                g2_uncompressed_byte_size: 192,
-                g1_compressed_byte_size: 48,
-                g2_compressed_byte_size: 96,
+                name: CurveKind::Bls12_381,
             },
+            beacon: BeaconParameters::default(),
+        }
+    }
+
+    pub fn default_bn254() -> Self {
+        Self {
+            curve: CurveParameters {
+                name: CurveKind::Bn254,
+            },
+            ..Self::default_bls12_381()
         }
     }
 
@@ -89,29 +165,77 @@ impl CeremonyConfig {
     pub fn tau_powers_g1_length(&self) -> usize {
         (self.tau_powers_length() << 1) - 1
     }
+}
 
-    pub fn accumulator_byte_size(&self) -> usize {
-        (self.tau_powers_g1_length() * self.curve.g1_uncompressed_byte_size) + // g1 tau powers
-        (self.tau_powers_length() * self.curve.g2_uncompressed_byte_size) + // g2 tau powers
-        (self.tau_powers_length() * self.curve.g1_uncompressed_byte_size) + // alpha tau powers
-        (self.tau_powers_length() * self.curve.g1_uncompressed_byte_size) + // beta tau powers
-        self.curve.g2_uncompressed_byte_size + // beta in g2
-        64 // blake2b hash size
-    }
+/// Command line flags and the configuration keys they override
+const CLI_OVERRIDES: &[(&str, &str)] = &[
+    ("--max-memory", "ceremony.max_memory"),
+    ("--key-escrow", "ceremony.key_escrow"),
+    ("--file-header", "ceremony.file_header"),
+    ("--mode", "ceremony.mode"),
+    ("--beacon-value", "beacon.value"),
+    ("--beacon-iterations-exp", "beacon.iterations_exp"),
+    ("--beacon-hash", "beacon.hash"),
+    ("--beacon-source", "beacon.source"),
+    ("--beacon-cardano-header", "beacon.cardano_header"),
+    ("--beacon-cardano-slot", "beacon.cardano_slot"),
+    (
+        "--beacon-cardano-block-number",
+        "beacon.cardano_block_number",
+    ),
+    ("--beacon-bitcoin-header", "beacon.bitcoin_header"),
+    ("--beacon-bitcoin-block-hash", "beacon.bitcoin_block_hash"),
+    ("--beacon-drand-round", "beacon.drand_round"),
+    ("--beacon-drand-chain-info", "beacon.drand_chain_info"),
+    ("--beacon-drand-chain-hash", "beacon.drand_chain_hash"),
+    ("--beacon-drand-round-number", "beacon.drand_round_number"),
+];
+
+fn default_file_header() -> bool {
+    true
+}
+
+/// Parses a memory size such as `1073741824`, `512MB` or `8GiB`. Units are binary multiples.
+pub fn parse_memory_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid memory size `{}`", value))?;
+    let shift = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 10,
+        "m" | "mb" | "mib" => 20,
+        "g" | "gb" | "gib" => 30,
+        "t" | "tb" | "tib" => 40,
+        _ => return Err(format!("unknown unit in memory size `{}`", value)),
+    };
 
-    pub fn public_key_size(&self) -> usize {
-        3 * self.curve.g2_uncompressed_byte_size + // tau, alpha, and beta in g2
-        6 * self.curve.g1_uncompressed_byte_size // (s1, s1*tau), (s2, s2*alpha), (s3, s3*beta) in g1
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("memory size `{}` is too large", value))
+}
+
+fn deserialize_memory_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MemorySize {
+        Bytes(u64),
+        Text(String),
     }
 
-    pub fn contribution_byte_size(&self) -> usize {
-        (self.tau_powers_g1_length() * self.curve.g1_compressed_byte_size) + // g1 tau powers
-        (self.tau_powers_length() * self.curve.g2_compressed_byte_size) + // g2 tau powers
-        (self.tau_powers_length() * self.curve.g1_compressed_byte_size) + // alpha tau powers
-        (self.tau_powers_length() * self.curve.g1_compressed_byte_size) + // beta tau powers
-        self.curve.g2_compressed_byte_size + // beta in g2
-        64 + // blake2b hash of input accumulator
-        self.public_key_size() // public key
+    match Option::<MemorySize>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(MemorySize::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(MemorySize::Text(text)) => parse_memory_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
     }
 }
 
@@ -131,4 +255,82 @@ impl From<bool> for crate::parameters::CheckForCorrectness {
             false => crate::parameters::CheckForCorrectness::No,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("1024"), Ok(1024));
+        assert_eq!(parse_memory_size("512MB"), Ok(512 << 20));
+        assert_eq!(parse_memory_size("8GiB"), Ok(8 << 30));
+        assert_eq!(parse_memory_size("2 t"), Ok(2 << 40));
+        assert!(parse_memory_size("GB").is_err());
+        assert!(parse_memory_size("8XB").is_err());
+    }
+
+    #[test]
+    fn test_max_memory_from_args() {
+        let config = CeremonyConfig::from_env_and_args(vec![
+            "--max-memory".to_string(),
+            "64MiB".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(config.ceremony.max_memory, Some(64 << 20));
+
+        let config =
+            CeremonyConfig::from_env_and_args(vec!["--max-memory=1GB".to_string()]).unwrap();
+        assert_eq!(config.ceremony.max_memory, Some(1 << 30));
+
+        assert!(CeremonyConfig::from_env_and_args(vec!["--unknown=1".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_key_escrow_is_opt_in() {
+        let config = CeremonyConfig::from_env_and_args(Vec::<String>::new()).unwrap();
+        assert!(!config.ceremony.key_escrow);
+
+        let config =
+            CeremonyConfig::from_env_and_args(vec!["--key-escrow=true".to_string()]).unwrap();
+        assert!(config.ceremony.key_escrow);
+    }
+
+    #[test]
+    fn test_mode_defaults_to_full() {
+        let config = CeremonyConfig::from_env_and_args(Vec::<String>::new()).unwrap();
+        assert_eq!(config.ceremony.mode, CeremonyMode::Full);
+
+        let config =
+            CeremonyConfig::from_env_and_args(vec!["--mode=tau_only".to_string()]).unwrap();
+        assert_eq!(config.ceremony.mode, CeremonyMode::TauOnly);
+
+        assert!(CeremonyConfig::from_env_and_args(vec!["--mode=alpha".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_beacon_from_args() {
+        let config = CeremonyConfig::from_env_and_args(Vec::<String>::new()).unwrap();
+        assert_eq!(config.beacon, BeaconParameters::default());
+
+        let config = CeremonyConfig::from_env_and_args(vec![
+            "--beacon-iterations-exp=12".to_string(),
+            "--beacon-hash".to_string(),
+            "blake2b256".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(config.beacon.iterations_exp, 12);
+        assert_eq!(config.beacon.hash, crate::beacon::BeaconHash::Blake2b256);
+        assert_eq!(config.beacon.value, BeaconParameters::default().value);
+
+        let config = CeremonyConfig::from_env_and_args(vec![
+            "--beacon-source=drand".to_string(),
+            "--beacon-drand-round-number".to_string(),
+            "1000".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(config.beacon.source, crate::beacon::BeaconSourceKind::Drand);
+        assert_eq!(config.beacon.drand_round_number, Some(1000));
+    }
+}
@@ -0,0 +1,65 @@
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::{CurveAffine, EncodedPoint, Engine};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::mem;
+
+/// Elliptic curves a ceremony can be run on. Selected by `curve.name` in `ceremony.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CurveKind {
+    #[serde(rename = "bls12_381")]
+    Bls12_381,
+    #[serde(rename = "bn254", alias = "bn256")]
+    Bn254,
+}
+
+impl CurveKind {
+    /// Serialized point sizes of the engine behind this curve
+    pub fn point_sizes(&self) -> PointSizes {
+        match *self {
+            CurveKind::Bls12_381 => PointSizes::for_engine::<Bls12>(),
+            CurveKind::Bn254 => PointSizes::for_engine::<Bn256>(),
+        }
+    }
+}
+
+impl fmt::Display for CurveKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CurveKind::Bls12_381 => write!(f, "bls12_381"),
+            CurveKind::Bn254 => write!(f, "bn254"),
+        }
+    }
+}
+
+/// Byte sizes of encoded G1 and G2 points for a particular engine, together with the sizes
+/// of the in-memory representations that chunk sizes are derived from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointSizes {
+    pub g1_uncompressed: usize,
+    pub g2_uncompressed: usize,
+    pub g1_compressed: usize,
+    pub g2_compressed: usize,
+    pub g1_affine: usize,
+    pub g2_affine: usize,
+    pub g1_projective: usize,
+    pub g2_projective: usize,
+    pub scalar: usize,
+}
+
+impl PointSizes {
+    pub fn for_engine<E: Engine>() -> Self {
+        Self {
+            g1_uncompressed: <E::G1Affine as CurveAffine>::Uncompressed::size(),
+            g2_uncompressed: <E::G2Affine as CurveAffine>::Uncompressed::size(),
+            g1_compressed: <E::G1Affine as CurveAffine>::Compressed::size(),
+            g2_compressed: <E::G2Affine as CurveAffine>::Compressed::size(),
+            g1_affine: mem::size_of::<E::G1Affine>(),
+            g2_affine: mem::size_of::<E::G2Affine>(),
+            g1_projective: mem::size_of::<E::G1>(),
+            g2_projective: mem::size_of::<E::G2>(),
+            scalar: mem::size_of::<E::Fr>(),
+        }
+    }
+}
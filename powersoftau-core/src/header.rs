@@ -0,0 +1,263 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use super::config::CeremonyMode;
+use super::curve::CurveKind;
+use super::parameters::{CeremonyError, TranscriptFile, UseCompression};
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Marks a challenge or response file that starts with a `FileHeader`
+pub const HEADER_MAGIC: &[u8; 8] = b"PTAUFILE";
+/// Version of the header layout written by this crate
+pub const HEADER_VERSION: u16 = 1;
+/// Size of the header on disk. Fields after the known ones are zero.
+pub const HEADER_SIZE: usize = 64;
+
+/// Self-describing header in front of a challenge or response file.
+///
+/// Everything after the header is laid out exactly as in a legacy headerless file, and the
+/// BLAKE2b hash chain only covers that part, so adding or stripping a header keeps transcripts valid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileHeader {
+    pub version: u16,
+    pub curve: CurveKind,
+    pub required_power: usize,
+    pub compression: UseCompression,
+    pub role: TranscriptFile,
+    /// Number of contributions the accumulator holds. Transcripts started with legacy files
+    /// count from the first file that has a header.
+    pub contribution_index: u64,
+    /// Vectors the accumulator holds. Headers written before tau-only ceremonies have a zero
+    /// byte there, which stands for the full mode.
+    pub mode: CeremonyMode,
+}
+
+impl FileHeader {
+    pub fn new(
+        role: TranscriptFile,
+        compression: UseCompression,
+        contribution_index: u64,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Self {
+        Self {
+            version: HEADER_VERSION,
+            curve: parameters.curve(),
+            required_power: parameters.required_power(),
+            compression,
+            role,
+            contribution_index,
+            mode: parameters.mode(),
+        }
+    }
+
+    /// Header of the file produced from a file with `header`: a response adds a contribution
+    /// to its challenge, a challenge derived from a response holds the same contributions.
+    pub fn next(
+        header: Option<&FileHeader>,
+        role: TranscriptFile,
+        compression: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Self {
+        let previous_index = header.map(|header| header.contribution_index).unwrap_or(0);
+        let contribution_index = match role {
+            TranscriptFile::Challenge => previous_index,
+            TranscriptFile::Response => previous_index + 1,
+        };
+
+        Self::new(role, compression, contribution_index, parameters)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut encoded = Vec::with_capacity(HEADER_SIZE);
+        encoded.extend_from_slice(HEADER_MAGIC);
+        encoded.write_u16::<BigEndian>(self.version)?;
+        encoded.write_u8(match self.curve {
+            CurveKind::Bls12_381 => 1,
+            CurveKind::Bn254 => 2,
+        })?;
+        encoded.write_u8(self.required_power as u8)?;
+        encoded.write_u8(match self.compression {
+            UseCompression::No => 0,
+            UseCompression::Yes => 1,
+        })?;
+        encoded.write_u8(match self.role {
+            TranscriptFile::Challenge => 1,
+            TranscriptFile::Response => 2,
+        })?;
+        encoded.write_u64::<BigEndian>(self.contribution_index)?;
+        encoded.write_u8(match self.mode {
+            CeremonyMode::Full => 0,
+            CeremonyMode::TauOnly => 1,
+        })?;
+        encoded.resize(HEADER_SIZE, 0);
+
+        writer.write_all(&encoded)
+    }
+
+    /// Decodes the header at the start of `input`. Legacy files without a header give `None`.
+    pub fn read(input: &[u8]) -> Result<Option<Self>, CeremonyError> {
+        if input.len() < HEADER_SIZE || &input[0..HEADER_MAGIC.len()] != HEADER_MAGIC {
+            return Ok(None);
+        }
+
+        let invalid = |field: &str, value: u64| {
+            CeremonyError::InvalidHeader(format!("unknown {} {}", field, value))
+        };
+        let mut encoded = &input[HEADER_MAGIC.len()..HEADER_SIZE];
+        let version = encoded.read_u16::<BigEndian>()?;
+        if version != HEADER_VERSION {
+            return Err(invalid("format version", version as u64));
+        }
+        let curve = match encoded.read_u8()? {
+            1 => CurveKind::Bls12_381,
+            2 => CurveKind::Bn254,
+            other => return Err(invalid("curve id", other as u64)),
+        };
+        let required_power = encoded.read_u8()? as usize;
+        let compression = match encoded.read_u8()? {
+            0 => UseCompression::No,
+            1 => UseCompression::Yes,
+            other => return Err(invalid("compression flag", other as u64)),
+        };
+        let role = match encoded.read_u8()? {
+            1 => TranscriptFile::Challenge,
+            2 => TranscriptFile::Response,
+            other => return Err(invalid("file role", other as u64)),
+        };
+        let contribution_index = encoded.read_u64::<BigEndian>()?;
+        let mode = match encoded.read_u8()? {
+            0 => CeremonyMode::Full,
+            1 => CeremonyMode::TauOnly,
+            other => return Err(invalid("ceremony mode", other as u64)),
+        };
+
+        Ok(Some(Self {
+            version,
+            curve,
+            required_power,
+            compression,
+            role,
+            contribution_index,
+            mode,
+        }))
+    }
+
+    /// Reads the header of `file`, if it has one
+    pub fn read_from_file(mut file: &File) -> Result<Option<Self>, CeremonyError> {
+        let mut encoded = Vec::with_capacity(HEADER_SIZE);
+        file.seek(SeekFrom::Start(0))?;
+        file.take(HEADER_SIZE as u64).read_to_end(&mut encoded)?;
+
+        Self::read(&encoded)
+    }
+
+    /// Checks that the header describes a file of `role` with `compression` in this ceremony
+    pub fn ensure_matches(
+        &self,
+        role: TranscriptFile,
+        compression: UseCompression,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<(), CeremonyError> {
+        let mismatch = |field: &str, found: String, expected: String| {
+            Err(CeremonyError::InvalidHeader(format!(
+                "the file is for {} {}, but {} is expected",
+                field, found, expected
+            )))
+        };
+
+        if self.curve != parameters.curve() {
+            return mismatch(
+                "curve",
+                self.curve.to_string(),
+                parameters.curve().to_string(),
+            );
+        }
+        if self.required_power != parameters.required_power() {
+            return mismatch(
+                "power",
+                self.required_power.to_string(),
+                parameters.required_power().to_string(),
+            );
+        }
+        if self.mode != parameters.mode() {
+            return mismatch("mode", self.mode.to_string(), parameters.mode().to_string());
+        }
+        if self.role != role {
+            return mismatch("role", self.role.to_string(), role.to_string());
+        }
+        if self.compression != compression {
+            let describe = |compression| match compression {
+                UseCompression::Yes => "compressed points".to_string(),
+                UseCompression::No => "uncompressed points".to_string(),
+            };
+            return mismatch(
+                "encoding",
+                describe(self.compression),
+                describe(compression),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Offset of the hash and points in a file with `header`
+pub fn body_offset(header: Option<&FileHeader>) -> u64 {
+    match header {
+        Some(_) => HEADER_SIZE as u64,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let parameters = RuntimeCeremonyParameters::default_bn254();
+        let header = FileHeader::new(
+            TranscriptFile::Response,
+            UseCompression::Yes,
+            7,
+            &parameters,
+        );
+
+        let mut encoded = vec![];
+        header.write(&mut encoded).unwrap();
+        assert_eq!(encoded.len(), HEADER_SIZE);
+        assert_eq!(FileHeader::read(&encoded).unwrap(), Some(header));
+
+        assert!(header
+            .ensure_matches(TranscriptFile::Response, UseCompression::Yes, &parameters)
+            .is_ok());
+        assert!(header
+            .ensure_matches(TranscriptFile::Challenge, UseCompression::Yes, &parameters)
+            .is_err());
+        assert!(header
+            .ensure_matches(
+                TranscriptFile::Response,
+                UseCompression::Yes,
+                &RuntimeCeremonyParameters::default_bls12_381()
+            )
+            .is_err());
+
+        // The mode sits in the first byte that older headers left zero
+        let tau_only = parameters.clone().with_mode(CeremonyMode::TauOnly);
+        let header = FileHeader::new(TranscriptFile::Response, UseCompression::Yes, 7, &tau_only);
+        let mut tau_only_encoded = vec![];
+        header.write(&mut tau_only_encoded).unwrap();
+        assert_eq!(FileHeader::read(&tau_only_encoded).unwrap(), Some(header));
+        assert_eq!(&tau_only_encoded[..22], &encoded[..22]);
+        assert!(header
+            .ensure_matches(TranscriptFile::Response, UseCompression::Yes, &parameters)
+            .is_err());
+
+        // A legacy file starts with the hash of the previous file
+        assert_eq!(FileHeader::read(&[0u8; 64]).unwrap(), None);
+
+        encoded[10] = 9;
+        assert!(FileHeader::read(&encoded).is_err());
+    }
+}
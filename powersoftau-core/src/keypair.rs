@@ -24,7 +24,9 @@ use std::fmt;
 use std::io::{self, Read, Write};
 use std::sync::{Arc, Mutex};
 
+use super::config::CeremonyMode;
 use super::parameters::*;
+use super::runtime_parameters::RuntimeCeremonyParameters;
 use super::utils::*;
 
 /// Contains terms of the form (s<sub>1</sub>, s<sub>1</sub><sup>x</sup>, H(s<sub>1</sub><sup>x</sup>)<sub>2</sub>, H(s<sub>1</sub><sup>x</sup>)<sub>2</sub><sup>x</sup>)
@@ -34,6 +36,8 @@ use super::utils::*;
 /// knowledge of τ, α and β.
 ///
 /// It is necessary to verify `same_ratio`((s<sub>1</sub>, s<sub>1</sub><sup>x</sup>), (H(s<sub>1</sub><sup>x</sup>)<sub>2</sub>, H(s<sub>1</sub><sup>x</sup>)<sub>2</sub><sup>x</sup>)).
+///
+/// In a tau-only ceremony only the terms for τ are part of the key, and the others are the point at infinity.
 #[derive(Eq)]
 pub struct PublicKey<E: Engine> {
     pub tau_g1: (E::G1Affine, E::G1Affine),
@@ -66,8 +70,16 @@ pub struct PrivateKey<E: Engine> {
 }
 
 /// Constructs a keypair given an RNG and a 64-byte transcript `digest`.
-pub fn keypair<R: Rng, E: Engine>(rng: &mut R, digest: &[u8]) -> (PublicKey<E>, PrivateKey<E>) {
-    assert_eq!(digest.len(), 64);
+pub fn keypair<R: Rng, E: Engine>(
+    rng: &mut R,
+    digest: &[u8],
+) -> Result<(PublicKey<E>, PrivateKey<E>), CeremonyError> {
+    if digest.len() != 64 {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "transcript digest must be 64 bytes long, while it's {}",
+            digest.len()
+        )));
+    }
 
     // tau is a conribution to the "powers of tau", in a set of points of the form "tau^i * G"
     let tau = E::Fr::rand(rng);
@@ -91,20 +103,20 @@ pub fn keypair<R: Rng, E: Engine>(rng: &mut R, digest: &[u8]) -> (PublicKey<E>,
             h.finalize().into()
         };
         // Hash into G2 as g^{s'}
-        let g2_s: E::G2Affine = hash_to_g2::<E>(h.as_ref()).into_affine();
+        let g2_s: E::G2Affine = hash_to_g2::<E>(h.as_ref())?.into_affine();
         // Compute g^{s'*x}
         let g2_s_x = g2_s.mul(x).into_affine();
 
-        ((g1_s, g1_s_x), g2_s_x)
+        Ok::<_, CeremonyError>(((g1_s, g1_s_x), g2_s_x))
     };
 
     // these "public keys" are requried for for next participants to check that points are in fact
     // sequential powers
-    let pk_tau = op(tau, 0);
-    let pk_alpha = op(alpha, 1);
-    let pk_beta = op(beta, 2);
+    let pk_tau = op(tau, 0)?;
+    let pk_alpha = op(alpha, 1)?;
+    let pk_beta = op(beta, 2)?;
 
-    (
+    Ok((
         PublicKey {
             tau_g1: pk_tau.0,
             alpha_g1: pk_alpha.0,
@@ -118,7 +130,27 @@ pub fn keypair<R: Rng, E: Engine>(rng: &mut R, digest: &[u8]) -> (PublicKey<E>,
             alpha: alpha,
             beta: beta,
         },
-    )
+    ))
+}
+
+/// Same as `keypair`, for a ceremony in `mode`. A tau-only ceremony leaves out alpha and beta,
+/// so their terms of the key are the point at infinity and their secrets are zero.
+pub fn keypair_for_mode<R: Rng, E: Engine>(
+    rng: &mut R,
+    digest: &[u8],
+    mode: CeremonyMode,
+) -> Result<(PublicKey<E>, PrivateKey<E>), CeremonyError> {
+    let (mut public_key, mut private_key) = keypair(rng, digest)?;
+    if mode == CeremonyMode::TauOnly {
+        public_key.alpha_g1 = (E::G1Affine::zero(), E::G1Affine::zero());
+        public_key.beta_g1 = (E::G1Affine::zero(), E::G1Affine::zero());
+        public_key.alpha_g2 = E::G2Affine::zero();
+        public_key.beta_g2 = E::G2Affine::zero();
+        private_key.alpha = E::Fr::zero();
+        private_key.beta = E::Fr::zero();
+    }
+
+    Ok((public_key, private_key))
 }
 
 impl<E: Engine> PublicKey<E> {
@@ -186,44 +218,58 @@ impl<E: Engine> PublicKey<E> {
     /// This function is intended to write the key to the memory map and calculates
     /// a position for writing into the file itself based on information whether
     /// contribution was output in compressed on uncompressed form
-    pub fn write<P>(
+    pub fn write(
         &self,
         output_map: &mut MmapMut,
         accumulator_was_compressed: UseCompression,
-    ) -> io::Result<()>
-    where
-        P: PowersOfTauParameters,
-    {
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<(), CeremonyError> {
         let mut position = match accumulator_was_compressed {
-            UseCompression::Yes => P::CONTRIBUTION_BYTE_SIZE - P::PUBLIC_KEY_SIZE,
-            UseCompression::No => P::ACCUMULATOR_BYTE_SIZE,
+            UseCompression::Yes => {
+                parameters.contribution_byte_size() - parameters.public_key_size()
+            }
+            UseCompression::No => parameters.accumulator_byte_size(),
         };
+        if output_map.len() < position + parameters.public_key_size() {
+            return Err(CeremonyError::SizeMismatch {
+                expected: position + parameters.public_key_size(),
+                actual: output_map.len(),
+            });
+        }
 
-        (&mut output_map[position..]).write(&self.tau_g1.0.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        (&mut output_map[position..]).write_all(self.tau_g1.0.into_uncompressed().as_ref())?;
+        position += parameters.g1_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.tau_g1.1.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        (&mut output_map[position..]).write_all(self.tau_g1.1.into_uncompressed().as_ref())?;
+        position += parameters.g1_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.alpha_g1.0.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        // A tau-only key has no terms for alpha and beta
+        let full = parameters.mode() == CeremonyMode::Full;
+        if full {
+            (&mut output_map[position..])
+                .write_all(self.alpha_g1.0.into_uncompressed().as_ref())?;
+            position += parameters.g1_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.alpha_g1.1.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+            (&mut output_map[position..])
+                .write_all(self.alpha_g1.1.into_uncompressed().as_ref())?;
+            position += parameters.g1_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.beta_g1.0.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+            (&mut output_map[position..]).write_all(self.beta_g1.0.into_uncompressed().as_ref())?;
+            position += parameters.g1_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.beta_g1.1.into_uncompressed().as_ref())?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+            (&mut output_map[position..]).write_all(self.beta_g1.1.into_uncompressed().as_ref())?;
+            position += parameters.g1_uncompressed_byte_size();
+        }
 
-        (&mut output_map[position..]).write(&self.tau_g2.into_uncompressed().as_ref())?;
-        position += P::G2_UNCOMPRESSED_BYTE_SIZE;
+        (&mut output_map[position..]).write_all(self.tau_g2.into_uncompressed().as_ref())?;
+        position += parameters.g2_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.alpha_g2.into_uncompressed().as_ref())?;
-        position += P::G2_UNCOMPRESSED_BYTE_SIZE;
+        if full {
+            (&mut output_map[position..]).write_all(self.alpha_g2.into_uncompressed().as_ref())?;
+            position += parameters.g2_uncompressed_byte_size();
 
-        (&mut output_map[position..]).write(&self.beta_g2.into_uncompressed().as_ref())?;
+            (&mut output_map[position..]).write_all(self.beta_g2.into_uncompressed().as_ref())?;
+        }
 
         output_map.flush()?;
 
@@ -233,60 +279,80 @@ impl<E: Engine> PublicKey<E> {
     /// Deserialize the public key. Points are always in uncompressed form, and
     /// always checked, since there aren't very many of them. Does not allow any
     /// points at infinity.
-    pub fn read<P>(
+    pub fn read(
         input_map: &Mmap,
         accumulator_was_compressed: UseCompression,
-    ) -> Result<Self, DeserializationError>
-    where
-        P: PowersOfTauParameters,
-    {
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<Self, CeremonyError> {
         fn read_uncompressed<EE: Engine, C: CurveAffine<Engine = EE, Scalar = EE::Fr>>(
             input_map: &Mmap,
             position: usize,
-        ) -> Result<C, DeserializationError> {
+        ) -> Result<C, CeremonyError> {
             let mut repr = C::Uncompressed::empty();
             let element_size = C::Uncompressed::size();
-            let mut memory_slice = input_map
-                .get(position..position + element_size)
-                .expect("must read point data from file");
+            let mut memory_slice = input_map.get(position..position + element_size).ok_or(
+                CeremonyError::SizeMismatch {
+                    expected: position + element_size,
+                    actual: input_map.len(),
+                },
+            )?;
             memory_slice.read_exact(repr.as_mut())?;
-            let v = repr.into_affine()?;
+            let v = repr
+                .into_affine()
+                .map_err(|e| CeremonyError::PublicKey(e.into()))?;
 
             if v.is_zero() {
-                Err(DeserializationError::PointAtInfinity)
+                Err(CeremonyError::PublicKey(
+                    DeserializationError::PointAtInfinity,
+                ))
             } else {
                 Ok(v)
             }
         }
 
         let mut position = match accumulator_was_compressed {
-            UseCompression::Yes => P::CONTRIBUTION_BYTE_SIZE - P::PUBLIC_KEY_SIZE,
-            UseCompression::No => P::ACCUMULATOR_BYTE_SIZE,
+            UseCompression::Yes => {
+                parameters.contribution_byte_size() - parameters.public_key_size()
+            }
+            UseCompression::No => parameters.accumulator_byte_size(),
         };
 
         let tau_g1_s = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
 
         let tau_g1_s_tau = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
+
+        if parameters.mode() == CeremonyMode::TauOnly {
+            let tau_g2 = read_uncompressed::<E, _>(input_map, position)?;
+
+            return Ok(PublicKey {
+                tau_g1: (tau_g1_s, tau_g1_s_tau),
+                alpha_g1: (E::G1Affine::zero(), E::G1Affine::zero()),
+                beta_g1: (E::G1Affine::zero(), E::G1Affine::zero()),
+                tau_g2,
+                alpha_g2: E::G2Affine::zero(),
+                beta_g2: E::G2Affine::zero(),
+            });
+        }
 
         let alpha_g1_s = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
 
         let alpha_g1_s_alpha = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
 
         let beta_g1_s = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
 
         let beta_g1_s_beta = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G1_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g1_uncompressed_byte_size();
 
         let tau_g2 = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G2_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g2_uncompressed_byte_size();
 
         let alpha_g2 = read_uncompressed::<E, _>(input_map, position)?;
-        position += P::G2_UNCOMPRESSED_BYTE_SIZE;
+        position += parameters.g2_uncompressed_byte_size();
 
         let beta_g2 = read_uncompressed::<E, _>(input_map, position)?;
 
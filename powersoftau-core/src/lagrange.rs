@@ -0,0 +1,540 @@
+use bellman::pairing::ff::{Field, PrimeField};
+use bellman::pairing::{CurveAffine, CurveProjective, EncodedPoint, Engine};
+use memmap::{Mmap, MmapMut};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::config::CeremonyMode;
+use super::parameters::{
+    CeremonyError, CheckForCorrectness, DeserializationError, ElementType, UseCompression,
+};
+use super::progress::{Phase, ProgressSink};
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Byte layout of the `phase1radix2m{k}` file of a domain of `size` points, the one the Zcash
+/// `prepare_phase2` wrote: alpha and beta in G1 and beta in G2, the powers of tau in G1 and G2
+/// and alpha and beta tau in G1 in the Lagrange basis, and tau^i (tau^size - 1) in G1 for
+/// i < size - 1. Every point is uncompressed. A tau-only accumulator has no alpha and beta, so
+/// their sections are empty.
+struct LagrangeLayout {
+    alpha_g1: Range<usize>,
+    beta_g1: Range<usize>,
+    beta_g2: Range<usize>,
+    coeffs_g1: Range<usize>,
+    coeffs_g2: Range<usize>,
+    alpha_coeffs_g1: Range<usize>,
+    beta_coeffs_g1: Range<usize>,
+    h: Range<usize>,
+}
+
+impl LagrangeLayout {
+    fn new(size: usize, parameters: &RuntimeCeremonyParameters) -> Self {
+        let g1 = parameters.g1_uncompressed_byte_size();
+        let g2 = parameters.g2_uncompressed_byte_size();
+        let alpha_beta = match parameters.mode() {
+            CeremonyMode::Full => 1,
+            CeremonyMode::TauOnly => 0,
+        };
+        let mut position = 0;
+        let mut section = |length: usize| {
+            position += length;
+            position - length..position
+        };
+
+        Self {
+            alpha_g1: section(alpha_beta * g1),
+            beta_g1: section(alpha_beta * g1),
+            beta_g2: section(alpha_beta * g2),
+            coeffs_g1: section(size * g1),
+            coeffs_g2: section(size * g2),
+            alpha_coeffs_g1: section(alpha_beta * size * g1),
+            beta_coeffs_g1: section(alpha_beta * size * g1),
+            h: section((size - 1) * g1),
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.h.end
+    }
+}
+
+/// Name of the file of the domain of 2^`power` points in `directory`
+pub fn lagrange_path_for<P: AsRef<Path>>(directory: P, power: usize) -> PathBuf {
+    directory.as_ref().join(format!("phase1radix2m{}", power))
+}
+
+/// Size of the file of the domain of 2^`power` points
+pub fn lagrange_byte_size(power: usize, parameters: &RuntimeCeremonyParameters) -> usize {
+    LagrangeLayout::new(1 << power, parameters).size()
+}
+
+/// Writes the powers of tau of the accumulator in `input_map` for the domain of 2^`power` points
+/// to `output_map`, which has to be `lagrange_byte_size` bytes long. The powers are copied chunk
+/// by chunk and then turned into the Lagrange basis with an inverse FFT that runs on the memory
+/// map itself, so only a chunk of points is in memory at any time. Of a tau-only accumulator only
+/// the powers of tau and h are written, and the sections of alpha and beta are left out.
+pub fn write_lagrange<E: Engine>(
+    input_map: &Mmap,
+    input_compression: UseCompression,
+    check_input_for_correctness: CheckForCorrectness,
+    output_map: &mut MmapMut,
+    power: usize,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    if power > parameters.required_power() {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "the accumulator holds 2^{} powers of tau, so there is no domain of 2^{} points",
+            parameters.required_power(),
+            power
+        )));
+    }
+    let size = 1 << power;
+    let layout = LagrangeLayout::new(size, parameters);
+    if output_map.len() != layout.size() {
+        return Err(CeremonyError::SizeMismatch {
+            expected: layout.size(),
+            actual: output_map.len(),
+        });
+    }
+    let g1 = parameters.g1_uncompressed_byte_size();
+    let g2 = parameters.g2_uncompressed_byte_size();
+    let alpha_beta = parameters.mode() == CeremonyMode::Full;
+
+    // tau^0 to tau^(size - 1) go to the coefficients, and tau^size to tau^(2 size - 2) to h
+    let mut accumulator = BatchedAccumulator::<E>::empty(parameters);
+    let mut start = 0;
+    while start < 2 * size - 1 {
+        let end = (2 * size - 1).min(start + parameters.empirical_batch_size());
+        let chunk = match start < size {
+            true => start..end.min(size),
+            false => start..end,
+        };
+        accumulator.read_chunk(
+            chunk.start,
+            chunk.len(),
+            input_compression,
+            check_input_for_correctness,
+            input_map,
+        )?;
+
+        if chunk.start == 0 && alpha_beta {
+            output_map[layout.alpha_g1.clone()].copy_from_slice(
+                accumulator.alpha_tau_powers_g1[0]
+                    .into_uncompressed()
+                    .as_ref(),
+            );
+            output_map[layout.beta_g1.clone()].copy_from_slice(
+                accumulator.beta_tau_powers_g1[0]
+                    .into_uncompressed()
+                    .as_ref(),
+            );
+            output_map[layout.beta_g2.clone()]
+                .copy_from_slice(accumulator.beta_g2.into_uncompressed().as_ref());
+        }
+
+        if chunk.start < size {
+            let mut sections: Vec<(&[E::G1Affine], &Range<usize>)> =
+                vec![(&accumulator.tau_powers_g1, &layout.coeffs_g1)];
+            if alpha_beta {
+                sections.push((&accumulator.alpha_tau_powers_g1, &layout.alpha_coeffs_g1));
+                sections.push((&accumulator.beta_tau_powers_g1, &layout.beta_coeffs_g1));
+            }
+            for &(points, section) in sections.iter() {
+                for (i, point) in points.iter().enumerate() {
+                    let position = section.start + (chunk.start + i) * g1;
+                    output_map[position..position + g1]
+                        .copy_from_slice(point.into_uncompressed().as_ref());
+                }
+            }
+            for (i, point) in accumulator.tau_powers_g2.iter().enumerate() {
+                let position = layout.coeffs_g2.start + (chunk.start + i) * g2;
+                output_map[position..position + g2]
+                    .copy_from_slice(point.into_uncompressed().as_ref());
+            }
+        } else {
+            for (i, point) in accumulator.tau_powers_g1.iter().enumerate() {
+                let position = layout.h.start + (chunk.start - size + i) * g1;
+                output_map[position..position + g1]
+                    .copy_from_slice(point.into_uncompressed().as_ref());
+            }
+        }
+
+        progress.points(Phase::Lagrange, ElementType::TauG1, chunk.end, 2 * size - 1);
+        start = chunk.end;
+    }
+
+    // h is tau^(i + size) - tau^i, while the coefficients still hold the powers
+    let block = block_size(size, parameters);
+    let mut start = 0;
+    while start < size - 1 {
+        let end = (size - 1).min(start + block);
+        let section = layout.h.start + start * g1..layout.h.start + end * g1;
+        let mut h = decode::<E::G1Affine>(&output_map[section.clone()], ElementType::TauG1, start)?;
+        let powers = decode::<E::G1Affine>(
+            &output_map[layout.coeffs_g1.start + start * g1..layout.coeffs_g1.start + end * g1],
+            ElementType::TauG1,
+            start,
+        )?;
+        for (h, power) in h.iter_mut().zip(powers.iter()) {
+            h.sub_assign(power);
+        }
+        encode::<E::G1Affine>(&mut h, &mut output_map[section]);
+        start = end;
+    }
+
+    ifft::<E::G1Affine>(
+        &mut output_map[layout.coeffs_g1.clone()],
+        ElementType::TauG1,
+        block,
+        progress,
+    )?;
+    ifft::<E::G2Affine>(
+        &mut output_map[layout.coeffs_g2.clone()],
+        ElementType::TauG2,
+        block,
+        progress,
+    )?;
+    if alpha_beta {
+        ifft::<E::G1Affine>(
+            &mut output_map[layout.alpha_coeffs_g1.clone()],
+            ElementType::AlphaG1,
+            block,
+            progress,
+        )?;
+        ifft::<E::G1Affine>(
+            &mut output_map[layout.beta_coeffs_g1.clone()],
+            ElementType::BetaG1,
+            block,
+            progress,
+        )?;
+    }
+
+    output_map.flush()?;
+
+    Ok(())
+}
+
+/// Points held in memory at once: the largest power of two the batch size allows, at most the
+/// whole domain
+fn block_size(size: usize, parameters: &RuntimeCeremonyParameters) -> usize {
+    let batch_size = parameters.empirical_batch_size().max(2);
+    let block = 1 << (usize::BITS - 1 - batch_size.leading_zeros());
+
+    block.min(size)
+}
+
+/// Replaces the uncompressed points in `points` with their inverse FFT over the domain of their
+/// number, a power of two. This is the decimation in frequency FFT with the inverse root of
+/// unity: the stages whose butterflies span `block` points or more stream both halves of every
+/// butterfly through memory, the remaining stages run on blocks of `block` points at once, and
+/// the results are finally put into natural order by swapping their encodings.
+fn ifft<C: CurveAffine>(
+    points: &mut [u8],
+    element_type: ElementType,
+    block: usize,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    let point_size = C::Uncompressed::size();
+    let size = points.len() / point_size;
+    let log_size = size.trailing_zeros();
+    if log_size > C::Scalar::S {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "the scalar field has no domain of 2^{} points",
+            log_size
+        )));
+    }
+
+    let mut omega = C::Scalar::root_of_unity();
+    for _ in log_size..C::Scalar::S {
+        omega.square();
+    }
+    let omega_inv = omega.inverse().expect("roots of unity are invertible");
+    let size_inv = C::Scalar::from_repr(<C::Scalar as PrimeField>::Repr::from(size as u64))
+        .expect("the domain size is in the scalar field")
+        .inverse()
+        .expect("the domain size is not zero");
+
+    let passes = (log_size as usize).saturating_sub(block.trailing_zeros() as usize) + 2;
+    let mut done = 0;
+
+    // Stages with butterflies across blocks, half a block from either side at a time
+    let mut span = size / 2;
+    while span >= block {
+        let half = block / 2;
+        let stride = size / (2 * span);
+        for group in (0..size).step_by(2 * span) {
+            for offset in (0..span).step_by(half) {
+                let low = (group + offset) * point_size..(group + offset + half) * point_size;
+                let high = (group + offset + span) * point_size
+                    ..(group + offset + span + half) * point_size;
+                let mut a = decode::<C>(&points[low.clone()], element_type, group + offset)?;
+                let mut b =
+                    decode::<C>(&points[high.clone()], element_type, group + offset + span)?;
+
+                let twiddles = powers(
+                    omega_inv.pow([(offset * stride) as u64]),
+                    omega_inv.pow([stride as u64]),
+                    half,
+                );
+                butterflies::<C>(&mut a, &mut b, &twiddles);
+
+                encode::<C>(&mut a, &mut points[low]);
+                encode::<C>(&mut b, &mut points[high]);
+
+                done += 2 * half;
+                progress.points(Phase::Lagrange, element_type, done, passes * size);
+            }
+        }
+        span /= 2;
+    }
+
+    // Stages within blocks, then the division by the domain size
+    for start in (0..size).step_by(block) {
+        let range = start * point_size..(start + block) * point_size;
+        let mut values = decode::<C>(&points[range.clone()], element_type, start)?;
+
+        let mut span = span;
+        while span >= 1 {
+            let stride = size / (2 * span);
+            let twiddles = powers(C::Scalar::one(), omega_inv.pow([stride as u64]), span);
+            for group in values.chunks_mut(2 * span) {
+                let (a, b) = group.split_at_mut(span);
+                butterflies::<C>(a, b, &twiddles);
+            }
+            span /= 2;
+        }
+        for value in values.iter_mut() {
+            value.mul_assign(size_inv.into_repr());
+        }
+
+        encode::<C>(&mut values, &mut points[range]);
+
+        done += block;
+        progress.points(Phase::Lagrange, element_type, done, passes * size);
+    }
+
+    // The results are in bit reversed order
+    let mut swap = vec![0u8; point_size];
+    for index in 0..size {
+        let reversed = match log_size {
+            0 => 0,
+            _ => index.reverse_bits() >> (usize::BITS - log_size),
+        };
+        if index < reversed {
+            let (low, high) = points.split_at_mut(reversed * point_size);
+            let low = &mut low[index * point_size..(index + 1) * point_size];
+            swap.copy_from_slice(low);
+            low.copy_from_slice(&high[..point_size]);
+            high[..point_size].copy_from_slice(&swap);
+        }
+    }
+    progress.points(Phase::Lagrange, element_type, passes * size, passes * size);
+
+    Ok(())
+}
+
+/// `first`, `first * step`, `first * step^2` and so on, `count` values in all
+fn powers<F: Field>(first: F, step: F, count: usize) -> Vec<F> {
+    let mut powers = Vec::with_capacity(count);
+    let mut current = first;
+    for _ in 0..count {
+        powers.push(current);
+        current.mul_assign(&step);
+    }
+
+    powers
+}
+
+/// Replaces `a` and `b` with `a + b` and `(a - b) * twiddle` pairwise, over multiple cores
+fn butterflies<C: CurveAffine>(
+    a: &mut [C::Projective],
+    b: &mut [C::Projective],
+    twiddles: &[C::Scalar],
+) {
+    let mut chunk_size = a.len() / num_cpus::get();
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    crossbeam::scope(|scope| {
+        for ((a, b), twiddles) in a
+            .chunks_mut(chunk_size)
+            .zip(b.chunks_mut(chunk_size))
+            .zip(twiddles.chunks(chunk_size))
+        {
+            scope.spawn(move || {
+                for ((a, b), twiddle) in a.iter_mut().zip(b.iter_mut()).zip(twiddles.iter()) {
+                    let mut difference = *a;
+                    difference.sub_assign(b);
+                    difference.mul_assign(twiddle.into_repr());
+                    a.add_assign(b);
+                    *b = difference;
+                }
+            });
+        }
+    });
+}
+
+/// Decodes uncompressed points this module wrote, the first of them being the one at `from`
+fn decode<C: CurveAffine>(
+    encoded: &[u8],
+    element_type: ElementType,
+    from: usize,
+) -> Result<Vec<C::Projective>, CeremonyError> {
+    let mut points = Vec::with_capacity(encoded.len() / C::Uncompressed::size());
+    for (i, bytes) in encoded.chunks(C::Uncompressed::size()).enumerate() {
+        let mut point = C::Uncompressed::empty();
+        point.as_mut().copy_from_slice(bytes);
+        let point = point.into_affine_unchecked().map_err(|e| {
+            CeremonyError::element(element_type, from + i, DeserializationError::from(e))
+        })?;
+        points.push(point.into_projective());
+    }
+
+    Ok(points)
+}
+
+/// Writes `points` uncompressed to `encoded`, normalizing them over multiple cores first
+fn encode<C: CurveAffine>(points: &mut [C::Projective], encoded: &mut [u8]) {
+    let mut chunk_size = points.len() / num_cpus::get();
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    crossbeam::scope(|scope| {
+        for points in points.chunks_mut(chunk_size) {
+            scope.spawn(move || {
+                C::Projective::batch_normalization(points);
+            });
+        }
+    });
+
+    for (point, bytes) in points
+        .iter()
+        .zip(encoded.chunks_mut(C::Uncompressed::size()))
+    {
+        bytes.copy_from_slice(point.into_affine().into_uncompressed().as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::{Bn256, Fr, G1Affine};
+
+    use crate::test_utils::{bn254_parameters, fresh_challenge};
+
+    fn scalar(value: u64) -> Fr {
+        Fr::from_repr(<Fr as PrimeField>::Repr::from(value)).unwrap()
+    }
+
+    #[test]
+    fn test_layout_matches_prepare_phase2() {
+        let parameters = bn254_parameters(3);
+        let g1 = parameters.g1_uncompressed_byte_size();
+        let g2 = parameters.g2_uncompressed_byte_size();
+
+        // alpha, beta, beta in G2, four sections of coefficients and h
+        assert_eq!(
+            lagrange_byte_size(0, &parameters),
+            2 * g1 + g2 + 3 * g1 + g2
+        );
+        assert_eq!(
+            lagrange_byte_size(3, &parameters),
+            2 * g1 + g2 + 8 * (3 * g1 + g2) + 7 * g1
+        );
+
+        // Only the powers of tau and h without alpha and beta
+        let tau_only = parameters.clone().with_mode(CeremonyMode::TauOnly);
+        assert_eq!(lagrange_byte_size(3, &tau_only), 8 * (g1 + g2) + 7 * g1);
+        assert_eq!(
+            lagrange_path_for("out", 3),
+            Path::new("out").join("phase1radix2m3")
+        );
+    }
+
+    #[test]
+    fn test_out_of_core_ifft_matches_naive_interpolation() {
+        let size = 8;
+        let exponents: Vec<Fr> = (0..size).map(|i| scalar(3 * i as u64 + 5)).collect();
+
+        // The inverse FFT of the scalars, term by term
+        let mut omega = Fr::root_of_unity();
+        for _ in 3..Fr::S {
+            omega.square();
+        }
+        let omega_inv = omega.inverse().unwrap();
+        let size_inv = scalar(size as u64).inverse().unwrap();
+        let expected: Vec<G1Affine> = (0..size)
+            .map(|i| {
+                let mut sum = Fr::zero();
+                for (j, exponent) in exponents.iter().enumerate() {
+                    let mut term = omega_inv.pow([(i * j) as u64]);
+                    term.mul_assign(exponent);
+                    sum.add_assign(&term);
+                }
+                sum.mul_assign(&size_inv);
+                G1Affine::one().mul(sum.into_repr()).into_affine()
+            })
+            .collect();
+
+        // Every block size, down to the one streaming all but the last stage
+        for &block in &[2, 4, 8] {
+            let point_size = <G1Affine as CurveAffine>::Uncompressed::size();
+            let mut points = vec![0u8; size * point_size];
+            for (exponent, bytes) in exponents.iter().zip(points.chunks_mut(point_size)) {
+                let point = G1Affine::one().mul(exponent.into_repr()).into_affine();
+                bytes.copy_from_slice(point.into_uncompressed().as_ref());
+            }
+
+            ifft::<G1Affine>(
+                &mut points,
+                ElementType::TauG1,
+                block,
+                &mut crate::progress::NoProgress,
+            )
+            .unwrap();
+
+            for (expected, bytes) in expected.iter().zip(points.chunks(point_size)) {
+                assert_eq!(bytes, expected.into_uncompressed().as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn test_initial_accumulator_interpolates_to_the_first_lagrange_polynomial() {
+        use crate::progress::NoProgress;
+
+        // Every power of the initial accumulator is the generator, as if tau were one, where
+        // only the first Lagrange polynomial is not zero
+        for &mode in &[CeremonyMode::Full, CeremonyMode::TauOnly] {
+            let parameters = bn254_parameters(2).with_mode(mode);
+            let challenge = fresh_challenge(&parameters);
+
+            let mut output = MmapMut::map_anon(lagrange_byte_size(2, &parameters)).unwrap();
+            write_lagrange::<Bn256>(
+                &challenge,
+                UseCompression::No,
+                CheckForCorrectness::Yes,
+                &mut output,
+                2,
+                &parameters,
+                &mut NoProgress,
+            )
+            .unwrap();
+
+            let layout = LagrangeLayout::new(4, &parameters);
+            let g1 = parameters.g1_uncompressed_byte_size();
+            let coeffs =
+                decode::<G1Affine>(&output[layout.coeffs_g1.clone()], ElementType::TauG1, 0)
+                    .unwrap();
+            assert_eq!(coeffs[0].into_affine(), G1Affine::one());
+            assert!(coeffs[1..].iter().all(|point| point.is_zero()));
+            let h = decode::<G1Affine>(&output[layout.h.clone()], ElementType::TauG1, 0).unwrap();
+            assert_eq!(h.len() * g1, layout.h.len());
+            assert!(h.iter().all(|point| point.is_zero()));
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use super::config::{CeremonyConfig, CeremonyMode};
+use super::curve::CurveKind;
+use super::header::{body_offset, FileHeader};
+use super::parameters::{CeremonyError, TranscriptFile, UseCompression};
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Largest power of tau that is considered when detecting the layout of a file
+pub const MAX_DETECTED_POWER: usize = 32;
+
+/// How a challenge or response file is laid out, as told by its header or its size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileLayout {
+    pub curve: CurveKind,
+    pub required_power: usize,
+    pub compression: UseCompression,
+    pub role: TranscriptFile,
+    pub mode: CeremonyMode,
+    pub header: Option<FileHeader>,
+}
+
+impl FileLayout {
+    /// Offset of the hash and points in the file
+    pub fn body_offset(&self) -> u64 {
+        body_offset(self.header.as_ref())
+    }
+
+    /// Size of the file on disk
+    pub fn file_size(&self) -> u64 {
+        let parameters = parameters_for(self.curve, self.required_power, self.mode);
+        self.body_offset() + parameters.file_byte_size(self.role, self.compression) as u64
+    }
+
+    /// Ceremony parameters of `parameters`, with the power of tau and mode of this file
+    pub fn parameters(&self, parameters: &RuntimeCeremonyParameters) -> RuntimeCeremonyParameters {
+        parameters
+            .clone()
+            .with_required_power(self.required_power)
+            .with_mode(self.mode)
+    }
+
+    /// Checks that the file is a `role` file
+    pub fn ensure_role(&self, role: TranscriptFile) -> Result<(), CeremonyError> {
+        if self.role != role {
+            return Err(CeremonyError::UnknownLayout(format!(
+                "expected a {} file, but found {}",
+                role, self
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for FileLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {} {} file for 2^{} {}powers on {}{}",
+            match self.compression {
+                UseCompression::Yes => "compressed",
+                UseCompression::No => "uncompressed",
+            },
+            self.role,
+            self.required_power,
+            match self.mode {
+                CeremonyMode::Full => "",
+                CeremonyMode::TauOnly => "tau-only ",
+            },
+            self.curve,
+            match self.header {
+                Some(_) => " with a header",
+                None => "",
+            }
+        )
+    }
+}
+
+/// Tells the power of tau, point compression, role and mode of the challenge or response file at
+/// `path` on `curve`. Files with a `FileHeader` are described by it, the layout of legacy files
+/// follows from their size alone. A file without a header is only taken for a tau-only file when
+/// no full layout has its size.
+pub fn detect_layout<P: AsRef<Path>>(
+    path: P,
+    curve: CurveKind,
+) -> Result<FileLayout, CeremonyError> {
+    let file = File::open(path)?;
+    let header = FileHeader::read_from_file(&file)?;
+    let size = file.metadata()?.len();
+
+    layout_for_size(size, header, curve)
+}
+
+/// Layout of a file of `size` bytes that starts with `header`, see `detect_layout`
+pub fn layout_for_size(
+    size: u64,
+    header: Option<FileHeader>,
+    curve: CurveKind,
+) -> Result<FileLayout, CeremonyError> {
+    if let Some(header) = header {
+        if header.curve != curve {
+            return Err(CeremonyError::InvalidHeader(format!(
+                "the file is for curve {}, but {} is expected",
+                header.curve, curve
+            )));
+        }
+
+        let layout = FileLayout {
+            curve,
+            required_power: header.required_power,
+            compression: header.compression,
+            role: header.role,
+            mode: header.mode,
+            header: Some(header),
+        };
+        if layout.file_size() != size {
+            return Err(CeremonyError::UnknownLayout(format!(
+                "the header describes {} of {} bytes, but the file has {}",
+                layout,
+                layout.file_size(),
+                size
+            )));
+        }
+
+        return Ok(layout);
+    }
+
+    let mut candidates = vec![];
+    for &mode in &[CeremonyMode::Full, CeremonyMode::TauOnly] {
+        if !candidates.is_empty() {
+            break;
+        }
+        for required_power in 1..=MAX_DETECTED_POWER {
+            for &role in &[TranscriptFile::Challenge, TranscriptFile::Response] {
+                for &compression in &[UseCompression::No, UseCompression::Yes] {
+                    let layout = FileLayout {
+                        curve,
+                        required_power,
+                        compression,
+                        role,
+                        mode,
+                        header: None,
+                    };
+                    if layout.file_size() == size {
+                        candidates.push(layout);
+                    }
+                }
+            }
+        }
+    }
+
+    match candidates.len() {
+        0 => Err(CeremonyError::UnknownLayout(format!(
+            "no challenge or response on {} has {} bytes",
+            curve, size
+        ))),
+        1 => Ok(candidates[0]),
+        _ => Err(CeremonyError::UnknownLayout(format!(
+            "{} bytes fit both {} and {}, write the files with a header to tell them apart",
+            size, candidates[0], candidates[1]
+        ))),
+    }
+}
+
+fn parameters_for(
+    curve: CurveKind,
+    required_power: usize,
+    mode: CeremonyMode,
+) -> RuntimeCeremonyParameters {
+    let mut config = CeremonyConfig::default_bls12_381();
+    config.curve.name = curve;
+
+    RuntimeCeremonyParameters::new(config)
+        .with_required_power(required_power)
+        .with_mode(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_is_detected_from_size() {
+        for &curve in &[CurveKind::Bls12_381, CurveKind::Bn254] {
+            for required_power in 4..=MAX_DETECTED_POWER {
+                for &role in &[TranscriptFile::Challenge, TranscriptFile::Response] {
+                    for &compression in &[UseCompression::No, UseCompression::Yes] {
+                        let layout = FileLayout {
+                            curve,
+                            required_power,
+                            compression,
+                            role,
+                            mode: CeremonyMode::Full,
+                            header: None,
+                        };
+                        let detected = layout_for_size(layout.file_size(), None, curve).unwrap();
+                        assert_eq!(detected, layout);
+                    }
+                }
+            }
+        }
+
+        assert!(layout_for_size(12345, None, CurveKind::Bn254).is_err());
+    }
+
+    #[test]
+    fn test_header_tells_apart_layouts_of_the_same_size() {
+        let parameters = parameters_for(CurveKind::Bn254, 2, CeremonyMode::Full);
+        let challenge = FileLayout {
+            curve: CurveKind::Bn254,
+            required_power: 2,
+            compression: UseCompression::No,
+            role: TranscriptFile::Challenge,
+            mode: CeremonyMode::Full,
+            header: None,
+        };
+        // An uncompressed response for 2^1 powers has the same size
+        assert!(layout_for_size(challenge.file_size(), None, CurveKind::Bn254).is_err());
+
+        let header = FileHeader::new(
+            TranscriptFile::Challenge,
+            UseCompression::No,
+            0,
+            &parameters,
+        );
+        let detected = layout_for_size(
+            challenge.file_size() + body_offset(Some(&header)),
+            Some(header),
+            CurveKind::Bn254,
+        )
+        .unwrap();
+        assert_eq!(detected.required_power, 2);
+        assert_eq!(detected.role, TranscriptFile::Challenge);
+
+        assert!(layout_for_size(challenge.file_size(), Some(header), CurveKind::Bn254).is_err());
+        assert!(layout_for_size(
+            challenge.file_size() + body_offset(Some(&header)),
+            Some(header),
+            CurveKind::Bls12_381
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_header_tells_the_mode() {
+        let parameters = parameters_for(CurveKind::Bn254, 5, CeremonyMode::TauOnly);
+        let header = FileHeader::new(
+            TranscriptFile::Response,
+            UseCompression::Yes,
+            3,
+            &parameters,
+        );
+        let size = body_offset(Some(&header))
+            + parameters.file_byte_size(TranscriptFile::Response, UseCompression::Yes) as u64;
+
+        let detected = layout_for_size(size, Some(header), CurveKind::Bn254).unwrap();
+        assert_eq!(detected.mode, CeremonyMode::TauOnly);
+        assert_eq!(
+            detected
+                .parameters(&RuntimeCeremonyParameters::default_bn254())
+                .mode(),
+            CeremonyMode::TauOnly
+        );
+        assert_eq!(detected.file_size(), size);
+    }
+}
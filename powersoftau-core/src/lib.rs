@@ -1,19 +1,37 @@
 #![allow(unused_imports)]
 
 pub mod batched_accumulator;
+pub mod beacon;
+pub mod beacon_source;
+pub mod cardano;
+pub mod checkpoint;
 pub mod config;
+pub mod curve;
+pub mod header;
 pub mod keypair;
+pub mod lagrange;
+pub mod layout;
 pub mod parameters;
+pub mod progress;
+pub mod ptau;
+pub mod reduction;
 pub mod runtime_parameters;
-pub mod small_bls12_381;
+pub mod split;
+#[cfg(test)]
+mod test_utils;
+pub mod transcript;
 pub mod utils;
 
 pub use batched_accumulator::BatchedAccumulator;
 pub use config::CeremonyConfig;
-pub use keypair::{keypair, PublicKey};
-pub use parameters::{CheckForCorrectness, UseCompression, PowersOfTauParameters};
+pub use curve::CurveKind;
+pub use header::FileHeader;
+pub use keypair::{keypair, keypair_for_mode, PublicKey};
+pub use layout::{detect_layout, FileLayout};
+pub use parameters::{CeremonyError, CheckForCorrectness, UseCompression};
+pub use progress::{ConsoleProgress, NoProgress, ProgressSink};
 pub use runtime_parameters::RuntimeCeremonyParameters;
-pub use small_bls12_381::Bls12CeremonyParameters;
+pub use transcript::{verify_transcript, TranscriptReport};
 pub use utils::blank_hash;
 
 use std::io::Result as IoResult;
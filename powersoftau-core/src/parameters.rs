@@ -22,44 +22,8 @@ use typenum::consts::U64;
 
 use super::keypair::*;
 
-pub trait PowersOfTauParameters: Clone {
-    const REQUIRED_POWER: usize;
-
-    const G1_UNCOMPRESSED_BYTE_SIZE: usize;
-    const G2_UNCOMPRESSED_BYTE_SIZE: usize;
-    const G1_COMPRESSED_BYTE_SIZE: usize;
-    const G2_COMPRESSED_BYTE_SIZE: usize;
-
-    const TAU_POWERS_LENGTH: usize = (1 << Self::REQUIRED_POWER);
-
-    const TAU_POWERS_G1_LENGTH: usize = (Self::TAU_POWERS_LENGTH << 1) - 1;
-
-    const ACCUMULATOR_BYTE_SIZE: usize = (Self::TAU_POWERS_G1_LENGTH * Self::G1_UNCOMPRESSED_BYTE_SIZE) + // g1 tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G2_UNCOMPRESSED_BYTE_SIZE) + // g2 tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G1_UNCOMPRESSED_BYTE_SIZE) + // alpha tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G1_UNCOMPRESSED_BYTE_SIZE) // beta tau powers
-                                            + Self::G2_UNCOMPRESSED_BYTE_SIZE // beta in g2
-                                            + Self::HASH_SIZE; // blake2b hash of previous contribution
-
-    const PUBLIC_KEY_SIZE: usize = 3 * Self::G2_UNCOMPRESSED_BYTE_SIZE + // tau, alpha, and beta in g2
-                                    6 * Self::G1_UNCOMPRESSED_BYTE_SIZE; // (s1, s1*tau), (s2, s2*alpha), (s3, s3*beta) in g1
-
-    const CONTRIBUTION_BYTE_SIZE: usize = (Self::TAU_POWERS_G1_LENGTH * Self::G1_COMPRESSED_BYTE_SIZE) + // g1 tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G2_COMPRESSED_BYTE_SIZE) + // g2 tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G1_COMPRESSED_BYTE_SIZE) + // alpha tau powers
-                                            (Self::TAU_POWERS_LENGTH * Self::G1_COMPRESSED_BYTE_SIZE) // beta tau powers
-                                            + Self::G2_COMPRESSED_BYTE_SIZE // beta in g2
-                                            + Self::HASH_SIZE // blake2b hash of input accumulator
-                                            + Self::PUBLIC_KEY_SIZE; // public key
-
-    // Blake2b hash size
-    const HASH_SIZE: usize = 64;
-
-    const EMPIRICAL_BATCH_SIZE: usize = 1 << 21;
-}
-
 /// Determines if point compression should be used.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum UseCompression {
     Yes,
     No,
@@ -112,3 +76,243 @@ pub enum ElementType {
     BetaG1,
     BetaG2,
 }
+
+impl fmt::Display for ElementType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ElementType::TauG1 => write!(f, "TauG1"),
+            ElementType::TauG2 => write!(f, "TauG2"),
+            ElementType::AlphaG1 => write!(f, "AlphaG1"),
+            ElementType::BetaG1 => write!(f, "BetaG1"),
+            ElementType::BetaG2 => write!(f, "BetaG2"),
+        }
+    }
+}
+
+/// One of the two files taking part in the verification of a contribution.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TranscriptFile {
+    Challenge,
+    Response,
+}
+
+impl fmt::Display for TranscriptFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TranscriptFile::Challenge => write!(f, "`challenge`"),
+            TranscriptFile::Response => write!(f, "`response`"),
+        }
+    }
+}
+
+/// Identifies the check that rejected a contribution during verification.
+#[derive(Debug)]
+pub enum VerificationError {
+    /// The public key does not prove knowledge of tau
+    TauProofOfKnowledge,
+    /// The public key does not prove knowledge of alpha
+    AlphaProofOfKnowledge,
+    /// The public key does not prove knowledge of beta
+    BetaProofOfKnowledge,
+    /// The first TauG1 element of the response is not the G1 generator
+    TauG1Generator,
+    /// The first TauG2 element of the response is not the G2 generator
+    TauG2Generator,
+    /// The previous tau was not multiplied by the tau from the public key
+    TauMultiplication,
+    /// The previous alpha was not multiplied by the alpha from the public key
+    AlphaMultiplication,
+    /// The previous beta in G1 was not multiplied by the beta from the public key
+    BetaMultiplication,
+    /// Beta in G2 was not multiplied by the same beta as in G1
+    BetaG2Multiplication,
+    /// Consecutive elements in the given range are not successive powers of tau
+    PowersRatio {
+        element_type: ElementType,
+        start: usize,
+        end: usize,
+    },
+    /// The last TauG1 power below TAU_POWERS_LENGTH and the first one above it are not consecutive powers
+    TauG1Boundary,
+    /// A chunk of the accumulator could not be formed from the requested range
+    ChunkRange { start: usize, end: usize },
+    /// A point in one of the files could not be read
+    Deserialization {
+        file: TranscriptFile,
+        error: Box<CeremonyError>,
+    },
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerificationError::TauProofOfKnowledge => {
+                write!(f, "Invalid proof of knowledge of tau in the public key")
+            }
+            VerificationError::AlphaProofOfKnowledge => {
+                write!(f, "Invalid proof of knowledge of alpha in the public key")
+            }
+            VerificationError::BetaProofOfKnowledge => {
+                write!(f, "Invalid proof of knowledge of beta in the public key")
+            }
+            VerificationError::TauG1Generator => write!(f, "tau_powers_g1[0] != 1"),
+            VerificationError::TauG2Generator => write!(f, "tau_powers_g2[0] != 1"),
+            VerificationError::TauMultiplication => {
+                write!(f, "Previous tau was not multiplied by the contributed tau")
+            }
+            VerificationError::AlphaMultiplication => {
+                write!(
+                    f,
+                    "Previous alpha was not multiplied by the contributed alpha"
+                )
+            }
+            VerificationError::BetaMultiplication => {
+                write!(
+                    f,
+                    "Previous beta was not multiplied by the contributed beta"
+                )
+            }
+            VerificationError::BetaG2Multiplication => {
+                write!(f, "Beta in G2 was not multiplied by the same beta as in G1")
+            }
+            VerificationError::PowersRatio {
+                element_type,
+                start,
+                end,
+            } => write!(
+                f,
+                "{} elements from {} to {} are not consecutive powers of tau",
+                element_type, start, end
+            ),
+            VerificationError::TauG1Boundary => write!(
+                f,
+                "TauG1 elements at the end of the TauG2 range are not consecutive powers of tau"
+            ),
+            VerificationError::ChunkRange { start, end } => {
+                write!(f, "Invalid chunk range from {} to {}", start, end)
+            }
+            VerificationError::Deserialization { file, ref error } => {
+                write!(f, "Unable to read {} from {}", error, file)
+            }
+        }
+    }
+}
+
+/// Errors returned from the public API of the ceremony instead of aborting the process.
+#[derive(Debug)]
+pub enum CeremonyError {
+    /// Disk or memory map IO failed
+    Io(io::Error),
+    /// An accumulator element is not a valid encoding of a curve point
+    Decoding {
+        element_type: ElementType,
+        index: usize,
+        error: GroupDecodingError,
+    },
+    /// An accumulator element is the point at infinity
+    PointAtInfinity {
+        element_type: ElementType,
+        index: usize,
+    },
+    /// The public key appended to a response could not be read
+    PublicKey(DeserializationError),
+    /// A file or memory map is smaller or larger than the ceremony parameters require
+    SizeMismatch { expected: usize, actual: usize },
+    /// The response was not computed on top of the expected challenge
+    HashChainMismatch,
+    /// The ceremony parameters, or arguments derived from them, can not be used
+    InvalidParameters(String),
+    /// The contribution was rejected during verification
+    Verification(VerificationError),
+    /// The checkpoint or key escrow of an interrupted run can not be used
+    Checkpoint(String),
+    /// The header of a file is malformed, or describes a different file than expected
+    InvalidHeader(String),
+    /// The size of a file does not match a single challenge or response layout
+    UnknownLayout(String),
+    /// A response of a transcript can not be checked, because the one before it is unusable
+    BrokenTranscript(String),
+    /// The beacon contribution was not derived from the published beacon
+    BeaconMismatch(String),
+    /// A reduced accumulator is not the one its note links to the source file
+    ReductionMismatch(String),
+    /// An SRS file split off the final accumulator is not the one its manifest links to the
+    /// transcript
+    SplitMismatch(String),
+}
+
+impl CeremonyError {
+    /// Attributes a deserialization error to the accumulator element it was encountered at
+    pub fn element(element_type: ElementType, index: usize, error: DeserializationError) -> Self {
+        match error {
+            DeserializationError::IoError(e) => CeremonyError::Io(e),
+            DeserializationError::DecodingError(error) => CeremonyError::Decoding {
+                element_type,
+                index,
+                error,
+            },
+            DeserializationError::PointAtInfinity => CeremonyError::PointAtInfinity {
+                element_type,
+                index,
+            },
+        }
+    }
+}
+
+impl fmt::Display for CeremonyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CeremonyError::Io(ref e) => write!(f, "Disk IO error: {}", e),
+            CeremonyError::Decoding {
+                element_type,
+                index,
+                ref error,
+            } => write!(
+                f,
+                "Decoding error in {} element at index {}: {}",
+                element_type, index, error
+            ),
+            CeremonyError::PointAtInfinity {
+                element_type,
+                index,
+            } => write!(
+                f,
+                "Point at infinity found in {} element at index {}",
+                element_type, index
+            ),
+            CeremonyError::PublicKey(ref e) => write!(f, "Invalid public key: {}", e),
+            CeremonyError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Expected {} bytes of ceremony data, but found {}",
+                expected, actual
+            ),
+            CeremonyError::HashChainMismatch => write!(
+                f,
+                "Hash chain failure: the response is not based on this challenge"
+            ),
+            CeremonyError::InvalidParameters(ref e) => write!(f, "Invalid parameters: {}", e),
+            CeremonyError::Verification(ref e) => write!(f, "Verification failed: {}", e),
+            CeremonyError::Checkpoint(ref e) => write!(f, "Unusable checkpoint: {}", e),
+            CeremonyError::InvalidHeader(ref e) => write!(f, "Invalid file header: {}", e),
+            CeremonyError::UnknownLayout(ref e) => write!(f, "Unrecognized file layout: {}", e),
+            CeremonyError::BrokenTranscript(ref e) => write!(f, "Broken transcript: {}", e),
+            CeremonyError::BeaconMismatch(ref e) => write!(f, "Beacon mismatch: {}", e),
+            CeremonyError::ReductionMismatch(ref e) => write!(f, "Reduction mismatch: {}", e),
+            CeremonyError::SplitMismatch(ref e) => write!(f, "SRS mismatch: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CeremonyError {}
+
+impl From<io::Error> for CeremonyError {
+    fn from(err: io::Error) -> CeremonyError {
+        CeremonyError::Io(err)
+    }
+}
+
+impl From<VerificationError> for CeremonyError {
+    fn from(err: VerificationError) -> CeremonyError {
+        CeremonyError::Verification(err)
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use super::parameters::ElementType;
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Long-running operation that progress is reported for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    GenerateInitial,
+    Transform,
+    Verify,
+    Decompress,
+    Compress,
+    Reduce,
+    Export,
+    Import,
+    Lagrange,
+    Hash,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Phase::GenerateInitial => write!(f, "Generating"),
+            Phase::Transform => write!(f, "Transforming"),
+            Phase::Verify => write!(f, "Verifying"),
+            Phase::Decompress => write!(f, "Decompressing"),
+            Phase::Compress => write!(f, "Compressing"),
+            Phase::Reduce => write!(f, "Reducing"),
+            Phase::Export => write!(f, "Exporting"),
+            Phase::Import => write!(f, "Importing"),
+            Phase::Lagrange => write!(f, "Interpolating"),
+            Phase::Hash => write!(f, "Hashing"),
+        }
+    }
+}
+
+/// Receives progress of accumulator operations. Operations report after every chunk,
+/// so an implementation is called at most a few times per second and may block briefly.
+pub trait ProgressSink {
+    /// `done` out of `total` points of the accumulator went through `phase`,
+    /// the last of them being of `element_type`
+    fn points(&mut self, phase: Phase, element_type: ElementType, done: usize, total: usize);
+
+    /// `hashed` out of `total` bytes of a file went through the hash function
+    fn bytes_hashed(&mut self, hashed: u64, total: u64);
+}
+
+/// Ignores all progress
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn points(&mut self, _phase: Phase, _element_type: ElementType, _done: usize, _total: usize) {}
+
+    fn bytes_hashed(&mut self, _hashed: u64, _total: u64) {}
+}
+
+/// Renders progress as a bar with an ETA on the standard error
+pub struct ConsoleProgress {
+    phase: Option<Phase>,
+    started: Instant,
+}
+
+impl ConsoleProgress {
+    pub fn new() -> Self {
+        Self {
+            phase: None,
+            started: Instant::now(),
+        }
+    }
+
+    fn render(&mut self, phase: Phase, detail: String, done: u64, total: u64) {
+        if self.phase != Some(phase) {
+            self.phase = Some(phase);
+            self.started = Instant::now();
+        }
+
+        const WIDTH: u64 = 40;
+        let total = total.max(1);
+        let filled = (WIDTH * done / total) as usize;
+        let eta = match done {
+            0 => "--:--:--".to_string(),
+            _ => format_duration(
+                self.started
+                    .elapsed()
+                    .mul_f64((total - done) as f64 / done as f64),
+            ),
+        };
+
+        let mut stderr = io::stderr();
+        let _ = write!(
+            stderr,
+            "\r{} [{}{}] {:5.1}% {} ETA {}",
+            phase,
+            "=".repeat(filled),
+            " ".repeat(WIDTH as usize - filled),
+            100.0 * done as f64 / total as f64,
+            detail,
+            eta
+        );
+        if done >= total {
+            let _ = writeln!(stderr);
+            self.phase = None;
+        }
+        let _ = stderr.flush();
+    }
+}
+
+impl Default for ConsoleProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressSink for ConsoleProgress {
+    fn points(&mut self, phase: Phase, element_type: ElementType, done: usize, total: usize) {
+        let detail = format!("{} {}/{} points", element_type, done, total);
+        self.render(phase, detail, done as u64, total as u64);
+    }
+
+    fn bytes_hashed(&mut self, hashed: u64, total: u64) {
+        let detail = format!("{}/{} MiB", hashed >> 20, total >> 20);
+        self.render(Phase::Hash, detail, hashed, total);
+    }
+}
+
+/// Formats a duration as `hh:mm:ss`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// Counts the points an operation has gone through and forwards them to a sink
+pub(crate) struct PointCounter<'a> {
+    sink: &'a mut dyn ProgressSink,
+    phase: Phase,
+    /// Whether the accumulator holds alpha and beta, which a tau-only one leaves out
+    alpha_beta: bool,
+    done: usize,
+    total: usize,
+}
+
+impl<'a> PointCounter<'a> {
+    /// Counter for an operation that goes through every point of the accumulator once.
+    /// The single BetaG2 point is carried along with every chunk and not counted.
+    pub(crate) fn new(
+        sink: &'a mut dyn ProgressSink,
+        phase: Phase,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Self {
+        let total = parameters.tau_powers_g1_length()
+            + parameters.tau_powers_length()
+            + 2 * parameters.alpha_beta_length();
+        Self {
+            sink,
+            phase,
+            alpha_beta: parameters.alpha_beta_length() > 0,
+            done: 0,
+            total,
+        }
+    }
+
+    /// Reports powers below TAU_POWERS_LENGTH, where every element type but BetaG2 has one point per power,
+    /// AlphaG1 and BetaG1 only if the accumulator holds them
+    pub(crate) fn advance_powers(&mut self, powers: usize) {
+        self.advance(ElementType::TauG1, powers);
+        self.advance(ElementType::TauG2, powers);
+        if self.alpha_beta {
+            self.advance(ElementType::AlphaG1, powers);
+            self.advance(ElementType::BetaG1, powers);
+        }
+    }
+
+    pub(crate) fn advance(&mut self, element_type: ElementType, points: usize) {
+        self.done += points;
+        self.sink
+            .points(self.phase, element_type, self.done, self.total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CeremonyConfig, CeremonyMode};
+
+    /// Keeps the last report
+    struct LastReport(usize, usize);
+
+    impl ProgressSink for LastReport {
+        fn points(&mut self, _phase: Phase, _element_type: ElementType, done: usize, total: usize) {
+            self.0 = done;
+            self.1 = total;
+        }
+
+        fn bytes_hashed(&mut self, _hashed: u64, _total: u64) {}
+    }
+
+    #[test]
+    fn test_point_counter_ends_at_total_in_either_mode() {
+        let mut config = CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 3;
+
+        for &mode in &[CeremonyMode::Full, CeremonyMode::TauOnly] {
+            let parameters = RuntimeCeremonyParameters::new(config.clone()).with_mode(mode);
+            let mut report = LastReport(0, 0);
+            let mut counter = PointCounter::new(&mut report, Phase::Transform, &parameters);
+            counter.advance_powers(parameters.tau_powers_length());
+            counter.advance(
+                ElementType::TauG1,
+                parameters.tau_powers_g1_length() - parameters.tau_powers_length(),
+            );
+
+            assert_eq!(report.0, report.1);
+        }
+    }
+}
@@ -0,0 +1,933 @@
+use bellman::pairing::{CurveAffine, Engine};
+use byteorder::{ByteOrder, LittleEndian};
+use generic_array::GenericArray;
+use memmap::{Mmap, MmapMut};
+use typenum::consts::U64;
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::config::CeremonyMode;
+use super::curve::CurveKind;
+use super::keypair::PublicKey;
+use super::parameters::{CeremonyError, CheckForCorrectness, ElementType, UseCompression};
+use super::progress::{Phase, PointCounter, ProgressSink};
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// Magic of a snarkjs `.ptau` file
+pub const PTAU_MAGIC: &[u8; 4] = b"ptau";
+/// Version of the `.ptau` layout written here
+pub const PTAU_VERSION: u32 = 1;
+
+/// Sections of a `.ptau` file, in the order they are written
+const HEADER_SECTION: u32 = 1;
+const TAU_G1_SECTION: u32 = 2;
+const TAU_G2_SECTION: u32 = 3;
+const ALPHA_TAU_G1_SECTION: u32 = 4;
+const BETA_TAU_G1_SECTION: u32 = 5;
+const BETA_G2_SECTION: u32 = 6;
+const CONTRIBUTIONS_SECTION: u32 = 7;
+const SECTIONS: u32 = 7;
+
+/// Magic, version and number of sections
+const FILE_HEADER_SIZE: usize = 12;
+/// Type and size in front of every section
+const SECTION_HEADER_SIZE: usize = 12;
+
+/// Size of the BLAKE2b state snarkjs derives the response hash of a contribution from
+const PARTIAL_HASH_SIZE: usize = 216;
+/// snarkjs type of an ordinary contribution. snarkjs derives the key of a beacon contribution,
+/// type 1, in its own way, so the beacon of this transcript is exported as an ordinary
+/// contribution too.
+const CONTRIBUTION: u32 = 0;
+/// snarkjs contribution parameter with the name of the contributor
+const NAME_PARAMETER: u8 = 1;
+/// Name the beacon contribution is exported under
+const BEACON_NAME: &str = "beacon";
+
+/// Base field of a curve, for the little endian Montgomery form `.ptau` files store coordinates in
+pub(crate) struct BaseField {
+    /// Little endian limbs of the modulus
+    modulus: Vec<u64>,
+    /// -modulus^-1 mod 2^64
+    inv: u64,
+    /// R^2 mod modulus, with R = 2^(64 * limbs)
+    r2: Vec<u64>,
+}
+
+impl BaseField {
+    pub(crate) fn for_curve(curve: CurveKind) -> Self {
+        let modulus = match curve {
+            CurveKind::Bn254 => vec![
+                0x3c20_8c16_d87c_fd47,
+                0x9781_6a91_6871_ca8d,
+                0xb850_45b6_8181_585d,
+                0x3064_4e72_e131_a029,
+            ],
+            CurveKind::Bls12_381 => vec![
+                0xb9fe_ffff_ffff_aaab,
+                0x1eab_fffe_b153_ffff,
+                0x6730_d2a0_f6b0_f624,
+                0x6477_4b84_f385_12bf,
+                0x4b1b_a7b6_434b_acd7,
+                0x1a01_11ea_397f_e69a,
+            ],
+        };
+
+        let mut inv = 1u64;
+        for _ in 0..63 {
+            inv = inv.wrapping_mul(inv);
+            inv = inv.wrapping_mul(modulus[0]);
+        }
+        let inv = inv.wrapping_neg();
+
+        // R^2 is 1 doubled 2 * 64 * limbs times
+        let mut r2 = vec![0u64; modulus.len()];
+        r2[0] = 1;
+        for _ in 0..(128 * modulus.len()) {
+            let carry = shl1(&mut r2);
+            if carry || !less_than(&r2, &modulus) {
+                sub_assign(&mut r2, &modulus);
+            }
+        }
+
+        Self { modulus, inv, r2 }
+    }
+
+    /// Bytes of an element
+    pub(crate) fn byte_size(&self) -> usize {
+        self.modulus.len() * 8
+    }
+
+    fn limbs_from_be(&self, be: &[u8]) -> Vec<u64> {
+        let mut limbs = vec![0u64; self.modulus.len()];
+        for (limb, bytes) in limbs.iter_mut().zip(be.rchunks(8)) {
+            let mut word = [0u8; 8];
+            word[8 - bytes.len()..].copy_from_slice(bytes);
+            *limb = u64::from_be_bytes(word);
+        }
+
+        limbs
+    }
+
+    /// Writes the big endian element `be` as little endian Montgomery form to `le`
+    pub(crate) fn to_montgomery(&self, be: &[u8], le: &mut [u8]) {
+        let montgomery = self.mul(&self.limbs_from_be(be), &self.r2);
+        for (bytes, limb) in le.chunks_mut(8).zip(montgomery.iter()) {
+            LittleEndian::write_u64(bytes, *limb);
+        }
+    }
+
+    /// Writes the little endian Montgomery form `le` as big endian element to `be`, if it is
+    /// reduced modulo the modulus
+    pub(crate) fn to_canonical(&self, le: &[u8], be: &mut [u8]) -> bool {
+        let limbs: Vec<u64> = le.chunks(8).map(LittleEndian::read_u64).collect();
+        if !less_than(&limbs, &self.modulus) {
+            return false;
+        }
+
+        let mut one = vec![0u64; self.modulus.len()];
+        one[0] = 1;
+        let element = self.mul(&limbs, &one);
+        for (bytes, limb) in be.rchunks_mut(8).zip(element.iter()) {
+            bytes.copy_from_slice(&limb.to_be_bytes());
+        }
+
+        true
+    }
+
+    /// Montgomery product a * b / R
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.modulus.len();
+        let mut t = vec![0u64; n + 2];
+        for &word in b.iter() {
+            let mut carry = 0u128;
+            for j in 0..n {
+                let sum = t[j] as u128 + a[j] as u128 * word as u128 + carry;
+                t[j] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = t[n] as u128 + carry;
+            t[n] = sum as u64;
+            t[n + 1] = (sum >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.inv);
+            let mut carry = (t[0] as u128 + m as u128 * self.modulus[0] as u128) >> 64;
+            for j in 1..n {
+                let sum = t[j] as u128 + m as u128 * self.modulus[j] as u128 + carry;
+                t[j - 1] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = t[n] as u128 + carry;
+            t[n - 1] = sum as u64;
+            t[n] = t[n + 1] + (sum >> 64) as u64;
+        }
+
+        let overflow = t[n] != 0;
+        t.truncate(n);
+        if overflow || !less_than(&t, &self.modulus) {
+            sub_assign(&mut t, &self.modulus);
+        }
+
+        t
+    }
+}
+
+/// Doubles `a`, giving the bit shifted out
+fn shl1(a: &mut [u64]) -> bool {
+    let mut carry = 0;
+    for limb in a.iter_mut() {
+        let next = *limb >> 63;
+        *limb = (*limb << 1) | carry;
+        carry = next;
+    }
+
+    carry == 1
+}
+
+fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (a, b) in a.iter().rev().zip(b.iter().rev()) {
+        if a != b {
+            return a < b;
+        }
+    }
+
+    false
+}
+
+/// Subtracts `b` from `a`, wrapping around
+fn sub_assign(a: &mut [u64], b: &[u64]) {
+    let mut borrow = false;
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        let (diff, borrow_a) = a.overflowing_sub(*b);
+        let (diff, borrow_b) = diff.overflowing_sub(borrow as u64);
+        *a = diff;
+        borrow = borrow_a || borrow_b;
+    }
+}
+
+/// Writes `point` to `output` the way snarkjs stores points: the coordinates in little endian
+/// Montgomery form, with the `c0` half of an extension field element first. The point at
+/// infinity is all zeros.
+fn write_point<C: CurveAffine>(point: &C, field: &BaseField, output: &mut [u8]) {
+    if point.is_zero() {
+        for byte in output.iter_mut() {
+            *byte = 0;
+        }
+        return;
+    }
+
+    // The uncompressed encoding holds big endian coordinates, `c1` before `c0` in G2
+    let encoded = point.into_uncompressed();
+    let n8 = field.byte_size();
+    let coordinates = output.len() / n8;
+    for (i, target) in output.chunks_mut(n8).enumerate() {
+        let source = match coordinates {
+            4 => i ^ 1,
+            _ => i,
+        };
+        field.to_montgomery(&encoded.as_ref()[source * n8..(source + 1) * n8], target);
+    }
+}
+
+/// A contribution recorded in the contributions section of a `.ptau` file
+pub struct PtauContribution<E: Engine> {
+    /// tau, tau in G2, alpha and beta in G1 and beta in G2 of the accumulator the contribution
+    /// produced
+    pub tau_g1: E::G1Affine,
+    pub tau_g2: E::G2Affine,
+    pub alpha_g1: E::G1Affine,
+    pub beta_g1: E::G1Affine,
+    pub beta_g2: E::G2Affine,
+    pub public_key: PublicKey<E>,
+    /// Hash of the challenge derived from the response, zero if there is none
+    pub next_challenge: GenericArray<u8, U64>,
+    /// Whether it is the contribution of the beacon, which is named after it
+    pub beacon: bool,
+}
+
+impl<E: Engine> PtauContribution<E> {
+    /// Takes the contribution from a response
+    pub fn read(
+        response: &Mmap,
+        compression: UseCompression,
+        next_challenge: GenericArray<u8, U64>,
+        beacon: bool,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Result<Self, CeremonyError> {
+        ensure_full_mode(parameters)?;
+        let mut accumulator = BatchedAccumulator::<E>::empty(parameters);
+        accumulator.read_chunk(0, 2, compression, CheckForCorrectness::Yes, response)?;
+
+        Ok(Self {
+            tau_g1: accumulator.tau_powers_g1[1],
+            tau_g2: accumulator.tau_powers_g2[1],
+            alpha_g1: accumulator.alpha_tau_powers_g1[0],
+            beta_g1: accumulator.beta_tau_powers_g1[0],
+            beta_g2: accumulator.beta_g2,
+            public_key: PublicKey::read(response, compression, parameters)?,
+            next_challenge,
+            beacon,
+        })
+    }
+
+    fn encode(&self, field: &BaseField) -> Result<Vec<u8>, CeremonyError> {
+        let g1_size = 2 * field.byte_size();
+        let g2_size = 4 * field.byte_size();
+        let mut encoded = vec![];
+        let mut push = |write: &dyn Fn(&mut [u8]), size: usize| {
+            let start = encoded.len();
+            encoded.resize(start + size, 0);
+            write(&mut encoded[start..]);
+        };
+
+        push(&|out| write_point(&self.tau_g1, field, out), g1_size);
+        push(&|out| write_point(&self.tau_g2, field, out), g2_size);
+        push(&|out| write_point(&self.alpha_g1, field, out), g1_size);
+        push(&|out| write_point(&self.beta_g1, field, out), g1_size);
+        push(&|out| write_point(&self.beta_g2, field, out), g2_size);
+
+        let key = &self.public_key;
+        for point in &[
+            key.tau_g1.0,
+            key.tau_g1.1,
+            key.alpha_g1.0,
+            key.alpha_g1.1,
+            key.beta_g1.0,
+            key.beta_g1.1,
+        ] {
+            push(&|out| write_point(point, field, out), g1_size);
+        }
+        for point in &[key.tau_g2, key.alpha_g2, key.beta_g2] {
+            push(&|out| write_point(point, field, out), g2_size);
+        }
+
+        // The hashes of this transcript cover other encodings than the ones of snarkjs, so its
+        // partial response hash is left empty
+        encoded.extend_from_slice(&[0u8; PARTIAL_HASH_SIZE]);
+        encoded.extend_from_slice(self.next_challenge.as_slice());
+
+        let mut parameters = vec![];
+        if self.beacon {
+            parameters.push(NAME_PARAMETER);
+            parameters.push(BEACON_NAME.len() as u8);
+            parameters.extend_from_slice(BEACON_NAME.as_bytes());
+        }
+        encoded.extend_from_slice(&CONTRIBUTION.to_le_bytes());
+        encoded.extend_from_slice(&(parameters.len() as u32).to_le_bytes());
+        encoded.extend_from_slice(&parameters);
+
+        Ok(encoded)
+    }
+}
+
+/// Byte layout of a `.ptau` file for an accumulator
+struct PtauLayout {
+    n8: usize,
+    /// Start of the body of every section, indexed by section type
+    bodies: [usize; SECTIONS as usize + 1],
+    size: usize,
+}
+
+impl PtauLayout {
+    fn new(
+        field: &BaseField,
+        contributions_size: usize,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Self {
+        let n8 = field.byte_size();
+        let section_sizes = [
+            (HEADER_SECTION, 4 + n8 + 4 + 4),
+            (TAU_G1_SECTION, parameters.tau_powers_g1_length() * 2 * n8),
+            (TAU_G2_SECTION, parameters.tau_powers_length() * 4 * n8),
+            (
+                ALPHA_TAU_G1_SECTION,
+                parameters.tau_powers_length() * 2 * n8,
+            ),
+            (BETA_TAU_G1_SECTION, parameters.tau_powers_length() * 2 * n8),
+            (BETA_G2_SECTION, 4 * n8),
+            (CONTRIBUTIONS_SECTION, contributions_size),
+        ];
+
+        let mut bodies = [0; SECTIONS as usize + 1];
+        let mut position = FILE_HEADER_SIZE;
+        for &(section, size) in section_sizes.iter() {
+            bodies[section as usize] = position + SECTION_HEADER_SIZE;
+            position += SECTION_HEADER_SIZE + size;
+        }
+
+        Self {
+            n8,
+            bodies,
+            size: position,
+        }
+    }
+
+    fn write_headers(&self, output: &mut [u8]) {
+        output[0..4].copy_from_slice(PTAU_MAGIC);
+        LittleEndian::write_u32(&mut output[4..8], PTAU_VERSION);
+        LittleEndian::write_u32(&mut output[8..12], SECTIONS);
+
+        for section in HEADER_SECTION..=SECTIONS {
+            let start = self.bodies[section as usize] - SECTION_HEADER_SIZE;
+            let end = match section {
+                SECTIONS => self.size,
+                _ => self.bodies[section as usize + 1] - SECTION_HEADER_SIZE,
+            };
+            LittleEndian::write_u32(&mut output[start..start + 4], section);
+            LittleEndian::write_u64(
+                &mut output[start + 4..start + 12],
+                (end - start - SECTION_HEADER_SIZE) as u64,
+            );
+        }
+    }
+
+    /// Position of the point at `index` of a section of points with `coordinates` coordinates
+    fn point(&self, section: u32, index: usize, coordinates: usize) -> std::ops::Range<usize> {
+        let size = coordinates * self.n8;
+        let start = self.bodies[section as usize] + index * size;
+
+        start..start + size
+    }
+}
+
+fn encode_contributions<E: Engine>(
+    contributions: &[PtauContribution<E>],
+    field: &BaseField,
+) -> Result<Vec<u8>, CeremonyError> {
+    let mut encoded = (contributions.len() as u32).to_le_bytes().to_vec();
+    for contribution in contributions {
+        encoded.extend(contribution.encode(field)?);
+    }
+
+    Ok(encoded)
+}
+
+/// A `.ptau` file always holds the alpha and beta sections, so a tau-only accumulator can't be
+/// written as one
+fn ensure_full_mode(parameters: &RuntimeCeremonyParameters) -> Result<(), CeremonyError> {
+    match parameters.mode() {
+        CeremonyMode::Full => Ok(()),
+        CeremonyMode::TauOnly => Err(CeremonyError::InvalidParameters(
+            "a tau-only accumulator has no alpha and beta sections to export".to_string(),
+        )),
+    }
+}
+
+/// Size of the `.ptau` file of an accumulator and its `contributions`
+pub fn ptau_byte_size<E: Engine>(
+    contributions: &[PtauContribution<E>],
+    parameters: &RuntimeCeremonyParameters,
+) -> Result<usize, CeremonyError> {
+    let field = BaseField::for_curve(parameters.curve());
+    let contributions = encode_contributions(contributions, &field)?;
+
+    Ok(PtauLayout::new(&field, contributions.len(), parameters).size)
+}
+
+/// Writes the accumulator in `input_map` as a snarkjs `.ptau` file to `output_map`, which has to
+/// be `ptau_byte_size` bytes long. The points are converted chunk by chunk, and `contributions`
+/// go into the contributions section.
+pub fn export_ptau<E: Engine>(
+    input_map: &Mmap,
+    input_compression: UseCompression,
+    check_input_for_correctness: CheckForCorrectness,
+    contributions: &[PtauContribution<E>],
+    output_map: &mut MmapMut,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    ensure_full_mode(parameters)?;
+    let field = BaseField::for_curve(parameters.curve());
+    let encoded_contributions = encode_contributions(contributions, &field)?;
+    let layout = PtauLayout::new(&field, encoded_contributions.len(), parameters);
+    if output_map.len() != layout.size {
+        return Err(CeremonyError::SizeMismatch {
+            expected: layout.size,
+            actual: output_map.len(),
+        });
+    }
+
+    layout.write_headers(output_map);
+
+    let header = layout.bodies[HEADER_SECTION as usize];
+    let n8 = layout.n8;
+    LittleEndian::write_u32(&mut output_map[header..header + 4], n8 as u32);
+    for (bytes, limb) in output_map[header + 4..header + 4 + n8]
+        .chunks_mut(8)
+        .zip(field.modulus.iter())
+    {
+        LittleEndian::write_u64(bytes, *limb);
+    }
+    let power = parameters.required_power() as u32;
+    LittleEndian::write_u32(&mut output_map[header + 4 + n8..header + 8 + n8], power);
+    LittleEndian::write_u32(&mut output_map[header + 8 + n8..header + 12 + n8], power);
+
+    let contributions_body = layout.bodies[CONTRIBUTIONS_SECTION as usize];
+    output_map[contributions_body..].copy_from_slice(&encoded_contributions);
+
+    let mut accumulator = BatchedAccumulator::<E>::empty(parameters);
+    let mut progress = PointCounter::new(progress, Phase::Export, parameters);
+    let mut start = 0;
+    while start < parameters.tau_powers_g1_length() {
+        let size = match start < parameters.tau_powers_length() {
+            true => parameters
+                .empirical_batch_size()
+                .min(parameters.tau_powers_length() - start),
+            false => parameters
+                .tau_g1_batch_size()
+                .min(parameters.tau_powers_g1_length() - start),
+        };
+        accumulator.read_chunk(
+            start,
+            size,
+            input_compression,
+            check_input_for_correctness,
+            input_map,
+        )?;
+
+        for (i, point) in accumulator.tau_powers_g1.iter().enumerate() {
+            let range = layout.point(TAU_G1_SECTION, start + i, 2);
+            write_point(point, &field, &mut output_map[range]);
+        }
+        for (i, point) in accumulator.tau_powers_g2.iter().enumerate() {
+            let range = layout.point(TAU_G2_SECTION, start + i, 4);
+            write_point(point, &field, &mut output_map[range]);
+        }
+        for (i, point) in accumulator.alpha_tau_powers_g1.iter().enumerate() {
+            let range = layout.point(ALPHA_TAU_G1_SECTION, start + i, 2);
+            write_point(point, &field, &mut output_map[range]);
+        }
+        for (i, point) in accumulator.beta_tau_powers_g1.iter().enumerate() {
+            let range = layout.point(BETA_TAU_G1_SECTION, start + i, 2);
+            write_point(point, &field, &mut output_map[range]);
+        }
+        if start == 0 {
+            let range = layout.point(BETA_G2_SECTION, 0, 4);
+            write_point(&accumulator.beta_g2, &field, &mut output_map[range]);
+        }
+
+        match start < parameters.tau_powers_length() {
+            true => progress.advance_powers(size),
+            false => progress.advance(ElementType::TauG1, size),
+        }
+        start += size;
+    }
+
+    output_map.flush()?;
+
+    Ok(())
+}
+
+/// What the header and contributions sections of a `.ptau` file tell about its accumulator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtauInfo {
+    /// Power of two of the number of powers of tau
+    pub power: usize,
+    /// Number of contributions the accumulator holds
+    pub contributions: usize,
+}
+
+/// Finds the body of every section of a `.ptau` file, indexed by section type. Sections snarkjs
+/// adds after the contributions, like the Lagrange bases of a prepared file, are skipped.
+fn read_sections(ptau: &[u8]) -> Result<Vec<std::ops::Range<usize>>, CeremonyError> {
+    let malformed = |e: &str| CeremonyError::UnknownLayout(format!("not a .ptau file: {}", e));
+    if ptau.len() < FILE_HEADER_SIZE || &ptau[0..4] != PTAU_MAGIC {
+        return Err(malformed("no ptau magic"));
+    }
+    let version = LittleEndian::read_u32(&ptau[4..8]);
+    if version != PTAU_VERSION {
+        return Err(malformed(&format!("unsupported version {}", version)));
+    }
+
+    let mut sections = vec![0..0; SECTIONS as usize + 1];
+    let mut found = vec![false; SECTIONS as usize + 1];
+    let mut position = FILE_HEADER_SIZE;
+    for _ in 0..LittleEndian::read_u32(&ptau[8..12]) {
+        if ptau.len() < position + SECTION_HEADER_SIZE {
+            return Err(malformed("truncated section header"));
+        }
+        let section = LittleEndian::read_u32(&ptau[position..position + 4]) as usize;
+        let size = LittleEndian::read_u64(&ptau[position + 4..position + 12]) as usize;
+        let body = position + SECTION_HEADER_SIZE;
+        if ptau.len() < body + size {
+            return Err(malformed(&format!("section {} is truncated", section)));
+        }
+        if section < sections.len() {
+            if found[section] {
+                return Err(malformed(&format!("section {} appears twice", section)));
+            }
+            found[section] = true;
+            sections[section] = body..body + size;
+        }
+        position = body + size;
+    }
+
+    match (HEADER_SECTION..=SECTIONS).find(|&section| !found[section as usize]) {
+        Some(section) => Err(malformed(&format!("section {} is missing", section))),
+        None => Ok(sections),
+    }
+}
+
+/// Reads the power and number of contributions of the `.ptau` file in `ptau`, checking that its
+/// points are on `curve`
+pub fn read_ptau_info(ptau: &[u8], curve: CurveKind) -> Result<PtauInfo, CeremonyError> {
+    let sections = read_sections(ptau)?;
+    let field = BaseField::for_curve(curve);
+    let n8 = field.byte_size();
+
+    let header = &ptau[sections[HEADER_SECTION as usize].clone()];
+    if header.len() < 4 + n8 + 4 || LittleEndian::read_u32(&header[0..4]) as usize != n8 {
+        return Err(CeremonyError::UnknownLayout(format!(
+            "the .ptau file is not for {}",
+            curve
+        )));
+    }
+    let modulus: Vec<u64> = header[4..4 + n8]
+        .chunks(8)
+        .map(LittleEndian::read_u64)
+        .collect();
+    if modulus != field.modulus {
+        return Err(CeremonyError::UnknownLayout(format!(
+            "the .ptau file is not for {}",
+            curve
+        )));
+    }
+    let power = LittleEndian::read_u32(&header[4 + n8..8 + n8]) as usize;
+
+    let contributions = &ptau[sections[CONTRIBUTIONS_SECTION as usize].clone()];
+    if contributions.len() < 4 {
+        return Err(CeremonyError::UnknownLayout(
+            "not a .ptau file: empty contributions section".to_string(),
+        ));
+    }
+
+    Ok(PtauInfo {
+        power,
+        contributions: LittleEndian::read_u32(&contributions[0..4]) as usize,
+    })
+}
+
+/// Writes the point at `input` the way the uncompressed encoding stores points: the coordinates
+/// big endian, with the `c1` half of an extension field element first
+fn read_point(input: &[u8], field: &BaseField, output: &mut [u8]) -> bool {
+    let n8 = field.byte_size();
+    let coordinates = input.len() / n8;
+    for (i, target) in output.chunks_mut(n8).enumerate() {
+        let source = match coordinates {
+            4 => i ^ 1,
+            _ => i,
+        };
+        if !field.to_canonical(&input[source * n8..(source + 1) * n8], target) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Converts the snarkjs `.ptau` file in `ptau` to an uncompressed challenge, written to
+/// `output_map`, which has to be `accumulator_byte_size` bytes long. The challenge starts with
+/// the hash of the `.ptau` file, in place of the hash of a previous response, and every point is
+/// decoded with `CheckForCorrectness::Yes` once converted. A tau-only challenge takes only the
+/// tau sections.
+pub fn import_ptau<E: Engine>(
+    ptau: &Mmap,
+    mut output_map: MmapMut,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<Mmap, CeremonyError> {
+    let info = read_ptau_info(ptau, parameters.curve())?;
+    if info.power != parameters.required_power() {
+        return Err(CeremonyError::InvalidParameters(format!(
+            "the .ptau file holds 2^{} powers of tau, not 2^{}",
+            info.power,
+            parameters.required_power()
+        )));
+    }
+    if output_map.len() != parameters.accumulator_byte_size() {
+        return Err(CeremonyError::SizeMismatch {
+            expected: parameters.accumulator_byte_size(),
+            actual: output_map.len(),
+        });
+    }
+
+    let hash = BatchedAccumulator::<E>::calculate_hash(ptau, progress);
+    output_map[0..parameters.hash_size()].copy_from_slice(hash.as_slice());
+
+    // The points sections follow each other in the same order as the uncompressed challenge
+    let field = BaseField::for_curve(parameters.curve());
+    let n8 = field.byte_size();
+    let sections = read_sections(ptau)?;
+    let mut position = parameters.hash_size();
+    let mut counter = PointCounter::new(progress, Phase::Import, parameters);
+    for &(section, element_type, length, coordinates) in &[
+        (
+            TAU_G1_SECTION,
+            ElementType::TauG1,
+            parameters.tau_powers_g1_length(),
+            2,
+        ),
+        (
+            TAU_G2_SECTION,
+            ElementType::TauG2,
+            parameters.tau_powers_length(),
+            4,
+        ),
+        (
+            ALPHA_TAU_G1_SECTION,
+            ElementType::AlphaG1,
+            parameters.alpha_beta_length(),
+            2,
+        ),
+        (
+            BETA_TAU_G1_SECTION,
+            ElementType::BetaG1,
+            parameters.alpha_beta_length(),
+            2,
+        ),
+        (
+            BETA_G2_SECTION,
+            ElementType::BetaG2,
+            parameters.beta_g2_length(),
+            4,
+        ),
+    ] {
+        // Sections the challenge leaves out are not read at all
+        if length == 0 {
+            continue;
+        }
+        let size = coordinates * n8;
+        let body = sections[section as usize].clone();
+        if body.len() != length * size {
+            return Err(CeremonyError::SizeMismatch {
+                expected: length * size,
+                actual: body.len(),
+            });
+        }
+
+        let mut start = 0;
+        while start < length {
+            let end = length.min(start + parameters.empirical_batch_size());
+            for index in start..end {
+                let input = &ptau[body.start + index * size..body.start + (index + 1) * size];
+                if !read_point(input, &field, &mut output_map[position..position + size]) {
+                    return Err(CeremonyError::InvalidParameters(format!(
+                        "{} point {} of the .ptau file has a coordinate out of the field",
+                        element_type, index
+                    )));
+                }
+                position += size;
+            }
+            if element_type != ElementType::BetaG2 {
+                counter.advance(element_type, end - start);
+            }
+            start = end;
+        }
+    }
+
+    output_map.flush()?;
+    let challenge = output_map.make_read_only()?;
+    BatchedAccumulator::<E>::check_points(&challenge, UseCompression::No, parameters, progress)?;
+
+    Ok(challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// R mod q for both base fields, i.e. one in Montgomery form
+    const BN254_ONE: [u64; 4] = [
+        0xd35d_438d_c58f_0d9d,
+        0x0a78_eb28_f5c7_0b3d,
+        0x666e_a36f_7879_462c,
+        0x0e0a_77c1_9a07_df2f,
+    ];
+    const BLS12_381_ONE: [u64; 6] = [
+        0x7609_0000_0002_fffd,
+        0xebf4_000b_c40c_0002,
+        0x5f48_9857_53c7_58ba,
+        0x77ce_5853_7052_5745,
+        0x5c07_1a97_a256_ec6d,
+        0x15f6_5ec3_fa80_e493,
+    ];
+
+    fn montgomery(field: &BaseField, be: &[u8]) -> Vec<u64> {
+        let mut le = vec![0u8; field.byte_size()];
+        field.to_montgomery(be, &mut le);
+        le.chunks(8).map(LittleEndian::read_u64).collect()
+    }
+
+    #[test]
+    fn test_elements_are_converted_to_montgomery_form() {
+        for &(curve, one) in &[
+            (CurveKind::Bn254, &BN254_ONE[..]),
+            (CurveKind::Bls12_381, &BLS12_381_ONE[..]),
+        ] {
+            let field = BaseField::for_curve(curve);
+            let n8 = field.byte_size();
+            let mut be = vec![0u8; n8];
+            be[n8 - 1] = 1;
+            assert_eq!(montgomery(&field, &be), one);
+
+            // Zero stays zero, and R / R is one
+            assert_eq!(montgomery(&field, &vec![0u8; n8]), vec![0u64; n8 / 8]);
+            let mut unit = vec![0u64; n8 / 8];
+            unit[0] = 1;
+            assert_eq!(field.mul(one, &unit), unit);
+
+            // q - 1 is -1, whose Montgomery form is q - R
+            let mut minus_one = field.modulus.clone();
+            minus_one[0] -= 1;
+            let be: Vec<u8> = minus_one
+                .iter()
+                .rev()
+                .flat_map(|l| l.to_be_bytes())
+                .collect();
+            let mut expected = field.modulus.clone();
+            sub_assign(&mut expected, one);
+            assert_eq!(montgomery(&field, &be), expected);
+        }
+    }
+
+    #[test]
+    fn test_sections_follow_each_other() {
+        let mut config = crate::config::CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 2;
+        let parameters = RuntimeCeremonyParameters::new(config);
+        let field = BaseField::for_curve(CurveKind::Bn254);
+        let layout = PtauLayout::new(&field, 4, &parameters);
+
+        let mut output = vec![0u8; layout.size];
+        layout.write_headers(&mut output);
+        assert_eq!(&output[0..4], PTAU_MAGIC);
+        assert_eq!(LittleEndian::read_u32(&output[8..12]), SECTIONS);
+
+        // Walking the sections by their sizes ends exactly at the end of the file
+        let mut position = FILE_HEADER_SIZE;
+        let mut sizes = vec![];
+        for section in HEADER_SECTION..=SECTIONS {
+            assert_eq!(LittleEndian::read_u32(&output[position..]), section);
+            let size = LittleEndian::read_u64(&output[position + 4..]) as usize;
+            sizes.push(size);
+            position += SECTION_HEADER_SIZE + size;
+        }
+        assert_eq!(position, output.len());
+        assert_eq!(
+            sizes,
+            vec![4 + 32 + 8, 7 * 64, 4 * 128, 4 * 64, 4 * 64, 128, 4]
+        );
+    }
+
+    #[test]
+    fn test_initial_accumulator_is_exported() {
+        use crate::progress::NoProgress;
+        use crate::test_utils::{bn254_parameters, fresh_challenge};
+        use bellman::pairing::bn256::Bn256;
+
+        let parameters = bn254_parameters(2);
+        let challenge = fresh_challenge(&parameters);
+
+        let contributions: Vec<PtauContribution<Bn256>> = vec![];
+        let size = ptau_byte_size(&contributions, &parameters).unwrap();
+        let mut ptau = MmapMut::map_anon(size).unwrap();
+        export_ptau(
+            &challenge,
+            UseCompression::No,
+            CheckForCorrectness::Yes,
+            &contributions,
+            &mut ptau,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+
+        // Every power of the initial accumulator is the generator (1, 2) of G1
+        let field = BaseField::for_curve(CurveKind::Bn254);
+        let layout = PtauLayout::new(&field, 4, &parameters);
+        let mut generator = vec![0u8; 64];
+        let mut two = [0u8; 32];
+        two[31] = 2;
+        field.to_montgomery(&two[..], &mut generator[32..]);
+        for (i, limb) in BN254_ONE.iter().enumerate() {
+            LittleEndian::write_u64(&mut generator[i * 8..], *limb);
+        }
+        for index in 0..parameters.tau_powers_g1_length() {
+            assert_eq!(
+                &ptau[layout.point(TAU_G1_SECTION, index, 2)],
+                &generator[..]
+            );
+        }
+        assert_eq!(
+            LittleEndian::read_u32(&ptau[layout.bodies[CONTRIBUTIONS_SECTION as usize]..]),
+            0
+        );
+
+        // Importing it back gives the same points, after the hash of the .ptau file
+        let ptau = ptau.make_read_only().unwrap();
+        let output = MmapMut::map_anon(parameters.accumulator_byte_size()).unwrap();
+        let imported = import_ptau::<Bn256>(&ptau, output, &parameters, &mut NoProgress).unwrap();
+        assert_eq!(
+            &imported[0..64],
+            BatchedAccumulator::<Bn256>::calculate_hash(&ptau, &mut NoProgress).as_slice()
+        );
+        assert_eq!(&imported[64..], &challenge[64..]);
+    }
+
+    #[test]
+    fn test_elements_are_converted_to_canonical_form() {
+        for &curve in &[CurveKind::Bn254, CurveKind::Bls12_381] {
+            let field = BaseField::for_curve(curve);
+            let n8 = field.byte_size();
+            let mut be = vec![0u8; n8];
+            for (i, byte) in be.iter_mut().enumerate().skip(1) {
+                *byte = i as u8;
+            }
+
+            let mut le = vec![0u8; n8];
+            field.to_montgomery(&be, &mut le);
+            let mut back = vec![0u8; n8];
+            assert!(field.to_canonical(&le, &mut back));
+            assert_eq!(back, be);
+
+            // The modulus itself is not a reduced element
+            let mut modulus = vec![0u8; n8];
+            for (bytes, limb) in modulus.chunks_mut(8).zip(field.modulus.iter()) {
+                LittleEndian::write_u64(bytes, *limb);
+            }
+            assert!(!field.to_canonical(&modulus, &mut back));
+        }
+    }
+
+    #[test]
+    fn test_ptau_info_is_read_from_header() {
+        let mut config = crate::config::CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 2;
+        let parameters = RuntimeCeremonyParameters::new(config);
+        let field = BaseField::for_curve(CurveKind::Bn254);
+        let layout = PtauLayout::new(&field, 4, &parameters);
+
+        let mut output = vec![0u8; layout.size];
+        layout.write_headers(&mut output);
+        let header = layout.bodies[HEADER_SECTION as usize];
+        LittleEndian::write_u32(&mut output[header..], 32);
+        for (i, limb) in field.modulus.iter().enumerate() {
+            LittleEndian::write_u64(&mut output[header + 4 + i * 8..], *limb);
+        }
+        LittleEndian::write_u32(&mut output[header + 36..], 2);
+        LittleEndian::write_u32(&mut output[header + 40..], 2);
+        let contributions = layout.bodies[CONTRIBUTIONS_SECTION as usize];
+        LittleEndian::write_u32(&mut output[contributions..], 3);
+
+        assert_eq!(
+            read_ptau_info(&output, CurveKind::Bn254).unwrap(),
+            PtauInfo {
+                power: 2,
+                contributions: 3
+            }
+        );
+        assert!(read_ptau_info(&output, CurveKind::Bls12_381).is_err());
+
+        // A file cut short loses its last section
+        assert!(read_ptau_info(&output[..output.len() - 1], CurveKind::Bn254).is_err());
+    }
+}
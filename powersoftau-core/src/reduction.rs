@@ -0,0 +1,151 @@
+use bellman::pairing::Engine;
+use memmap::Mmap;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::curve::CurveKind;
+use super::parameters::CeremonyError;
+use super::progress::ProgressSink;
+
+/// Note written next to an accumulator reduced to a smaller power, linking it to the transcript
+/// file it was cut from. The reduced file starts with the hash of that file, in place of the hash
+/// of a previous response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReductionNote {
+    pub curve: CurveKind,
+    /// Power of the accumulator the points were taken from
+    pub source_power: usize,
+    /// Power of the reduced accumulator
+    pub power: usize,
+    /// Hex encoded BLAKE2b hash of the source file
+    pub source_hash: String,
+    /// Hex encoded BLAKE2b hash of the reduced file
+    pub hash: String,
+}
+
+impl ReductionNote {
+    pub fn new(
+        curve: CurveKind,
+        source_power: usize,
+        power: usize,
+        source_hash: &[u8],
+        hash: &[u8],
+    ) -> Self {
+        Self {
+            curve,
+            source_power,
+            power,
+            source_hash: hex::encode(source_hash),
+            hash: hex::encode(hash),
+        }
+    }
+
+    /// Checks that `reduced` is the file of this note and starts with the hash of `source`
+    pub fn verify<E: Engine>(
+        &self,
+        source: &Mmap,
+        reduced: &Mmap,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        let source_hash = BatchedAccumulator::<E>::calculate_hash(source, progress);
+        if hex::encode(source_hash.as_slice()) != self.source_hash {
+            return Err(CeremonyError::ReductionMismatch(
+                "the source file is not the one in the note".to_string(),
+            ));
+        }
+        BatchedAccumulator::<E>::check_hash_chain(reduced, source_hash.as_slice()).map_err(
+            |_| {
+                CeremonyError::ReductionMismatch(
+                    "the reduced file does not start with the hash of the source file".to_string(),
+                )
+            },
+        )?;
+
+        let hash = BatchedAccumulator::<E>::calculate_hash(reduced, progress);
+        if hex::encode(hash.as_slice()) != self.hash {
+            return Err(CeremonyError::ReductionMismatch(
+                "the reduced file is not the one in the note".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Location of the note of the reduced accumulator at `reduced`
+    pub fn path_for<P: AsRef<Path>>(reduced: P) -> PathBuf {
+        let mut path = reduced.as_ref().as_os_str().to_owned();
+        path.push(".reduction");
+        PathBuf::from(path)
+    }
+
+    /// Reads the note at `path`, if there is one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, CeremonyError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_reader(file).map(Some).map_err(|e| {
+            CeremonyError::InvalidParameters(format!("malformed reduction note: {}", e))
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CeremonyError> {
+        let mut file = File::create(path)?;
+        serde_json::to_writer_pretty(&mut file, self)
+            .map_err(|e| CeremonyError::InvalidParameters(e.to_string()))?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::Bn256;
+    use memmap::MmapMut;
+    use std::io::Write;
+
+    use crate::progress::NoProgress;
+
+    fn map(bytes: &[u8]) -> Mmap {
+        let mut map = MmapMut::map_anon(bytes.len()).unwrap();
+        (&mut map[0..]).write_all(bytes).unwrap();
+        map.make_read_only().unwrap()
+    }
+
+    #[test]
+    fn test_note_links_reduced_file_to_source() {
+        let source = map(&[7u8; 256]);
+        let source_hash = BatchedAccumulator::<Bn256>::calculate_hash(&source, &mut NoProgress);
+        let mut reduced = source_hash.to_vec();
+        reduced.extend_from_slice(&[7u8; 64]);
+        let reduced = map(&reduced);
+        let reduced_hash = BatchedAccumulator::<Bn256>::calculate_hash(&reduced, &mut NoProgress);
+
+        let note = ReductionNote::new(
+            CurveKind::Bn254,
+            3,
+            2,
+            source_hash.as_slice(),
+            reduced_hash.as_slice(),
+        );
+        note.verify::<Bn256>(&source, &reduced, &mut NoProgress)
+            .unwrap();
+
+        // Another source, or a reduced file that does not start with its hash
+        let other = map(&[8u8; 256]);
+        assert!(note
+            .verify::<Bn256>(&other, &reduced, &mut NoProgress)
+            .is_err());
+        let unlinked = map(&[7u8; 128]);
+        assert!(note
+            .verify::<Bn256>(&source, &unlinked, &mut NoProgress)
+            .is_err());
+    }
+}
@@ -1,14 +1,52 @@
-use crate::config::CeremonyConfig;
-use crate::parameters::{CheckForCorrectness, UseCompression};
+use crate::beacon::BeaconParameters;
+use crate::config::{CeremonyConfig, CeremonyMode};
+use crate::curve::{CurveKind, PointSizes};
+use crate::parameters::{CheckForCorrectness, TranscriptFile, UseCompression};
+
+/// Number of powers processed in a single chunk when no memory budget is configured
+const DEFAULT_EMPIRICAL_BATCH_SIZE: usize = 1 << 21;
 
 #[derive(Clone, Debug)]
 pub struct RuntimeCeremonyParameters {
     config: CeremonyConfig,
+    point_sizes: PointSizes,
+    empirical_batch_size: Option<usize>,
 }
 
 impl RuntimeCeremonyParameters {
     pub fn new(config: CeremonyConfig) -> Self {
-        Self { config }
+        let point_sizes = config.curve.name.point_sizes();
+        Self {
+            config,
+            point_sizes,
+            empirical_batch_size: None,
+        }
+    }
+
+    /// Overrides the number of powers processed in a single chunk, ignoring the memory budget.
+    /// A chunk holds at least one power.
+    pub fn with_empirical_batch_size(mut self, empirical_batch_size: usize) -> Self {
+        self.empirical_batch_size = Some(empirical_batch_size.max(1));
+        self
+    }
+
+    /// Overrides the power of tau, e.g. with the one of an existing challenge
+    pub fn with_required_power(mut self, required_power: usize) -> Self {
+        self.config.ceremony.required_power = required_power;
+        self
+    }
+
+    /// Overrides the vectors the accumulator holds, e.g. with the ones of an existing challenge
+    pub fn with_mode(mut self, mode: CeremonyMode) -> Self {
+        self.config.ceremony.mode = mode;
+        self
+    }
+
+    /// Sets the memory budget in bytes that chunk sizes are derived from
+    pub fn with_max_memory(mut self, max_memory: u64) -> Self {
+        self.config.ceremony.max_memory = Some(max_memory);
+        self.empirical_batch_size = None;
+        self
     }
 
     pub fn from_file(path: &str) -> Result<Self, config::ConfigError> {
@@ -21,29 +59,50 @@ impl RuntimeCeremonyParameters {
         Ok(Self::new(config))
     }
 
+    pub fn from_env_and_args<I>(args: I) -> Result<Self, config::ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let config = CeremonyConfig::from_env_and_args(args)?;
+        Ok(Self::new(config))
+    }
+
     pub fn default_bls12_381() -> Self {
         Self::new(CeremonyConfig::default_bls12_381())
     }
 
+    pub fn default_bn254() -> Self {
+        Self::new(CeremonyConfig::default_bn254())
+    }
+
+    pub fn curve(&self) -> CurveKind {
+        self.config.curve.name
+    }
+
+    /// Vectors the accumulator holds
+    pub fn mode(&self) -> CeremonyMode {
+        self.config.ceremony.mode
+    }
+
     // Accessors that match the old trait interface
     pub fn required_power(&self) -> usize {
         self.config.ceremony.required_power
     }
 
     pub fn g1_uncompressed_byte_size(&self) -> usize {
-        self.config.curve.g1_uncompressed_byte_size
+        self.point_sizes.g1_uncompressed
     }
 
     pub fn g2_uncompressed_byte_size(&self) -> usize {
-        self.config.curve.g2_uncompressed_byte_size
+        self.point_sizes.g2_uncompressed
     }
 
     pub fn g1_compressed_byte_size(&self) -> usize {
-        self.config.curve.g1_compressed_byte_size
+        self.point_sizes.g1_compressed
     }
 
     pub fn g2_compressed_byte_size(&self) -> usize {
-        self.config.curve.g2_compressed_byte_size
+        self.point_sizes.g2_compressed
     }
 
     pub fn tau_powers_length(&self) -> usize {
@@ -54,24 +113,151 @@ impl RuntimeCeremonyParameters {
         self.config.tau_powers_g1_length()
     }
 
+    /// Number of AlphaG1 and BetaG1 powers, none in a tau-only ceremony
+    pub fn alpha_beta_length(&self) -> usize {
+        match self.mode() {
+            CeremonyMode::Full => self.tau_powers_length(),
+            CeremonyMode::TauOnly => 0,
+        }
+    }
+
+    /// Number of BetaG2 points, none in a tau-only ceremony
+    pub fn beta_g2_length(&self) -> usize {
+        match self.mode() {
+            CeremonyMode::Full => 1,
+            CeremonyMode::TauOnly => 0,
+        }
+    }
+
     pub fn accumulator_byte_size(&self) -> usize {
-        self.config.accumulator_byte_size()
+        (self.tau_powers_g1_length() * self.g1_uncompressed_byte_size()) + // g1 tau powers
+        (self.tau_powers_length() * self.g2_uncompressed_byte_size()) + // g2 tau powers
+        (self.alpha_beta_length() * self.g1_uncompressed_byte_size()) + // alpha tau powers
+        (self.alpha_beta_length() * self.g1_uncompressed_byte_size()) + // beta tau powers
+        self.beta_g2_length() * self.g2_uncompressed_byte_size() + // beta in g2
+        self.hash_size() // blake2b hash of previous contribution
     }
 
     pub fn public_key_size(&self) -> usize {
-        self.config.public_key_size()
+        match self.mode() {
+            CeremonyMode::Full => {
+                3 * self.g2_uncompressed_byte_size() + // tau, alpha, and beta in g2
+                6 * self.g1_uncompressed_byte_size() // (s1, s1*tau), (s2, s2*alpha), (s3, s3*beta) in g1
+            }
+            CeremonyMode::TauOnly => {
+                self.g2_uncompressed_byte_size() + // tau in g2
+                2 * self.g1_uncompressed_byte_size() // (s1, s1*tau) in g1
+            }
+        }
     }
 
     pub fn contribution_byte_size(&self) -> usize {
-        self.config.contribution_byte_size()
+        (self.tau_powers_g1_length() * self.g1_compressed_byte_size()) + // g1 tau powers
+        (self.tau_powers_length() * self.g2_compressed_byte_size()) + // g2 tau powers
+        (self.alpha_beta_length() * self.g1_compressed_byte_size()) + // alpha tau powers
+        (self.alpha_beta_length() * self.g1_compressed_byte_size()) + // beta tau powers
+        self.beta_g2_length() * self.g2_compressed_byte_size() + // beta in g2
+        self.hash_size() + // blake2b hash of input accumulator
+        self.public_key_size() // public key
+    }
+
+    /// Size of a file of `role` without a header
+    pub fn file_byte_size(&self, role: TranscriptFile, compression: UseCompression) -> usize {
+        match (role, compression) {
+            (TranscriptFile::Challenge, UseCompression::No) => self.accumulator_byte_size(),
+            (TranscriptFile::Challenge, UseCompression::Yes) => {
+                self.contribution_byte_size() - self.public_key_size()
+            }
+            (TranscriptFile::Response, UseCompression::No) => {
+                self.accumulator_byte_size() + self.public_key_size()
+            }
+            (TranscriptFile::Response, UseCompression::Yes) => self.contribution_byte_size(),
+        }
     }
 
     pub fn hash_size(&self) -> usize {
         64 // Blake2b hash size
     }
 
+    /// Whether the private key is escrowed so that a contribution can be resumed after a restart
+    pub fn key_escrow(&self) -> bool {
+        self.config.ceremony.key_escrow
+    }
+
+    /// Whether new challenge and response files start with a `FileHeader`
+    pub fn file_header(&self) -> bool {
+        self.config.ceremony.file_header
+    }
+
+    pub fn max_memory(&self) -> Option<u64> {
+        self.config.ceremony.max_memory
+    }
+
+    /// Number of powers processed at once below TAU_POWERS_LENGTH, where every element type is present
     pub fn empirical_batch_size(&self) -> usize {
-        1 << 21
+        self.batch_size(self.bytes_per_power())
+    }
+
+    /// Number of powers processed at once from TAU_POWERS_LENGTH on, where only TauG1 is left
+    pub fn tau_g1_batch_size(&self) -> usize {
+        self.batch_size(self.bytes_per_tau_g1_power())
+    }
+
+    fn batch_size(&self, bytes_per_power: usize) -> usize {
+        match (self.empirical_batch_size, self.max_memory()) {
+            (Some(batch_size), _) => batch_size,
+            (None, Some(max_memory)) => {
+                // leave room for the extra power verification reads at the end of a chunk
+                let batch_size = (max_memory / bytes_per_power as u64)
+                    .saturating_sub(1)
+                    .max(1);
+                batch_size.min(self.tau_powers_g1_length() as u64) as usize
+            }
+            (None, None) => DEFAULT_EMPIRICAL_BATCH_SIZE,
+        }
+    }
+
+    /// Heap memory held per power below TAU_POWERS_LENGTH: the points of two accumulators
+    /// during verification, the encoded and decoded points of the element type being read,
+    /// and the exponent and projective point of the element type being raised to it
+    pub fn bytes_per_power(&self) -> usize {
+        let sizes = &self.point_sizes;
+        let accumulator = match self.mode() {
+            CeremonyMode::Full => 3 * sizes.g1_affine + sizes.g2_affine,
+            CeremonyMode::TauOnly => sizes.g1_affine + sizes.g2_affine,
+        };
+        let reading =
+            (sizes.g1_uncompressed + sizes.g1_affine).max(sizes.g2_uncompressed + sizes.g2_affine);
+        let exponentiation = sizes.scalar + sizes.g1_projective.max(sizes.g2_projective);
+
+        2 * accumulator + reading + exponentiation
+    }
+
+    /// Heap memory held per power from TAU_POWERS_LENGTH on, where only TauG1 is processed
+    pub fn bytes_per_tau_g1_power(&self) -> usize {
+        let sizes = &self.point_sizes;
+
+        let accumulator = sizes.g1_affine;
+        let reading = sizes.g1_uncompressed + sizes.g1_affine;
+        let exponentiation = sizes.scalar + sizes.g1_projective;
+
+        2 * accumulator + reading + exponentiation
+    }
+
+    /// Peak heap memory of transform, verify and decompress with the current chunk sizes.
+    /// Memory maps of the ceremony files are not included, as the page cache can evict them.
+    pub fn estimated_peak_memory(&self) -> u64 {
+        // verification reads one extra power to overlap neighbouring chunks
+        let powers = self.empirical_batch_size().min(self.tau_powers_length()) + 1;
+        let tau_g1_powers = self
+            .tau_g1_batch_size()
+            .min(self.tau_powers_g1_length() - self.tau_powers_length())
+            + 1;
+
+        let peak =
+            (powers * self.bytes_per_power()).max(tau_g1_powers * self.bytes_per_tau_g1_power());
+
+        peak as u64
     }
 
     pub fn use_compression(&self) -> UseCompression {
@@ -82,16 +268,101 @@ impl RuntimeCeremonyParameters {
         self.config.ceremony.check_correctness.into()
     }
 
+    /// Random beacon of the final contribution
+    pub fn beacon(&self) -> &BeaconParameters {
+        &self.config.beacon
+    }
+
     // Print configuration summary
     pub fn print_info(&self) {
         println!("Ceremony Configuration:");
-        println!("  Required Power: {} (2^{} = {} powers)", 
-                 self.required_power(), 
-                 self.required_power(), 
-                 self.tau_powers_length());
+        println!(
+            "  Required Power: {} (2^{} = {} powers)",
+            self.required_power(),
+            self.required_power(),
+            self.tau_powers_length()
+        );
         println!("  Curve: {}", self.config.curve.name);
+        println!("  Mode: {}", self.mode());
         println!("  Compression: {:?}", self.config.ceremony.compression);
         println!("  Accumulator size: {} bytes", self.accumulator_byte_size());
-        println!("  Contribution size: {} bytes", self.contribution_byte_size());
+        println!(
+            "  Contribution size: {} bytes",
+            self.contribution_byte_size()
+        );
+        match self.max_memory() {
+            Some(max_memory) => println!("  Max memory: {} MiB", max_memory >> 20),
+            None => println!("  Max memory: not set"),
+        }
+        println!(
+            "  Batch size: {} powers ({} for the rest of TauG1)",
+            self.empirical_batch_size(),
+            self.tau_g1_batch_size()
+        );
+        println!(
+            "  Estimated peak memory: {} MiB",
+            self.estimated_peak_memory() >> 20
+        );
+        if let Some(max_memory) = self.max_memory() {
+            if self.estimated_peak_memory() > max_memory {
+                println!("  WARNING: estimated peak memory exceeds the memory budget");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_size_follows_memory_budget() {
+        let mut config = CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 20;
+        let parameters = RuntimeCeremonyParameters::new(config);
+        assert_eq!(
+            parameters.empirical_batch_size(),
+            DEFAULT_EMPIRICAL_BATCH_SIZE
+        );
+
+        let small = parameters.clone().with_max_memory(16 << 20);
+        let large = parameters.clone().with_max_memory(256 << 20);
+        assert!(small.empirical_batch_size() < large.empirical_batch_size());
+        assert!(small.empirical_batch_size() < small.tau_g1_batch_size());
+        assert!(small.estimated_peak_memory() <= 16 << 20);
+        assert!(large.estimated_peak_memory() <= 256 << 20);
+
+        let tiny = parameters.clone().with_max_memory(1);
+        assert_eq!(tiny.empirical_batch_size(), 1);
+        assert_eq!(tiny.tau_g1_batch_size(), 1);
+
+        let empty = parameters.with_empirical_batch_size(0);
+        assert_eq!(empty.empirical_batch_size(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tau_only_files_leave_out_alpha_and_beta() {
+        let mut config = CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 10;
+        let full = RuntimeCeremonyParameters::new(config);
+        let tau_only = full.clone().with_mode(CeremonyMode::TauOnly);
+
+        let g1 = full.g1_uncompressed_byte_size();
+        let g2 = full.g2_uncompressed_byte_size();
+        assert_eq!(
+            full.accumulator_byte_size() - tau_only.accumulator_byte_size(),
+            2 * full.tau_powers_length() * g1 + g2
+        );
+        assert_eq!(
+            full.public_key_size() - tau_only.public_key_size(),
+            2 * g2 + 4 * g1
+        );
+        assert_eq!(tau_only.alpha_beta_length(), 0);
+        assert_eq!(tau_only.beta_g2_length(), 0);
+
+        // The same memory budget holds more powers when there is no alpha and beta
+        let full = full.with_max_memory(256 << 10);
+        let tau_only = tau_only.with_max_memory(256 << 10);
+        assert!(tau_only.empirical_batch_size() > full.empirical_batch_size());
+    }
+}
@@ -0,0 +1,362 @@
+use bellman::pairing::Engine;
+use generic_array::GenericArray;
+use memmap::{Mmap, MmapMut};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use typenum::consts::U64;
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::config::CeremonyMode;
+use super::curve::CurveKind;
+use super::parameters::{CeremonyError, CheckForCorrectness, TranscriptFile, UseCompression};
+use super::progress::ProgressSink;
+use super::runtime_parameters::RuntimeCeremonyParameters;
+
+/// File of the tau-only SRS, for KZG and PLONK
+pub const TAU_ONLY_SRS: &str = "tau_only_srs";
+/// File of the full SRS, for Groth16
+pub const GROTH16_SRS: &str = "groth16_srs";
+/// File of the manifest of both
+pub const SPLIT_MANIFEST: &str = "srs_manifest.json";
+
+/// Final hash of the transcript the final accumulator in `input_map` ends: the hash of the last
+/// response, which a challenge derived from it starts with.
+pub fn final_hash<E: Engine>(
+    input_map: &Mmap,
+    role: TranscriptFile,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<GenericArray<u8, U64>, CeremonyError> {
+    match role {
+        TranscriptFile::Response => {
+            Ok(BatchedAccumulator::<E>::calculate_hash(input_map, progress))
+        }
+        TranscriptFile::Challenge => {
+            let hash =
+                input_map
+                    .get(0..parameters.hash_size())
+                    .ok_or(CeremonyError::SizeMismatch {
+                        expected: parameters.hash_size(),
+                        actual: input_map.len(),
+                    })?;
+            Ok(GenericArray::clone_from_slice(hash))
+        }
+    }
+}
+
+/// Writes the full accumulator in `input_map` as two uncompressed challenges that start with
+/// `final_hash`: the powers of tau alone to `tau_only_map`, and every point, alpha and beta
+/// included, to `groth16_map`. Both have to be as long as the challenge of their mode. The powers
+/// of tau come first in either mode, so they are at the same place in both files.
+#[allow(clippy::too_many_arguments)]
+pub fn split_final<E: Engine>(
+    input_map: &Mmap,
+    input_compression: UseCompression,
+    check_input_for_correctness: CheckForCorrectness,
+    final_hash: &[u8],
+    tau_only_map: &mut MmapMut,
+    groth16_map: &mut MmapMut,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    if parameters.mode() != CeremonyMode::Full {
+        return Err(CeremonyError::InvalidParameters(
+            "a tau-only accumulator has no alpha and beta to split off".to_string(),
+        ));
+    }
+    let tau_only_parameters = parameters.clone().with_mode(CeremonyMode::TauOnly);
+
+    for (output_map, parameters) in [
+        (tau_only_map, &tau_only_parameters),
+        (groth16_map, parameters),
+    ] {
+        let expected = parameters.file_byte_size(TranscriptFile::Challenge, UseCompression::No);
+        if output_map.len() != expected {
+            return Err(CeremonyError::SizeMismatch {
+                expected,
+                actual: output_map.len(),
+            });
+        }
+        output_map[0..parameters.hash_size()].copy_from_slice(final_hash);
+
+        BatchedAccumulator::<E>::convert(
+            input_map,
+            output_map,
+            input_compression,
+            UseCompression::No,
+            check_input_for_correctness,
+            parameters,
+            progress,
+        )?;
+        output_map.flush()?;
+    }
+
+    Ok(())
+}
+
+/// An SRS file split off the final accumulator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SrsArtifact {
+    /// Name of the file, next to the manifest
+    pub file: String,
+    /// Hex encoded BLAKE2b hash of the file
+    pub hash: String,
+}
+
+/// Manifest written next to the SRS files split off a final accumulator, linking both to the
+/// final hash of the transcript. Each file starts with that hash, in place of the hash of a
+/// previous response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitManifest {
+    pub curve: CurveKind,
+    pub power: usize,
+    /// Hex encoded hash of the last response of the transcript
+    pub final_hash: String,
+    /// Powers of tau in G1 and G2, for KZG and PLONK
+    pub tau_only: SrsArtifact,
+    /// Powers of tau with alpha and beta, for Groth16
+    pub groth16: SrsArtifact,
+}
+
+impl SplitManifest {
+    pub fn new(
+        curve: CurveKind,
+        power: usize,
+        final_hash: &[u8],
+        tau_only_hash: &[u8],
+        groth16_hash: &[u8],
+    ) -> Self {
+        Self {
+            curve,
+            power,
+            final_hash: hex::encode(final_hash),
+            tau_only: SrsArtifact {
+                file: TAU_ONLY_SRS.to_string(),
+                hash: hex::encode(tau_only_hash),
+            },
+            groth16: SrsArtifact {
+                file: GROTH16_SRS.to_string(),
+                hash: hex::encode(groth16_hash),
+            },
+        }
+    }
+
+    /// Checks that `tau_only` and `groth16` are the files of this manifest and start with the
+    /// final hash of the transcript
+    pub fn verify<E: Engine>(
+        &self,
+        tau_only: &Mmap,
+        groth16: &Mmap,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<(), CeremonyError> {
+        let final_hash = hex::decode(&self.final_hash).map_err(|e| {
+            CeremonyError::InvalidParameters(format!("malformed final hash: {}", e))
+        })?;
+
+        for (artifact, map) in &[(&self.tau_only, tau_only), (&self.groth16, groth16)] {
+            BatchedAccumulator::<E>::check_hash_chain(map, &final_hash).map_err(|_| {
+                CeremonyError::SplitMismatch(format!(
+                    "`{}` does not start with the final hash of the transcript",
+                    artifact.file
+                ))
+            })?;
+
+            let hash = BatchedAccumulator::<E>::calculate_hash(map, progress);
+            if hex::encode(hash.as_slice()) != artifact.hash {
+                return Err(CeremonyError::SplitMismatch(format!(
+                    "`{}` is not the file in the manifest",
+                    artifact.file
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Location of the manifest of the SRS files in `directory`
+    pub fn path_for<P: AsRef<Path>>(directory: P) -> PathBuf {
+        directory.as_ref().join(SPLIT_MANIFEST)
+    }
+
+    /// Reads the manifest at `path`, if there is one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, CeremonyError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_reader(file)
+            .map(Some)
+            .map_err(|e| CeremonyError::InvalidParameters(format!("malformed SRS manifest: {}", e)))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CeremonyError> {
+        let mut file = File::create(path)?;
+        serde_json::to_writer_pretty(&mut file, self)
+            .map_err(|e| CeremonyError::InvalidParameters(e.to_string()))?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::Bn256;
+    use std::io::Write;
+
+    use crate::config::CeremonyConfig;
+    use crate::progress::NoProgress;
+    use crate::test_utils::{bn254_parameters, fresh_challenge};
+
+    fn map(bytes: &[u8]) -> Mmap {
+        let mut map = MmapMut::map_anon(bytes.len()).unwrap();
+        (&mut map[0..]).write_all(bytes).unwrap();
+        map.make_read_only().unwrap()
+    }
+
+    #[test]
+    fn test_manifest_links_both_files_to_final_hash() {
+        let final_hash = [5u8; 64];
+        let mut tau_only = final_hash.to_vec();
+        tau_only.extend_from_slice(&[7u8; 64]);
+        let tau_only = map(&tau_only);
+        let mut groth16 = final_hash.to_vec();
+        groth16.extend_from_slice(&[7u8; 128]);
+        let groth16 = map(&groth16);
+
+        let manifest = SplitManifest::new(
+            CurveKind::Bn254,
+            3,
+            &final_hash,
+            BatchedAccumulator::<Bn256>::calculate_hash(&tau_only, &mut NoProgress).as_slice(),
+            BatchedAccumulator::<Bn256>::calculate_hash(&groth16, &mut NoProgress).as_slice(),
+        );
+        manifest
+            .verify::<Bn256>(&tau_only, &groth16, &mut NoProgress)
+            .unwrap();
+
+        // The files swapped, or a file that does not start with the final hash
+        assert!(manifest
+            .verify::<Bn256>(&groth16, &tau_only, &mut NoProgress)
+            .is_err());
+        let unlinked = map(&[7u8; 192]);
+        assert!(manifest
+            .verify::<Bn256>(&tau_only, &unlinked, &mut NoProgress)
+            .is_err());
+    }
+
+    #[test]
+    fn test_final_hash_of_challenge_is_its_prefix() {
+        let mut config = CeremonyConfig::default_bn254();
+        config.ceremony.required_power = 2;
+        let parameters = RuntimeCeremonyParameters::new(config);
+
+        let mut challenge = vec![3u8; 64];
+        challenge.extend_from_slice(&[7u8; 64]);
+        let challenge = map(&challenge);
+        let hash = final_hash::<Bn256>(
+            &challenge,
+            TranscriptFile::Challenge,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert_eq!(hash.as_slice(), &[3u8; 64][..]);
+
+        let hash = final_hash::<Bn256>(
+            &challenge,
+            TranscriptFile::Response,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert_eq!(
+            hash,
+            BatchedAccumulator::<Bn256>::calculate_hash(&challenge, &mut NoProgress)
+        );
+    }
+
+    #[test]
+    fn test_final_accumulator_is_split_into_both_srs() {
+        let parameters = bn254_parameters(2);
+        let tau_only_parameters = parameters.clone().with_mode(CeremonyMode::TauOnly);
+        let challenge = fresh_challenge(&parameters);
+        let hash = final_hash::<Bn256>(
+            &challenge,
+            TranscriptFile::Challenge,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+
+        let srs_map = |parameters: &RuntimeCeremonyParameters| {
+            MmapMut::map_anon(
+                parameters.file_byte_size(TranscriptFile::Challenge, UseCompression::No),
+            )
+            .unwrap()
+        };
+        let mut tau_only = srs_map(&tau_only_parameters);
+        let mut groth16 = srs_map(&parameters);
+        split_final::<Bn256>(
+            &challenge,
+            UseCompression::No,
+            CheckForCorrectness::Yes,
+            hash.as_slice(),
+            &mut tau_only,
+            &mut groth16,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        let tau_only = tau_only.make_read_only().unwrap();
+        let groth16 = groth16.make_read_only().unwrap();
+
+        // The powers of tau come first in either file, so the tau-only SRS is a prefix of the
+        // Groth16 one
+        assert!(tau_only.len() < groth16.len());
+        assert_eq!(&groth16[0..tau_only.len()], &tau_only[..]);
+
+        // Each file decodes under the parameters of its mode
+        let read = |map: &Mmap, parameters: &RuntimeCeremonyParameters| {
+            let mut accumulator = BatchedAccumulator::<Bn256>::empty(parameters);
+            accumulator
+                .read_chunk(
+                    0,
+                    parameters.tau_powers_g1_length(),
+                    UseCompression::No,
+                    CheckForCorrectness::Yes,
+                    map,
+                )
+                .unwrap();
+            accumulator
+        };
+        let tau_only = read(&tau_only, &tau_only_parameters);
+        let groth16 = read(&groth16, &parameters);
+        assert_eq!(tau_only.tau_powers_g1, groth16.tau_powers_g1);
+        assert_eq!(tau_only.tau_powers_g2, groth16.tau_powers_g2);
+        assert_eq!(
+            groth16.alpha_tau_powers_g1.len(),
+            parameters.tau_powers_length()
+        );
+
+        // A tau-only accumulator has nothing to split off
+        let mut tau_only = srs_map(&tau_only_parameters);
+        let mut groth16 = srs_map(&parameters);
+        assert!(split_final::<Bn256>(
+            &challenge,
+            UseCompression::No,
+            CheckForCorrectness::Yes,
+            hash.as_slice(),
+            &mut tau_only,
+            &mut groth16,
+            &tau_only_parameters,
+            &mut NoProgress,
+        )
+        .is_err());
+    }
+}
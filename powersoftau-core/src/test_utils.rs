@@ -0,0 +1,33 @@
+use bellman::pairing::bn256::Bn256;
+use memmap::{Mmap, MmapMut};
+use std::io::Write;
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::config::CeremonyConfig;
+use super::parameters::UseCompression;
+use super::progress::NoProgress;
+use super::runtime_parameters::RuntimeCeremonyParameters;
+use super::utils::blank_hash;
+
+/// Parameters of a ceremony on BN254 for 2^`required_power` powers of tau
+pub(crate) fn bn254_parameters(required_power: usize) -> RuntimeCeremonyParameters {
+    let mut config = CeremonyConfig::default_bn254();
+    config.ceremony.required_power = required_power;
+    RuntimeCeremonyParameters::new(config)
+}
+
+/// The uncompressed challenge a ceremony with `parameters` starts from, in memory
+pub(crate) fn fresh_challenge(parameters: &RuntimeCeremonyParameters) -> Mmap {
+    let mut challenge = MmapMut::map_anon(parameters.accumulator_byte_size()).unwrap();
+    (&mut challenge[0..])
+        .write_all(blank_hash().as_slice())
+        .unwrap();
+    BatchedAccumulator::<Bn256>::generate_initial(
+        &mut challenge,
+        UseCompression::No,
+        parameters,
+        &mut NoProgress,
+    )
+    .unwrap();
+    challenge.make_read_only().unwrap()
+}
@@ -0,0 +1,531 @@
+use bellman::pairing::Engine;
+use blake2::{Blake2b, Digest};
+use generic_array::GenericArray;
+use memmap::{Mmap, MmapMut, MmapOptions};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use typenum::consts::U64;
+
+use super::batched_accumulator::BatchedAccumulator;
+use super::beacon::verify_beacon_key;
+use super::keypair::PublicKey;
+use super::layout::detect_layout;
+use super::parameters::{CeremonyError, CheckForCorrectness, TranscriptFile, UseCompression};
+use super::progress::ProgressSink;
+use super::runtime_parameters::RuntimeCeremonyParameters;
+use super::utils::{blank_hash, ScratchFile};
+
+/// Outcome of the verification of a single response of a transcript
+#[derive(Debug)]
+pub struct ContributionReport {
+    pub path: PathBuf,
+    /// Hash of the response, which the next contribution builds on. Missing if the file could
+    /// not be read.
+    pub response_hash: Option<GenericArray<u8, U64>>,
+    pub result: Result<(), CeremonyError>,
+}
+
+/// Outcome of the verification of a whole transcript, one report per response in order
+#[derive(Debug, Default)]
+pub struct TranscriptReport {
+    pub contributions: Vec<ContributionReport>,
+}
+
+impl TranscriptReport {
+    /// A transcript passes if it has at least one response and every response was verified
+    pub fn is_valid(&self) -> bool {
+        !self.contributions.is_empty()
+            && self
+                .contributions
+                .iter()
+                .all(|contribution| contribution.result.is_ok())
+    }
+}
+
+/// A response of the transcript, mapped from disk
+struct Response {
+    map: Mmap,
+    compression: UseCompression,
+    hash: GenericArray<u8, U64>,
+}
+
+/// Verifies an ordered list of response files, starting from the fresh accumulator described by
+/// `parameters`. Each response has to build on the challenge derived from the one before it, its
+/// public key has to prove knowledge of the contributed secrets, and its powers have to be
+/// consistent with them.
+///
+/// The challenges of the transcript are not needed: they are derived from the responses, with
+/// either point encoding, into a scratch file at `scratch_path` that is removed when done.
+/// A failed contribution does not stop the verification of the ones after it.
+///
+/// With an `iterated_beacon`, the last response is the contribution of the beacon, which hashes
+/// to it: its public key also has to be the one the beacon derives.
+pub fn verify_transcript<E: Engine, P: AsRef<Path>>(
+    responses: &[P],
+    iterated_beacon: Option<&[u8; 32]>,
+    scratch_path: &Path,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<TranscriptReport, CeremonyError> {
+    // The scratch file is removed on every path out of here, even if a contribution fails
+    // half-way through deriving its challenge
+    let _scratch = ScratchFile(scratch_path);
+    let mut report = TranscriptReport::default();
+    let mut previous: Option<Response> = None;
+
+    for (index, path) in responses.iter().enumerate() {
+        let path = path.as_ref();
+        let response = match read_response::<E>(path, parameters, progress) {
+            Ok(response) => response,
+            Err(e) => {
+                report.contributions.push(ContributionReport {
+                    path: path.to_path_buf(),
+                    response_hash: None,
+                    result: Err(e),
+                });
+                previous = None;
+                continue;
+            }
+        };
+
+        let beacon = match index + 1 == responses.len() {
+            true => iterated_beacon,
+            false => None,
+        };
+        let result = match (index, &previous) {
+            (0, _) => {
+                verify_response::<E>(&response, None, beacon, scratch_path, parameters, progress)
+            }
+            (_, Some(previous)) => verify_response::<E>(
+                &response,
+                Some(previous),
+                beacon,
+                scratch_path,
+                parameters,
+                progress,
+            ),
+            (_, None) => Err(CeremonyError::BrokenTranscript(format!(
+                "the response before `{}` could not be read",
+                path.display()
+            ))),
+        };
+
+        report.contributions.push(ContributionReport {
+            path: path.to_path_buf(),
+            response_hash: Some(response.hash),
+            result,
+        });
+        previous = Some(response);
+    }
+
+    Ok(report)
+}
+
+/// Checks that the challenge that follows the response at `path` hashes to `expected`, the hash
+/// a later file of the transcript starts with. The response does not tell how the points of that
+/// challenge are encoded, so both encodings are tried, the one of the response first. A challenge
+/// in the other encoding is written to `scratch_path`, which is removed when done.
+pub fn check_next_challenge<E: Engine>(
+    path: &Path,
+    expected: &[u8],
+    scratch_path: &Path,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    let _scratch = ScratchFile(scratch_path);
+    let response = read_response::<E>(path, parameters, progress)?;
+
+    let other = match response.compression {
+        UseCompression::Yes => UseCompression::No,
+        UseCompression::No => UseCompression::Yes,
+    };
+    for &compression in &[response.compression, other] {
+        let (challenge_hash, _) = derive_challenge::<E>(
+            Some(&response),
+            compression,
+            scratch_path,
+            parameters,
+            progress,
+        )?;
+        if challenge_hash.as_slice() == expected {
+            return Ok(());
+        }
+    }
+
+    Err(CeremonyError::HashChainMismatch)
+}
+
+fn read_response<E: Engine>(
+    path: &Path,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<Response, CeremonyError> {
+    let layout = detect_layout(path, parameters.curve())?;
+    layout.ensure_role(TranscriptFile::Response)?;
+    if layout.required_power != parameters.required_power() {
+        return Err(CeremonyError::UnknownLayout(format!(
+            "expected a response for 2^{} powers, but found {}",
+            parameters.required_power(),
+            layout
+        )));
+    }
+    if layout.mode != parameters.mode() {
+        return Err(CeremonyError::UnknownLayout(format!(
+            "expected a {} response, but found {}",
+            parameters.mode(),
+            layout
+        )));
+    }
+
+    let file = File::open(path)?;
+    let map = unsafe { MmapOptions::new().offset(layout.body_offset()).map(&file)? };
+    let hash = BatchedAccumulator::<E>::calculate_hash(&map, progress);
+
+    Ok(Response {
+        map,
+        compression: layout.compression,
+        hash,
+    })
+}
+
+/// Verifies `response` against the challenge derived from `previous`, or against the fresh
+/// accumulator for the first response, and against the beacon that hashes to `iterated_beacon`
+/// if it is the contribution of the beacon
+fn verify_response<E: Engine>(
+    response: &Response,
+    previous: Option<&Response>,
+    iterated_beacon: Option<&[u8; 32]>,
+    scratch_path: &Path,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), CeremonyError> {
+    let public_key = PublicKey::<E>::read(&response.map, response.compression, parameters)?;
+
+    // The response tells the hash of its challenge, but not how its points were encoded,
+    // so both encodings are tried, the one of the configuration first
+    let preferred = parameters.use_compression();
+    let other = match preferred {
+        UseCompression::Yes => UseCompression::No,
+        UseCompression::No => UseCompression::Yes,
+    };
+    for &compression in &[preferred, other] {
+        let (challenge_hash, scratch) =
+            derive_challenge::<E>(previous, compression, scratch_path, parameters, progress)?;
+        if BatchedAccumulator::<E>::check_hash_chain(&response.map, challenge_hash.as_slice())
+            .is_err()
+        {
+            continue;
+        }
+
+        // A challenge in the encoding of the previous response has the same points at the same
+        // offsets, so the previous response is read as it is
+        let challenge = match (&scratch, previous) {
+            (Some(scratch), _) => scratch,
+            (None, Some(previous)) => &previous.map,
+            (None, None) => unreachable!("the fresh accumulator is always generated"),
+        };
+
+        if let Some(iterated_beacon) = iterated_beacon {
+            verify_beacon_key::<E>(
+                &response.map,
+                response.compression,
+                challenge_hash.as_slice(),
+                iterated_beacon,
+                parameters,
+            )?;
+        }
+
+        return BatchedAccumulator::<E>::verify_transformation(
+            challenge,
+            &response.map,
+            &public_key,
+            challenge_hash.as_slice(),
+            compression,
+            response.compression,
+            CheckForCorrectness::No,
+            CheckForCorrectness::Yes,
+            parameters,
+            progress,
+        );
+    }
+
+    Err(CeremonyError::HashChainMismatch)
+}
+
+/// Hash of the challenge that follows `previous` with `compression`, along with the challenge
+/// itself when it had to be written to `scratch_path`
+fn derive_challenge<E: Engine>(
+    previous: Option<&Response>,
+    compression: UseCompression,
+    scratch_path: &Path,
+    parameters: &RuntimeCeremonyParameters,
+    progress: &mut dyn ProgressSink,
+) -> Result<(GenericArray<u8, U64>, Option<Mmap>), CeremonyError> {
+    if let Some(previous) = previous {
+        if previous.compression == compression {
+            let points = parameters.hash_size()
+                ..parameters.file_byte_size(TranscriptFile::Challenge, compression);
+            let mut hasher = Blake2b::default();
+            Digest::update(&mut hasher, previous.hash.as_slice());
+            Digest::update(&mut hasher, &previous.map[points.clone()]);
+            progress.bytes_hashed(points.end as u64, points.end as u64);
+
+            return Ok((hasher.finalize(), None));
+        }
+    }
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(scratch_path)?;
+    file.set_len(parameters.file_byte_size(TranscriptFile::Challenge, compression) as u64)?;
+    let mut scratch = unsafe { MmapMut::map_mut(&file)? };
+
+    match previous {
+        Some(previous) => {
+            (&mut scratch[0..]).write_all(previous.hash.as_slice())?;
+            BatchedAccumulator::<E>::convert(
+                &previous.map,
+                &mut scratch,
+                previous.compression,
+                compression,
+                CheckForCorrectness::No,
+                parameters,
+                progress,
+            )?;
+        }
+        None => {
+            (&mut scratch[0..]).write_all(blank_hash().as_slice())?;
+            BatchedAccumulator::<E>::generate_initial(
+                &mut scratch,
+                compression,
+                parameters,
+                progress,
+            )?;
+        }
+    }
+
+    let scratch = scratch.make_read_only()?;
+    let hash = BatchedAccumulator::<E>::calculate_hash(&scratch, progress);
+
+    Ok((hash, Some(scratch)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::pairing::bn256::Bn256;
+    use rand::chacha::ChaChaRng;
+    use rand::SeedableRng;
+    use std::env;
+
+    use crate::beacon::beacon_rng;
+    use crate::config::CeremonyMode;
+    use crate::keypair::keypair;
+    use crate::progress::NoProgress;
+    use crate::test_utils::{bn254_parameters, fresh_challenge};
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("powersoftau-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Writes a compressed response on top of `challenge` to `path`
+    fn contribute(
+        challenge: &Mmap,
+        challenge_compression: UseCompression,
+        mut rng: ChaChaRng,
+        path: &Path,
+        parameters: &RuntimeCeremonyParameters,
+    ) -> Mmap {
+        let challenge_hash =
+            BatchedAccumulator::<Bn256>::calculate_hash(challenge, &mut NoProgress);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.set_len(parameters.contribution_byte_size() as u64)
+            .unwrap();
+        let mut response = unsafe { MmapMut::map_mut(&file).unwrap() };
+        (&mut response[0..])
+            .write_all(challenge_hash.as_slice())
+            .unwrap();
+
+        let (pubkey, privkey) = keypair::<_, Bn256>(&mut rng, challenge_hash.as_ref()).unwrap();
+        BatchedAccumulator::<Bn256>::transform(
+            challenge,
+            &mut response,
+            challenge_compression,
+            UseCompression::Yes,
+            CheckForCorrectness::Yes,
+            &privkey,
+            parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        pubkey
+            .write(&mut response, UseCompression::Yes, parameters)
+            .unwrap();
+
+        response.make_read_only().unwrap()
+    }
+
+    /// Derives the uncompressed challenge that follows `response`
+    fn new_challenge(response: &Mmap, parameters: &RuntimeCeremonyParameters) -> Mmap {
+        let response_hash = BatchedAccumulator::<Bn256>::calculate_hash(response, &mut NoProgress);
+        let mut challenge = MmapMut::map_anon(parameters.accumulator_byte_size()).unwrap();
+        (&mut challenge[0..])
+            .write_all(response_hash.as_slice())
+            .unwrap();
+        BatchedAccumulator::<Bn256>::decompress(
+            response,
+            &mut challenge,
+            CheckForCorrectness::Yes,
+            parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        challenge.make_read_only().unwrap()
+    }
+
+    #[test]
+    fn test_transcript_is_verified_from_responses_alone() {
+        let parameters = bn254_parameters(2);
+        let challenge = fresh_challenge(&parameters);
+
+        // The second contribution is computed on the compressed challenge, which holds the
+        // points of the first response as they are
+        let first_path = temporary_path("transcript-response-1");
+        let first = contribute(
+            &challenge,
+            UseCompression::No,
+            ChaChaRng::from_seed(&[1, 2, 3, 4]),
+            &first_path,
+            &parameters,
+        );
+        let second_path = temporary_path("transcript-response-2");
+        let second = contribute(
+            &first,
+            UseCompression::Yes,
+            ChaChaRng::from_seed(&[2, 2, 3, 4]),
+            &second_path,
+            &parameters,
+        );
+        // The last contribution is the one of a beacon
+        let beacon = [7u8; 32];
+        let third_path = temporary_path("transcript-response-3");
+        let third = contribute(
+            &new_challenge(&second, &parameters),
+            UseCompression::No,
+            beacon_rng(&beacon),
+            &third_path,
+            &parameters,
+        );
+
+        let scratch_path = temporary_path("transcript-scratch");
+        let responses = [&first_path, &second_path, &third_path];
+        let report = verify_transcript::<Bn256, _>(
+            &responses,
+            None,
+            &scratch_path,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.contributions.len(), 3);
+        assert!(!scratch_path.exists());
+
+        // The public key of the last response is derived from its beacon, and from no other
+        let report = verify_transcript::<Bn256, _>(
+            &responses,
+            Some(&beacon),
+            &scratch_path,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert!(report.is_valid());
+        let report = verify_transcript::<Bn256, _>(
+            &responses,
+            Some(&[8u8; 32]),
+            &scratch_path,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert!(!report.is_valid());
+        assert!(report.contributions[1].result.is_ok());
+        match report.contributions[2].result {
+            Err(CeremonyError::BeaconMismatch(_)) => {}
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        // Leaving out a response breaks the hash chain of the next one
+        let report = verify_transcript::<Bn256, _>(
+            &[&first_path, &third_path],
+            None,
+            &scratch_path,
+            &parameters,
+            &mut NoProgress,
+        )
+        .unwrap();
+        assert!(!report.is_valid());
+        assert!(report.contributions[0].result.is_ok());
+        match report.contributions[1].result {
+            Err(CeremonyError::HashChainMismatch) => {}
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        // The challenge after a response is the one the next response builds on, in either
+        // encoding
+        let hash_size = parameters.hash_size();
+        for (path, next) in [(&first_path, &second), (&second_path, &third)].iter() {
+            check_next_challenge::<Bn256>(
+                path,
+                &next[0..hash_size],
+                &scratch_path,
+                &parameters,
+                &mut NoProgress,
+            )
+            .unwrap();
+        }
+        match check_next_challenge::<Bn256>(
+            &first_path,
+            &third[0..hash_size],
+            &scratch_path,
+            &parameters,
+            &mut NoProgress,
+        ) {
+            Err(CeremonyError::HashChainMismatch) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(!scratch_path.exists());
+
+        // The responses of a full ceremony are no part of a tau-only transcript
+        let tau_only = parameters.clone().with_mode(CeremonyMode::TauOnly);
+        let report = verify_transcript::<Bn256, _>(
+            &responses,
+            None,
+            &scratch_path,
+            &tau_only,
+            &mut NoProgress,
+        )
+        .unwrap();
+        match report.contributions[0].result {
+            Err(CeremonyError::UnknownLayout(_)) => {}
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        for path in &responses {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
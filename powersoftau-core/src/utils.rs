@@ -16,51 +16,63 @@ use generic_array::GenericArray;
 use rand::chacha::ChaChaRng;
 use rand::{Rand, Rng, SeedableRng};
 use std::fmt;
+use std::fs;
 use std::io::{self, Read, Write};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use typenum::consts::U64;
 
 use super::parameters::*;
 
-/// Hashes to G2 using the first 32 bytes of `digest`. Panics if `digest` is less
+/// Hashes to G2 using the first 32 bytes of `digest`. Fails if `digest` is less
 /// than 32 bytes.
-pub fn hash_to_g2<E: Engine>(mut digest: &[u8]) -> E::G2 {
-    assert!(digest.len() >= 32);
+pub fn hash_to_g2<E: Engine>(mut digest: &[u8]) -> Result<E::G2, CeremonyError> {
+    if digest.len() < 32 {
+        return Err(CeremonyError::SizeMismatch {
+            expected: 32,
+            actual: digest.len(),
+        });
+    }
 
     let mut seed = Vec::with_capacity(8);
 
     for _ in 0..8 {
-        seed.push(
-            digest
-                .read_u32::<BigEndian>()
-                .expect("assertion above guarantees this to work"),
-        );
+        seed.push(digest.read_u32::<BigEndian>()?);
     }
 
-    ChaChaRng::from_seed(&seed).gen()
+    Ok(ChaChaRng::from_seed(&seed).gen())
 }
 
 #[test]
 fn test_hash_to_g2() {
-    assert!(
+    assert_eq!(
         hash_to_g2::<Bn256>(&[
             1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
             25, 26, 27, 28, 29, 30, 31, 32, 33
-        ]) == hash_to_g2::<Bn256>(&[
+        ])
+        .unwrap(),
+        hash_to_g2::<Bn256>(&[
             1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
             25, 26, 27, 28, 29, 30, 31, 32, 34
         ])
+        .unwrap()
     );
 
-    assert!(
+    assert_ne!(
         hash_to_g2::<Bn256>(&[
             1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
             25, 26, 27, 28, 29, 30, 31, 32
-        ]) != hash_to_g2::<Bn256>(&[
+        ])
+        .unwrap(),
+        hash_to_g2::<Bn256>(&[
             1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
             25, 26, 27, 28, 29, 30, 31, 33
         ])
+        .unwrap()
     );
+
+    // A digest that is too short is an error rather than a panic
+    assert!(hash_to_g2::<Bn256>(&[1, 2, 3]).is_err());
 }
 
 /// Computes a random linear combination over v1/v2.
@@ -127,25 +139,38 @@ fn test_hash_to_g2() {
 fn merge_pairs<E: Engine, G: CurveAffine<Engine = E, Scalar = E::Fr>>(
     v1: &[G],
     v2: &[G],
-) -> (G, G) {
+) -> Result<(G, G), CeremonyError> {
     use self::rand::thread_rng;
 
-    assert_eq!(v1.len(), v2.len());
+    if v1.len() != v2.len() {
+        return Err(CeremonyError::SizeMismatch {
+            expected: v1.len(),
+            actual: v2.len(),
+        });
+    }
     let rng = &mut thread_rng();
 
     let randomness: Vec<<G::Scalar as PrimeField>::Repr> = (0..v1.len())
         .map(|_| G::Scalar::rand(rng).into_repr())
         .collect();
 
-    let s = dense_multiexp(&v1, &randomness[..]).into_affine();
-    let sx = dense_multiexp(&v2, &randomness[..]).into_affine();
+    let s = dense_multiexp(&v1, &randomness[..])?.into_affine();
+    let sx = dense_multiexp(&v2, &randomness[..])?.into_affine();
 
-    (s, sx)
+    Ok((s, sx))
 }
 
 /// Construct a single pair (s, s^x) for a vector of
-/// the form [1, x, x^2, x^3, ...].
-pub fn power_pairs<E: Engine, G: CurveAffine<Engine = E, Scalar = E::Fr>>(v: &[G]) -> (G, G) {
+/// the form [1, x, x^2, x^3, ...]. Fails on an empty vector.
+pub fn power_pairs<E: Engine, G: CurveAffine<Engine = E, Scalar = E::Fr>>(
+    v: &[G],
+) -> Result<(G, G), CeremonyError> {
+    if v.is_empty() {
+        return Err(CeremonyError::InvalidParameters(
+            "there are no powers to pair".to_string(),
+        ));
+    }
+
     merge_pairs::<E, _>(&v[0..(v.len() - 1)], &v[1..])
 }
 
@@ -154,6 +179,16 @@ pub fn blank_hash() -> GenericArray<u8, U64> {
     Blake2b512::digest("").into()
 }
 
+/// Removes a scratch file when dropped, so that it is gone whichever way the work on it
+/// ends. Declare it before the file is opened, so that the file is closed first.
+pub(crate) struct ScratchFile<'a>(pub(crate) &'a Path);
+
+impl Drop for ScratchFile<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.0);
+    }
+}
+
 /// Checks if pairs have the same ratio.
 /// Under the hood uses pairing to check
 /// x1/x2 = y1/y2 => x1*y2 = x2*y1
@@ -180,25 +215,28 @@ pub fn compute_g2_s<E: Engine>(
     g1_s: &E::G1Affine,
     g1_s_x: &E::G1Affine,
     personalization: u8,
-) -> E::G2Affine {
+) -> Result<E::G2Affine, CeremonyError> {
     let mut h = Blake2b512::default();
     Digest::update(&mut h, &[personalization]);
     Digest::update(&mut h, digest);
     Digest::update(&mut h, g1_s.into_uncompressed().as_ref());
     Digest::update(&mut h, g1_s_x.into_uncompressed().as_ref());
 
-    hash_to_g2::<E>(h.finalize().as_ref()).into_affine()
+    Ok(hash_to_g2::<E>(h.finalize().as_ref())?.into_affine())
 }
 
-/// Perform multi-exponentiation. The caller is responsible for ensuring that
-/// the number of bases is the same as the number of exponents.
+/// Perform multi-exponentiation. Fails unless the number of bases is the same as
+/// the number of exponents.
 #[allow(dead_code)]
 pub fn dense_multiexp<G: CurveAffine>(
     bases: &[G],
     exponents: &[<G::Scalar as PrimeField>::Repr],
-) -> <G as CurveAffine>::Projective {
+) -> Result<<G as CurveAffine>::Projective, CeremonyError> {
     if exponents.len() != bases.len() {
-        panic!("invalid length")
+        return Err(CeremonyError::SizeMismatch {
+            expected: bases.len(),
+            actual: exponents.len(),
+        });
     }
     let c = if exponents.len() < 32 {
         3u32
@@ -206,7 +244,7 @@ pub fn dense_multiexp<G: CurveAffine>(
         (f64::from(exponents.len() as u32)).ln().ceil() as u32
     };
 
-    dense_multiexp_inner(bases, exponents, 0, c, true)
+    Ok(dense_multiexp_inner(bases, exponents, 0, c, true))
 }
 
 fn dense_multiexp_inner<G: CurveAffine>(
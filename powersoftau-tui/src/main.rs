@@ -10,15 +10,122 @@ use ratatui::{
     widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
 };
+use bellman::pairing::bls12_381::Bls12;
+use bellman::pairing::bn256::Bn256;
+use bellman::pairing::Engine;
+use memmap::MmapOptions;
+use powersoftau_core::batched_accumulator::BatchedAccumulator;
+use powersoftau_core::header::{body_offset, FileHeader};
+use powersoftau_core::parameters::ElementType;
+use powersoftau_core::progress::{format_duration, Phase, ProgressSink};
+use powersoftau_core::{CurveKind, RuntimeCeremonyParameters};
+use std::fs::OpenOptions;
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Latest progress reported by a ceremony operation running in the background
+#[derive(Default)]
+struct ProgressState {
+    phase: Option<Phase>,
+    element_type: Option<ElementType>,
+    done: u64,
+    total: u64,
+    started: Option<Instant>,
+}
+
+impl ProgressState {
+    fn update(&mut self, phase: Phase, element_type: Option<ElementType>, done: u64, total: u64) {
+        if self.phase != Some(phase) {
+            self.phase = Some(phase);
+            self.started = Some(Instant::now());
+        }
+        self.element_type = element_type;
+        self.done = done;
+        self.total = total;
+    }
+
+    fn percent(&self) -> u16 {
+        if self.total == 0 {
+            0
+        } else {
+            (100 * self.done / self.total) as u16
+        }
+    }
+
+    fn current_step(&self) -> String {
+        match (self.phase, self.element_type) {
+            (None, _) => "Idle".to_string(),
+            (Some(phase), None) => format!("{}", phase),
+            (Some(phase), Some(element_type)) => format!("{} {}", phase, element_type),
+        }
+    }
+
+    fn estimated_remaining(&self) -> String {
+        match self.started {
+            Some(started) if self.done > 0 => {
+                let remaining = (self.total - self.done) as f64 / self.done as f64;
+                format_duration(started.elapsed().mul_f64(remaining))
+            }
+            _ => "--:--:--".to_string(),
+        }
+    }
+
+    fn time_elapsed(&self) -> String {
+        match self.started {
+            Some(started) => format_duration(started.elapsed()),
+            None => "--:--:--".to_string(),
+        }
+    }
+}
+
+/// Feeds the progress gauge from the thread running an operation
+struct GaugeProgress {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl ProgressSink for GaugeProgress {
+    fn points(&mut self, phase: Phase, element_type: ElementType, done: usize, total: usize) {
+        self.state
+            .lock()
+            .unwrap()
+            .update(phase, Some(element_type), done as u64, total as u64);
+    }
+
+    fn bytes_hashed(&mut self, hashed: u64, total: u64) {
+        self.state.lock().unwrap().update(Phase::Hash, None, hashed, total);
+    }
+}
+
+/// Hashes `./challenge`, if there is one
+fn hash_challenge_file<E: Engine>(progress: &mut GaugeProgress) {
+    let challenge = match OpenOptions::new().read(true).open("challenge") {
+        Ok(challenge) => challenge,
+        Err(_) => return,
+    };
+    let offset = match FileHeader::read_from_file(&challenge) {
+        Ok(header) => body_offset(header.as_ref()),
+        Err(_) => return,
+    };
+    if let Ok(map) = unsafe { MmapOptions::new().offset(offset).map(&challenge) } {
+        BatchedAccumulator::<E>::calculate_hash(&map, progress);
+    }
+}
 
 struct App {
     log_list_state: ListState,
     log_entries: Vec<&'static str>,
+    progress: Arc<Mutex<ProgressState>>,
+    /// Curve of the ceremony configuration
+    curve: CurveKind,
+    /// Set while a hash runs in the background
+    hashing: Arc<AtomicBool>,
 }
 
 impl App {
-    fn new() -> App {
+    fn new(curve: CurveKind) -> App {
         let log_entries = vec![
             "[2024-07-28 17:30:15] INFO: Powers of Tau ceremony initialized",
             "[2024-07-28 17:30:16] INFO: Loading ceremony configuration (power=8)",
@@ -45,6 +152,9 @@ impl App {
         let mut app = App {
             log_list_state: ListState::default(),
             log_entries,
+            progress: Arc::new(Mutex::new(ProgressState::default())),
+            curve,
+            hashing: Arc::new(AtomicBool::new(false)),
         };
         
         // Start with the last (most recent) log selected
@@ -79,9 +189,33 @@ impl App {
         };
         self.log_list_state.select(Some(i));
     }
+
+    /// Hashes `./challenge` in the background, reporting to the progress gauge. Only one hash
+    /// runs at a time, since they would all report to the same gauge.
+    fn hash_challenge(&mut self) {
+        if self.hashing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let hashing = self.hashing.clone();
+        let curve = self.curve;
+        let mut progress = GaugeProgress {
+            state: self.progress.clone(),
+        };
+        thread::spawn(move || {
+            match curve {
+                CurveKind::Bls12_381 => hash_challenge_file::<Bls12>(&mut progress),
+                CurveKind::Bn254 => hash_challenge_file::<Bn256>(&mut progress),
+            }
+            hashing.store(false, Ordering::SeqCst);
+        });
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The ceremony configuration tells the curve of the challenge. It is loaded before the
+    // terminal is set up, so that an error leaves the terminal as it was.
+    let params = RuntimeCeremonyParameters::from_env_and_args(std::env::args().skip(1))?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -90,7 +224,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app instance
-    let mut app = App::new();
+    let mut app = App::new(params.curve());
 
     // Run the application
     let result = run_app(&mut terminal, &mut app);
@@ -111,9 +245,15 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(),
     loop {
         terminal.draw(|f| ui(f, app))?;
 
+        // Redraw periodically so that progress of background operations shows up
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+
         if let Event::Key(key) = event::read()? {
             match key.code {
                 KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('h') => app.hash_challenge(),
                 KeyCode::Down => app.next_log(),
                 KeyCode::Up => app.previous_log(),
                 _ => {}
@@ -145,6 +285,7 @@ fn ui(f: &mut Frame, app: &mut App) {
         Controls:\n\
         • Press 'q' to quit\n\
         • Use ↑↓ arrow keys to navigate logs\n\
+        • Press 'h' to hash ./challenge\n\
         • Press Enter to select options (future)\n\n\
         Status: Ready to begin ceremony";
     
@@ -163,13 +304,14 @@ fn ui(f: &mut Frame, app: &mut App) {
         ])
         .split(chunks[1]);
 
+    let progress = app.progress.lock().unwrap();
+
     // Status bar on the left
-    let progress = 65; // Example progress value
     let status_bar = Gauge::default()
         .block(Block::default().title("Progress").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Green))
-        .percent(progress)
-        .label(format!("{}%", progress));
+        .percent(progress.percent())
+        .label(format!("{}%", progress.percent()));
     
     f.render_widget(status_bar, status_chunks[0]);
 
@@ -179,13 +321,17 @@ fn ui(f: &mut Frame, app: &mut App) {
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::Green));
     
-    let status_content = "Running\n\n\
-        Current Step: Computing participant contribution\n\
+    let status_content = format!("Running\n\n\
+        Current Step: {}\n\
         Participants: 3/10\n\
-        Time Elapsed: 00:15:42\n\
-        Estimated Remaining: 00:45:18\n\n\
+        Time Elapsed: {}\n\
+        Estimated Remaining: {}\n\n\
         Last Action: Verified contribution from participant #2\n\
-        Next: Waiting for participant #4";
+        Next: Waiting for participant #4",
+        progress.current_step(),
+        progress.time_elapsed(),
+        progress.estimated_remaining());
+    drop(progress);
     
     let status_paragraph = Paragraph::new(status_content)
         .block(status_block)